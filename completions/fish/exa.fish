@@ -9,7 +9,20 @@ complete -c exa -s 'G' -l 'grid'         -d "Display entries in a grid"
 complete -c exa -s 'x' -l 'across'       -d "Sort the grid across, rather than downwards"
 complete -c exa -s 'R' -l 'recurse'      -d "Recurse into directories"
 complete -c exa -s 'T' -l 'tree'         -d "Recurse into directories as a tree"
+complete -c exa        -l 'tree-style'   -d "How to draw the tree" -x -a "
+    unicode\t'Unicode lines (default)'
+    rounded\t'Unicode lines with rounded corners'
+    ascii\t'Lines made of ASCII characters'
+    indent\t'Indentation without any lines'
+    heavy\t'Thick Unicode lines'
+"
+complete -c exa        -l 'tree-colors' \
+                       -l 'tree-colours' -d "Colour each level of the tree differently"
+complete -c exa        -l 'tree-compact' -d "Merge directories with only one directory inside into one row"
 complete -c exa -s 'F' -l 'classify'     -d "Display type indicator by file names"
+complete -c exa        -l 'json'         -d "Display each entry's metadata as a JSON object"
+complete -c exa -s '0' -l 'print0'       -d "Display raw paths separated by NUL characters"
+complete -c exa        -l 'summary'      -d "Count the files, directories, and sizes after each listing"
 complete -c exa        -l 'color' \
                        -l 'colour'       -d "When to use terminal colours" -x -a "
     always\t'Always use colour'
@@ -17,16 +30,39 @@ complete -c exa        -l 'color' \
     never\t'Never use colour'
 "
 complete -c exa        -l 'color-scale' \
-                       -l 'colour-scale' -d "Highlight levels of file sizes distinctly"
+                       -l 'colour-scale' -d "Colour fields on a scale" -f -a "
+    all\t'Colour both sizes and ages on a scale'
+    size\t'Colour file sizes on a scale'
+    age\t'Shade timestamps by age'
+"
+complete -c exa        -l 'color-scale-mode' \
+                       -l 'colour-scale-mode' -d "How to colour sizes on a scale" -x -a "
+    fixed\t'Colour each order of magnitude the same'
+    gradient\t'Colour sizes relative to the listing'
+"
+complete -c exa        -l 'theme'        -d "Which colour theme to use" -r -a "
+    vscode\t'Dark palette based on VS Code (default)'
+    exa\t'Original exa palette'
+"
 complete -c exa        -l 'icons'        -d "Display icons"
 complete -c exa        -l 'no-icons'     -d "Don't display icons"
+complete -c exa        -l 'hyperlink'    -d "Display entries as hyperlinks"
+complete -c exa        -l 'quoting-style' -d "How to quote file names" -x -a "
+    literal\t'Print names as they are'
+    shell\t'Quote names when a shell would need them to be'
+    shell-escape\t'Quote names, escaping control characters'
+    c\t'Quote and escape names like C strings'
+    escape\t'Escape names like C strings, without quotes'
+"
 
 # Filtering and sorting options
 complete -c exa -l 'group-directories-first' -d "Sort directories before other files"
-complete -c exa -l 'git-ignore'           -d "Ignore files mentioned in '.gitignore'"
+complete -c exa -l 'git-ignore'           -d "Ignore files mentioned in '.gitignore' or '.ignore'"
 complete -c exa -s 'a' -l 'all'       -d "Show hidden and 'dot' files"
 complete -c exa -s 'd' -l 'list-dirs' -d "List directories like regular files"
 complete -c exa -s 'L' -l 'level'     -d "Limit the depth of recursion" -x -a "1 2 3 4 5 6 7 8 9"
+complete -c exa        -l 'max-entries' -d "List at most this many entries of each directory" -x
+complete -c exa        -l 'prune'     -d "List these directories without recursing into them" -x
 complete -c exa -s 'r' -l 'reverse'   -d "Reverse the sort order"
 complete -c exa -s 's' -l 'sort'      -d "Which field to sort by" -x -a "
     accessed\t'Sort by file accessed time'
@@ -54,6 +90,31 @@ complete -c exa -s 's' -l 'sort'      -d "Which field to sort by" -x -a "
 
 complete -c exa -s 'I' -l 'ignore-glob' -d "Ignore files that match these glob patterns" -r
 complete -c exa -s 'D' -l 'only-dirs'   -d "List only directories"
+complete -c exa -l 'match'        -d "Show only files that match these glob patterns" -r
+complete -c exa -l 'ignore-regex' -d "Ignore files that match this regular expression" -x
+complete -c exa -l 'match-regex'  -d "Show only files that match this regular expression" -x
+complete -c exa -l 'min-size'    -d "List only files at least this big" -x
+complete -c exa -l 'max-size'    -d "List only files at most this big" -x
+complete -c exa -l 'newer'       -d "List only files newer than a date or a duration ago" -x
+complete -c exa -l 'older-than'  -d "List only files older than a date or a duration ago" -x
+complete -c exa -l 'filter-time' -d "Which timestamp --newer and --older-than use" -x -a "
+    modified\t'Use the modified timestamp'
+    changed\t'Use the changed timestamp'
+    accessed\t'Use the accessed timestamp'
+    created\t'Use the created timestamp'
+"
+complete -c exa -l 'type'        -d "List only these types of file" -x -a "
+    f\t'Regular files'
+    d\t'Directories'
+    l\t'Symlinks'
+    p\t'Named pipes'
+    s\t'Sockets'
+    b\t'Block devices'
+    c\t'Character devices'
+"
+complete -c exa -l 'owner'       -d "List only files with this owner and/or group" -x -a "(__fish_complete_users)"
+complete -c exa -l 'perm'        -d "List only files with these permission bits" -x
+complete -c exa -l 'prune-empty' -d "Hide directories with nothing left in them"
 
 # Long view options
 complete -c exa -s 'b' -l 'binary'   -d "List file sizes with binary prefixes"
@@ -79,13 +140,22 @@ complete -c exa        -l 'time-style'    -d "How to format timestamps" -x -a "
     iso\t'Display brief ISO timestamps'
     long-iso\t'Display longer ISO timestaps, up to the minute'
     full-iso\t'Display full ISO timestamps, up to the nanosecond'
+    relative\t'Display how long ago each timestamp was'
+    +FORMAT\t'Use a custom strftime-style format'
 "
+complete -c exa        -l 'total-size'     -d "Show the total size of each directory's contents"
+complete -c exa        -l 'disk-usage'     -d "Add up the space used on disk for --total-size"
 complete -c exa        -l 'no-permissions' -d "Suppress the permissions field"
 complete -c exa        -l 'octal-permissions' -d "List each file's permission in octal format"
 complete -c exa        -l 'no-filesize'    -d "Suppress the filesize field"
 complete -c exa        -l 'no-user'        -d "Suppress the user field"
 complete -c exa        -l 'no-time'        -d "Suppress the time field"
+complete -c exa        -l 'align'          -d "Override column alignments" -x
+complete -c exa        -l 'max-name-width' -d "Shorten names wider than this many characters" -x
+complete -c exa        -l 'columns'        -d "Which columns to list, in order" -x -a "(__fish_append , path inode octal perm links size blocks user group mtime ctime btime atime git name)"
+complete -c exa        -l 'magic'          -d "Read the start of each file to work out its type"
 
 # Optional extras
 complete -c exa -l 'git' -d "List each file's Git status, if tracked"
+complete -c exa -l 'git-untracked-depth' -d "Limit how deep to look inside untracked directories" -x
 complete -c exa -s '@' -l 'extended' -d "List each file's extended attributes and sizes"
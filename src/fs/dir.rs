@@ -1,9 +1,10 @@
-use crate::fs::feature::git::GitCache;
-use crate::fs::fields::GitStatus;
+use crate::fs::ignore::{IgnoreCache, DirIgnores};
 use std::io;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::iter::Zip;
 use std::slice::Iter as SliceIter;
+use std::sync::Arc;
 
 use log::*;
 
@@ -21,6 +22,11 @@ pub struct Dir {
     /// A vector of the files that have been read from this directory.
     contents: Vec<PathBuf>,
 
+    /// The metadata of each file in `contents`, in the same order, for the
+    /// ones that got looked up when the directory was read ahead of being
+    /// listed. The rest get statted as they’re listed.
+    metadata: Vec<Option<fs::Metadata>>,
+
     /// The path that was read.
     pub path: PathBuf,
 }
@@ -40,21 +46,35 @@ impl Dir {
 
         let contents = fs::read_dir(&path)?
                           .map(|result| result.map(|entry| entry.path()))
-                          .collect::<Result<_, _>>()?;
+                          .collect::<Result<Vec<_>, _>>()?;
+
+        let metadata = vec![None; contents.len()];
+        Ok(Self { contents, metadata, path })
+    }
 
-        Ok(Self { contents, path })
+    /// Look up the metadata of the files in this directory now, rather than
+    /// as each one gets listed, so it can be done on another thread. Hidden
+    /// files are skipped unless they’re going to be shown, and any file that
+    /// fails gets statted again when it’s listed, so the error gets shown.
+    pub fn stat_contents(&mut self, dots: DotFilter) {
+        for (path, metadata) in self.contents.iter().zip(&mut self.metadata) {
+            if dots.shows_dotfiles() || ! File::filename(path).starts_with('.') {
+                debug!("Statting file {:?}", path);
+                *metadata = fs::symlink_metadata(path).ok();
+            }
+        }
     }
 
     /// Produce an iterator of IO results of trying to read all the files in
-    /// this directory.
-    pub fn files<'dir, 'ig>(&'dir self, dots: DotFilter, git: Option<&'ig GitCache>, git_ignoring: bool) -> Files<'dir, 'ig> {
+    /// this directory, skipping any that are ignored if an ignore cache is
+    /// given.
+    pub fn files(&self, dots: DotFilter, ignore: Option<&IgnoreCache>) -> Files<'_> {
         Files {
-            inner:     self.contents.iter(),
+            inner:     self.contents.iter().zip(self.metadata.iter()),
             dir:       self,
             dotfiles:  dots.shows_dotfiles(),
             dots:      dots.dots(),
-            git,
-            git_ignoring,
+            ignores:   ignore.map(|i| i.for_dir(&self.path)),
         }
     }
 
@@ -71,10 +91,11 @@ impl Dir {
 
 
 /// Iterator over reading the contents of a directory as `File` objects.
-pub struct Files<'dir, 'ig> {
+pub struct Files<'dir> {
 
-    /// The internal iterator over the paths that have been read already.
-    inner: SliceIter<'dir, PathBuf>,
+    /// The internal iterator over the paths that have been read already,
+    /// along with their metadata if it’s been looked up.
+    inner: Zip<SliceIter<'dir, PathBuf>, SliceIter<'dir, Option<fs::Metadata>>>,
 
     /// The directory that begat those paths.
     dir: &'dir Dir,
@@ -86,12 +107,12 @@ pub struct Files<'dir, 'ig> {
     /// any files have been listed.
     dots: DotsNext,
 
-    git: Option<&'ig GitCache>,
-
-    git_ignoring: bool,
+    /// The ignore rules for the files in the directory, if ignored files
+    /// should be skipped.
+    ignores: Option<Arc<DirIgnores>>,
 }
 
-impl<'dir, 'ig> Files<'dir, 'ig> {
+impl<'dir> Files<'dir> {
     fn parent(&self) -> PathBuf {
         // We can’t use `Path#parent` here because all it does is remove the
         // last path component, which is no good for us if the path is
@@ -105,7 +126,7 @@ impl<'dir, 'ig> Files<'dir, 'ig> {
     /// varies depending on the dotfile visibility flag)
     fn next_visible_file(&mut self) -> Option<Result<File<'dir>, (PathBuf, io::Error)>> {
         loop {
-            if let Some(path) = self.inner.next() {
+            if let Some((path, metadata)) = self.inner.next() {
                 let filename = File::filename(path);
                 if ! self.dotfiles && filename.starts_with('.') {
                     continue;
@@ -118,15 +139,23 @@ impl<'dir, 'ig> Files<'dir, 'ig> {
                     continue;
                 }
 
-                if self.git_ignoring {
-                    let git_status = self.git.map(|g| g.get(path, false)).unwrap_or_default();
-                    if git_status.unstaged == GitStatus::Ignored {
-                         continue;
+                if let Some(ref ignores) = self.ignores {
+                    let is_dir = || match metadata {
+                        Some(m)  => m.is_dir(),
+                        None     => fs::symlink_metadata(path).map_or(false, |m| m.is_dir()),
+                    };
+
+                    if ignores.is_ignored(&filename, is_dir) {
+                        continue;
                     }
                 }
 
-                return Some(File::from_args(path.clone(), self.dir, filename)
-                                 .map_err(|e| (path.clone(), e)))
+                let file = match metadata {
+                    Some(m)  => Ok(File::with_metadata(path.clone(), self.dir, filename, m.clone())),
+                    None     => File::from_args(path.clone(), self.dir, filename),
+                };
+
+                return Some(file.map_err(|e| (path.clone(), e)))
             }
 
             return None
@@ -148,7 +177,7 @@ enum DotsNext {
     Files,
 }
 
-impl<'dir, 'ig> Iterator for Files<'dir, 'ig> {
+impl<'dir> Iterator for Files<'dir> {
     type Item = Result<File<'dir>, (PathBuf, io::Error)>;
 
     fn next(&mut self) -> Option<Self::Item> {
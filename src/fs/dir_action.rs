@@ -1,5 +1,8 @@
 //! What to do when encountering a directory?
 
+use crate::fs::File;
+
+
 /// The action to take when trying to list a file that turns out to be a
 /// directory.
 ///
@@ -19,7 +22,7 @@
 /// into them and print out their contents. The recurse mode does this by
 /// having extra output blocks at the end, while the tree mode will show
 /// directories inline, with their contents immediately underneath.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum DirAction {
 
     /// This directory should be listed along with the regular files, instead
@@ -39,7 +42,7 @@ pub enum DirAction {
 impl DirAction {
 
     /// Gets the recurse options, if this dir action has any.
-    pub fn recurse_options(self) -> Option<RecurseOptions> {
+    pub fn recurse_options(&self) -> Option<&RecurseOptions> {
         match self {
             Self::Recurse(o)  => Some(o),
             _                 => None,
@@ -47,7 +50,7 @@ impl DirAction {
     }
 
     /// Whether to treat directories as regular files or not.
-    pub fn treat_dirs_as_files(self) -> bool {
+    pub fn treat_dirs_as_files(&self) -> bool {
         match self {
             Self::AsFile      => true,
             Self::Recurse(o)  => o.tree,
@@ -58,7 +61,7 @@ impl DirAction {
 
 
 /// The options that determine how to recurse into a directory.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct RecurseOptions {
 
     /// Whether recursion should be done as a tree or as multiple individual
@@ -68,15 +71,45 @@ pub struct RecurseOptions {
     /// The maximum number of times that recursion should descend to, if one
     /// is specified.
     pub max_depth: Option<usize>,
+
+    /// The most entries to list in each directory, if there’s a limit. The
+    /// rest get counted in a placeholder row after the ones that are.
+    pub max_entries: Option<usize>,
+
+    /// Globs of the names of directories that get listed, but never
+    /// recursed into, such as `node_modules` or `target`.
+    pub prune: Vec<glob::Pattern>,
 }
 
 impl RecurseOptions {
 
     /// Returns whether a directory of the given depth would be too deep.
-    pub fn is_too_deep(self, depth: usize) -> bool {
+    pub fn is_too_deep(&self, depth: usize) -> bool {
         match self.max_depth {
             None     => false,
             Some(d)  => d <= depth
         }
     }
+
+    /// Returns whether a directory has been pruned, meaning that it gets
+    /// listed like any other, but its contents don’t.
+    pub fn is_pruned(&self, dir: &File<'_>) -> bool {
+        self.prune.iter().any(|p| p.matches(&dir.name))
+    }
+
+    /// Returns whether to recurse into a file at the given depth, which it
+    /// only does for directories other than `.` and `..` that are neither
+    /// too deep nor pruned.
+    pub fn descends_into(&self, file: &File<'_>, depth: usize) -> bool {
+        file.is_directory() && ! file.is_all_all && ! self.is_too_deep(depth) && ! self.is_pruned(file)
+    }
+
+    /// Returns how many of a directory’s entries should be listed, given
+    /// how many it has.
+    pub fn entries_to_list(&self, count: usize) -> usize {
+        match self.max_entries {
+            None     => count,
+            Some(n)  => n.min(count),
+        }
+    }
 }
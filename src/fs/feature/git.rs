@@ -1,6 +1,7 @@
 //! Getting the Git status of files and directories.
 
-use std::ffi::OsStr;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
 #[cfg(target_family = "unix")]
 use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
@@ -22,6 +23,10 @@ pub struct GitCache {
 
     /// Paths that we’ve confirmed do not have Git repositories underneath them.
     misses: Vec<PathBuf>,
+
+    /// How many levels of untracked directories to look inside when getting
+    /// statuses, or `None` to look inside all of them.
+    untracked_depth: Option<usize>,
 }
 
 impl GitCache {
@@ -32,9 +37,16 @@ impl GitCache {
     pub fn get(&self, index: &Path, prefix_lookup: bool) -> f::Git {
         self.repos.iter()
             .find(|e| e.has_path(index))
-            .map(|repo| repo.search(index, prefix_lookup))
+            .map(|repo| repo.search(index, prefix_lookup, self.untracked_depth))
             .unwrap_or_default()
     }
+
+    /// Limits how many levels of untracked directories get looked inside.
+    /// Past the limit, everything in an untracked directory is reported as
+    /// new, along with the directory at the limit.
+    pub fn set_untracked_depth(&mut self, depth: Option<usize>) {
+        self.untracked_depth = depth;
+    }
 }
 
 use std::iter::FromIterator;
@@ -46,6 +58,7 @@ impl FromIterator<PathBuf> for GitCache {
         let mut git = Self {
             repos: Vec::with_capacity(iter.size_hint().0),
             misses: Vec::new(),
+            untracked_depth: None,
         };
 
         for path in iter {
@@ -82,8 +95,8 @@ impl FromIterator<PathBuf> for GitCache {
 /// A **Git repository** is one we’ve discovered somewhere on the filesystem.
 pub struct GitRepo {
 
-    /// The queryable contents of the repository: either a `git2` repo, or the
-    /// cached results from when we queried it last time.
+    /// The `git2` repository, along with the statuses that have been read
+    /// from it so far.
     contents: Mutex<GitContents>,
 
     /// The working directory of this repository.
@@ -101,22 +114,14 @@ pub struct GitRepo {
 }
 
 /// A repository’s queried state.
-enum GitContents {
+struct GitContents {
 
     /// All the interesting Git stuff goes through this.
-    Before {
-        repo: git2::Repository,
-    },
-
-    /// Temporary value used in `repo_to_statuses` so we can move the
-    /// repository out of the `Before` variant.
-    Processing,
-
-    /// The data we’ve extracted from the repository, but only after we’ve
-    /// actually done so.
-    After {
-        statuses: Git,
-    },
+    repo: git2::Repository,
+
+    /// The statuses that have been read so far, for the parts of the
+    /// working tree that have been scanned.
+    statuses: StatusNode,
 }
 
 impl GitRepo {
@@ -124,28 +129,30 @@ impl GitRepo {
     /// Searches through this repository for a path (to a file or directory,
     /// depending on the prefix-lookup flag) and returns its Git status.
     ///
-    /// Actually querying the `git2` repository for the mapping of paths to
-    /// Git statuses is only done once, and gets cached so we don’t need to
-    /// re-query the entire repository the times after that.
-    ///
-    /// The temporary `Processing` enum variant is used after the `git2`
-    /// repository is moved out, but before the results have been moved in!
-    /// See <https://stackoverflow.com/q/45985827/3484614>
-    fn search(&self, index: &Path, prefix_lookup: bool) -> f::Git {
-        use std::mem::replace;
+    /// Rather than getting the status of the entire working tree, this only
+    /// asks `git2` about the directory that the file is in, or everything
+    /// under the directory for a prefix lookup. The results get cached, so
+    /// the other files in the same directory don’t need another query.
+    fn search(&self, index: &Path, prefix_lookup: bool, untracked_depth: Option<usize>) -> f::Git {
+        let path = reorient(index);
+        let relative = match path.strip_prefix(&self.workdir) {
+            Ok(r)   => r,
+            Err(_)  => return f::Git::default(),
+        };
+
+        let scope = if prefix_lookup { Some(relative) } else { relative.parent() };
 
         let mut contents = self.contents.lock().unwrap();
-        if let GitContents::After { ref statuses } = *contents {
-            debug!("Git repo {:?} has been found in cache", &self.workdir);
-            return statuses.status(index, prefix_lookup);
+        if let Some(scope) = scope {
+            if ! contents.statuses.has_scanned(scope) {
+                contents.scan(scope, &self.workdir, untracked_depth);
+            }
         }
 
-        debug!("Querying Git repo {:?} for the first time", &self.workdir);
-        let repo = replace(&mut *contents, GitContents::Processing).inner_repo();
-        let statuses = repo_to_statuses(&repo, &self.workdir);
-        let result = statuses.status(index, prefix_lookup);
-        let _processing = replace(&mut *contents, GitContents::After { statuses });
-        result
+        let s = contents.statuses.status(relative, prefix_lookup);
+        let staged = index_status(s);
+        let unstaged = working_tree_status(s);
+        f::Git { staged, unstaged }
     }
 
     /// Whether this repository has the given working directory.
@@ -172,7 +179,7 @@ impl GitRepo {
 
         if let Some(workdir) = repo.workdir() {
             let workdir = workdir.to_path_buf();
-            let contents = Mutex::new(GitContents::Before { repo });
+            let contents = Mutex::new(GitContents { repo, statuses: StatusNode::default() });
             Ok(Self { contents, workdir, original_path: path, extra_paths: Vec::new() })
         }
         else {
@@ -184,110 +191,189 @@ impl GitRepo {
 
 
 impl GitContents {
-    /// Assumes that the repository hasn’t been queried, and extracts it
-    /// (consuming the value) if it has. This is needed because the entire
-    /// enum variant gets replaced when a repo is queried (see above).
-    fn inner_repo(self) -> git2::Repository {
-        if let Self::Before { repo } = self {
-            repo
-        }
-        else {
-            unreachable!("Tried to extract a non-Repository")
+
+    /// Gets the statuses of everything under the given directory, relative
+    /// to the working directory, and adds them to the tree.
+    ///
+    /// When there’s a depth limit, the untracked directories get found
+    /// first, as Git reports them as a whole unless it’s told to look
+    /// inside them. Then the files inside them are trimmed to the limit,
+    /// so anything further down is covered by the directory at the limit.
+    fn scan(&mut self, scope: &Path, workdir: &Path, untracked_depth: Option<usize>) {
+        let limit = if let Some(limit) = untracked_depth { limit } else {
+            for (path, status) in self.query(scope, workdir, true) {
+                self.statuses.insert(&path, status);
+            }
+
+            self.statuses.mark_scanned(scope);
+            return;
+        };
+
+        let untracked_dirs = self.query(scope, workdir, false).into_iter()
+                                 .filter(|(path, status)| *status == git2::Status::WT_NEW && workdir.join(path).is_dir())
+                                 .map(|(path, _)| path)
+                                 .collect::<Vec<_>>();
+
+        for (path, status) in self.query(scope, workdir, true) {
+            match untracked_dirs.iter().find(|dir| path.starts_with(dir)) {
+                Some(dir) => {
+                    let inside = path.strip_prefix(dir).unwrap_or(&path);
+                    if inside.components().count() > limit {
+                        let trimmed = dir.join(inside.components().take(limit).collect::<PathBuf>());
+                        self.statuses.insert(&trimmed, git2::Status::WT_NEW);
+                    }
+                    else {
+                        self.statuses.insert(&path, status);
+                    }
+                }
+                None => {
+                    self.statuses.insert(&path, status);
+                }
+            }
         }
+
+        self.statuses.mark_scanned(scope);
     }
-}
 
-/// Iterates through a repository’s statuses, consuming it and returning the
-/// mapping of files to their Git status.
-/// We will have already used the working directory at this point, so it gets
-/// passed in rather than deriving it from the `Repository` again.
-fn repo_to_statuses(repo: &git2::Repository, workdir: &Path) -> Git {
-    let mut statuses = Vec::new();
-
-    info!("Getting Git statuses for repo with workdir {:?}", workdir);
-    match repo.statuses(None) {
-        Ok(es) => {
-            for e in es.iter() {
-                #[cfg(target_family = "unix")]
-                let path = workdir.join(Path::new(OsStr::from_bytes(e.path_bytes())));
-                // TODO: handle non Unix systems better:
-                // https://github.com/ogham/exa/issues/698
-                #[cfg(not(target_family = "unix"))]
-                let path = workdir.join(Path::new(e.path().unwrap()));
-                let elem = (path, e.status());
-                statuses.push(elem);
-            }
+    /// Asks `git2` for the statuses of everything under the given directory.
+    fn query(&self, dir: &Path, workdir: &Path, recurse_untracked: bool) -> Vec<(PathBuf, git2::Status)> {
+        info!("Getting Git statuses under {:?} in repo with workdir {:?}", dir, workdir);
+
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true)
+               .include_ignored(true)
+               .recurse_untracked_dirs(recurse_untracked);
+
+        // The path is given literally, so that any wildcards in file names
+        // don’t get treated as patterns.
+        if ! dir.as_os_str().is_empty() {
+            options.pathspec(dir).disable_pathspec_match(true);
         }
-        Err(e) => {
-            error!("Error looking up Git statuses: {:?}", e);
+
+        let mut statuses = Vec::new();
+        match self.repo.statuses(Some(&mut options)) {
+            Ok(es) => {
+                for e in es.iter() {
+                    #[cfg(target_family = "unix")]
+                    let path = PathBuf::from(OsStr::from_bytes(e.path_bytes()));
+                    // TODO: handle non Unix systems better:
+                    // https://github.com/ogham/exa/issues/698
+                    #[cfg(not(target_family = "unix"))]
+                    let path = PathBuf::from(e.path().unwrap());
+                    statuses.push((path, e.status()));
+                }
+            }
+            Err(e) => {
+                error!("Error looking up Git statuses: {:?}", e);
+            }
         }
-    }
 
-    Git { statuses }
+        statuses
+    }
 }
 
-// The `repo.statuses` call above takes a long time. exa debug output:
-//
-//   20.311276  INFO:exa::fs::feature::git: Getting Git statuses for repo with workdir "/vagrant/"
-//   20.799610  DEBUG:exa::output::table: Getting Git status for file "./Cargo.toml"
-//
-// Even inserting another logging line immediately afterwards doesn’t make it
-// look any faster.
+// Getting the statuses of the entire working tree takes a long time in a
+// large repository, which is why the queries are limited to the
+// directories being listed. Listing the root of a repository with `--tree`
+// still ends up querying everything, but only once.
+
+
+/// A node in the tree of Git statuses, which has one node for each path
+/// component. Looking up a path takes one step for each component in it,
+/// rather than checking every status in the repository.
+struct StatusNode {
+
+    /// The status of the file or directory at this path, if Git reported
+    /// one for it. Git reports ignored and untracked directories as a
+    /// whole, so their status applies to everything inside them.
+    status: git2::Status,
 
+    /// The statuses of everything underneath this path, combined, apart
+    /// from ignored files, as a directory isn’t ignored just because it has
+    /// an ignored file in it.
+    contents: git2::Status,
 
-/// Container of Git statuses for all the files in this folder’s Git repository.
-struct Git {
-    statuses: Vec<(PathBuf, git2::Status)>,
+    /// Whether everything underneath this path has been scanned already.
+    scanned: bool,
+
+    /// The nodes for the paths underneath this one.
+    children: HashMap<OsString, StatusNode>,
+}
+
+impl Default for StatusNode {
+    fn default() -> Self {
+        Self {
+            status:    git2::Status::empty(),
+            contents:  git2::Status::empty(),
+            scanned:   false,
+            children:  HashMap::new(),
+        }
+    }
 }
 
-impl Git {
+impl StatusNode {
 
-    /// Get either the file or directory status for the given path.
-    /// “Prefix lookup” means that it should report an aggregate status of all
-    /// paths starting with the given prefix (in other words, a directory).
-    fn status(&self, index: &Path, prefix_lookup: bool) -> f::Git {
-        if prefix_lookup { self.dir_status(index) }
-                    else { self.file_status(index) }
+    /// Adds a status for the given path, relative to the working directory.
+    fn insert(&mut self, path: &Path, status: git2::Status) {
+        let mut node = self;
+        for component in path.components() {
+            node.contents |= status - git2::Status::IGNORED;
+            node = node.children.entry(component.as_os_str().to_os_string()).or_default();
+        }
+
+        node.status |= status;
     }
 
-    /// Get the user-facing status of a file.
-    /// We check the statuses directly applying to a file, and for the ignored
-    /// status we check if any of its parents directories is ignored by git.
-    fn file_status(&self, file: &Path) -> f::Git {
-        let path = reorient(file);
+    /// Marks everything under the given path as having been scanned.
+    fn mark_scanned(&mut self, path: &Path) {
+        let mut node = self;
+        for component in path.components() {
+            node = node.children.entry(component.as_os_str().to_os_string()).or_default();
+        }
 
-        let s = self.statuses.iter()
-            .filter(|p| if p.1 == git2::Status::IGNORED {
-                path.starts_with(&p.0)
-            } else {
-                p.0 == path
-            })
-            .fold(git2::Status::empty(), |a, b| a | b.1);
+        node.scanned = true;
+    }
 
-        let staged = index_status(s);
-        let unstaged = working_tree_status(s);
-        f::Git { staged, unstaged }
+    /// Whether the statuses under the given path are known already, either
+    /// because it or a directory above it has been scanned, or because one
+    /// of them has a status that covers everything inside it.
+    fn has_scanned(&self, path: &Path) -> bool {
+        let mut node = self;
+        for component in path.components() {
+            if node.scanned || ! node.status.is_empty() {
+                return true;
+            }
+
+            match node.children.get(component.as_os_str()) {
+                Some(child)  => node = child,
+                None         => return false,
+            }
+        }
+
+        node.scanned || ! node.status.is_empty()
     }
 
-    /// Get the combined, user-facing status of a directory.
-    /// Statuses are aggregating (for example, a directory is considered
-    /// modified if any file under it has the status modified), except for
-    /// ignored status which applies to files under (for example, a directory
-    /// is considered ignored if one of its parent directories is ignored).
-    fn dir_status(&self, dir: &Path) -> f::Git {
-        let path = reorient(dir);
-
-        let s = self.statuses.iter()
-            .filter(|p| if p.1 == git2::Status::IGNORED {
-                path.starts_with(&p.0)
-            } else {
-                p.0.starts_with(&path)
-            })
-            .fold(git2::Status::empty(), |a, b| a | b.1);
+    /// Gets the status of the given path, including the statuses of the
+    /// directories above it that apply to everything inside them. For a
+    /// prefix lookup, this also combines the statuses of everything under
+    /// the path, so a directory is modified if any file inside it is.
+    fn status(&self, path: &Path, prefix_lookup: bool) -> git2::Status {
+        let mut status = git2::Status::empty();
+        let mut node = self;
+        for component in path.components() {
+            status |= node.status;
+            match node.children.get(component.as_os_str()) {
+                Some(child)  => node = child,
+                None         => return status,
+            }
+        }
 
-        let staged = index_status(s);
-        let unstaged = working_tree_status(s);
-        f::Git { staged, unstaged }
+        status |= node.status;
+        if prefix_lookup {
+            status |= node.contents;
+        }
+
+        status
     }
 }
 
@@ -343,3 +429,103 @@ fn index_status(status: git2::Status) -> f::GitStatus {
         _                                                => f::GitStatus::NotModified,
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::temp_tree::TempTree;
+
+    fn tree(statuses: &[(&str, git2::Status)]) -> StatusNode {
+        let mut tree = StatusNode::default();
+        for (path, status) in statuses {
+            tree.insert(Path::new(path), *status);
+        }
+        tree
+    }
+
+    #[test]
+    fn file_status() {
+        let tree = tree(&[ ("src/main.rs", git2::Status::WT_MODIFIED) ]);
+        assert_eq!(tree.status(Path::new("src/main.rs"), false), git2::Status::WT_MODIFIED);
+        assert_eq!(tree.status(Path::new("src/lib.rs"), false), git2::Status::empty());
+    }
+
+    #[test]
+    fn directory_contents() {
+        let tree = tree(&[ ("src/fs/dir.rs", git2::Status::INDEX_NEW) ]);
+        assert_eq!(tree.status(Path::new("src"), true), git2::Status::INDEX_NEW);
+        assert_eq!(tree.status(Path::new("src"), false), git2::Status::empty());
+    }
+
+    #[test]
+    fn ignored_directories_cover_their_contents() {
+        let tree = tree(&[ ("target/", git2::Status::IGNORED), ("notes.txt", git2::Status::IGNORED) ]);
+        assert_eq!(tree.status(Path::new("target/debug/exa"), false), git2::Status::IGNORED);
+        assert_eq!(tree.status(Path::new(""), true), git2::Status::empty());
+        assert!(tree.has_scanned(Path::new("target/debug")));
+    }
+
+    #[test]
+    fn scanned_directories() {
+        let mut tree = tree(&[]);
+        tree.mark_scanned(Path::new("src"));
+        assert!(tree.has_scanned(Path::new("src")));
+        assert!(tree.has_scanned(Path::new("src/fs")));
+        assert!(! tree.has_scanned(Path::new("xtests")));
+    }
+
+    /// Scans a repository with a tracked directory and three levels of
+    /// untracked ones.
+    fn scan(name: &str, untracked_depth: Option<usize>) -> StatusNode {
+        let tree = TempTree::new(name);
+        tree.file("tracked/a.txt", 1)
+            .file("new/b.txt", 1)
+            .file("new/deeper/c.txt", 1)
+            .file("new/deeper/deepest/d.txt", 1);
+
+        let repo = git2::Repository::init(tree.as_path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked/a.txt")).unwrap();
+        index.write().unwrap();
+
+        let workdir = repo.workdir().unwrap().to_path_buf();
+        let mut contents = GitContents { repo, statuses: StatusNode::default() };
+        contents.scan(Path::new(""), &workdir, untracked_depth);
+        contents.statuses
+    }
+
+    #[test]
+    fn scan_everything() {
+        let tree = scan("git-scan-everything", None);
+        assert_eq!(tree.status(Path::new("tracked/a.txt"), false), git2::Status::INDEX_NEW);
+        assert_eq!(tree.status(Path::new("new/deeper/deepest/d.txt"), false), git2::Status::WT_NEW);
+        assert_eq!(tree.status(Path::new("new"), false), git2::Status::empty());
+        assert_eq!(tree.status(Path::new("new"), true), git2::Status::WT_NEW);
+    }
+
+    #[test]
+    fn scan_untracked_as_a_whole() {
+        let tree = scan("git-scan-depth-0", Some(0));
+        assert_eq!(tree.status(Path::new("tracked/a.txt"), false), git2::Status::INDEX_NEW);
+        assert_eq!(tree.status(Path::new("new"), false), git2::Status::WT_NEW);
+        assert_eq!(tree.status(Path::new("new/deeper/c.txt"), false), git2::Status::WT_NEW);
+    }
+
+    #[test]
+    fn scan_one_level_down() {
+        let tree = scan("git-scan-depth-1", Some(1));
+        assert_eq!(tree.status(Path::new("new"), false), git2::Status::empty());
+        assert_eq!(tree.status(Path::new("new/b.txt"), false), git2::Status::WT_NEW);
+        assert_eq!(tree.status(Path::new("new/deeper"), false), git2::Status::WT_NEW);
+        assert_eq!(tree.status(Path::new("new/deeper/deepest/d.txt"), false), git2::Status::WT_NEW);
+    }
+
+    #[test]
+    fn scan_two_levels_down() {
+        let tree = scan("git-scan-depth-2", Some(2));
+        assert_eq!(tree.status(Path::new("new/deeper"), false), git2::Status::empty());
+        assert_eq!(tree.status(Path::new("new/deeper/c.txt"), false), git2::Status::WT_NEW);
+        assert_eq!(tree.status(Path::new("new/deeper/deepest"), false), git2::Status::WT_NEW);
+    }
+}
@@ -0,0 +1,247 @@
+//! Working out what a file is from the first few bytes of its contents,
+//! rather than from its name, for files that don’t have an extension.
+//!
+//! This is only done with `--magic`, as it means opening and reading every
+//! regular file that gets listed, which is a lot slower than only looking
+//! at their metadata.
+
+use std::io::{self, Read};
+
+use log::*;
+
+use crate::fs::File;
+
+
+/// How many bytes to read from the start of each file. This has to be
+/// enough to get past the header of a tar archive.
+const HEADER_LENGTH: u64 = 512;
+
+
+/// The kinds of file that can be recognised by their contents.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Magic {
+
+    /// A Linux or BSD executable or library.
+    Elf,
+
+    /// A macOS executable or library, including universal ones.
+    MachO,
+
+    /// A script with a shebang line, and which interpreter it’s for.
+    Script(Interpreter),
+
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+
+    Zip,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    SevenZip,
+    Tar,
+
+    Pdf,
+}
+
+/// The interpreters that get picked out of a shebang line. Anything else
+/// is still a script, just not one of these.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Interpreter {
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+    Python,
+    Node,
+    Perl,
+    Ruby,
+    Other,
+}
+
+impl Magic {
+
+    /// Reads the start of the file and works out what it is. Only regular
+    /// files get read; anything else, or a file that can’t be opened,
+    /// returns `None`.
+    pub fn detect(file: &File<'_>) -> Option<Self> {
+        if ! file.is_file() {
+            return None;
+        }
+
+        match read_header(file) {
+            Ok(header) => {
+                Self::from_header(&header)
+            }
+            Err(e) => {
+                debug!("Error reading header of {:?}: {:#?}", file.path, e);
+                None
+            }
+        }
+    }
+
+    /// Works out what a file is from the bytes at the start of it.
+    pub fn from_header(header: &[u8]) -> Option<Self> {
+        let magic = match header {
+            [ 0x7F, b'E', b'L', b'F', .. ]                         => Self::Elf,
+            [ 0xFE, 0xED, 0xFA, 0xCE | 0xCF, .. ]                  => Self::MachO,
+            [ 0xCE | 0xCF, 0xFA, 0xED, 0xFE, .. ]                  => Self::MachO,
+            [ b'#', b'!', rest @ .. ]                              => Self::Script(Interpreter::from_shebang(rest)),
+            [ 0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, .. ] => Self::Png,
+            [ 0xFF, 0xD8, 0xFF, .. ]                               => Self::Jpeg,
+            [ b'G', b'I', b'F', b'8', b'7' | b'9', b'a', .. ]      => Self::Gif,
+            [ b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', .. ] => Self::Webp,
+            [ b'P', b'K', 0x03, 0x04, .. ] |
+            [ b'P', b'K', 0x05, 0x06, .. ]                         => Self::Zip,
+            [ 0x1F, 0x8B, .. ]                                     => Self::Gzip,
+            [ b'B', b'Z', b'h', .. ]                               => Self::Bzip2,
+            [ 0xFD, b'7', b'z', b'X', b'Z', 0x00, .. ]             => Self::Xz,
+            [ 0x28, 0xB5, 0x2F, 0xFD, .. ]                         => Self::Zstd,
+            [ b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, .. ]             => Self::SevenZip,
+            [ b'%', b'P', b'D', b'F', b'-', .. ]                   => Self::Pdf,
+
+            // Universal Mach-O binaries share their magic number with Java
+            // class files, which have a version number of at least 45 where
+            // the binaries have their (much smaller) number of architectures.
+            [ 0xCA, 0xFE, 0xBA, 0xBE, a, b, c, d, .. ] if u32::from_be_bytes([ *a, *b, *c, *d ]) < 45 => Self::MachO,
+
+            _ if header.get(257 .. 262) == Some(&b"ustar"[..])     => Self::Tar,
+            _                                                      => return None,
+        };
+
+        Some(magic)
+    }
+
+    /// The name that the file type rules use for this kind of file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Elf       => "elf",
+            Self::MachO     => "mach-o",
+            Self::Script(i) => i.name(),
+            Self::Png       => "png",
+            Self::Jpeg      => "jpeg",
+            Self::Gif       => "gif",
+            Self::Webp      => "webp",
+            Self::Zip       => "zip",
+            Self::Gzip      => "gzip",
+            Self::Bzip2     => "bzip2",
+            Self::Xz        => "xz",
+            Self::Zstd      => "zstd",
+            Self::SevenZip  => "7z",
+            Self::Tar       => "tar",
+            Self::Pdf       => "pdf",
+        }
+    }
+}
+
+impl Interpreter {
+
+    /// Picks the interpreter out of the rest of a shebang line, after the
+    /// `#!`. This looks through `env`, and ignores any version number on
+    /// the end of the program’s name, so `/usr/bin/env python3` is Python.
+    fn from_shebang(line: &[u8]) -> Self {
+        let line = line.split(|b| *b == b'\n').next().unwrap_or_default();
+        let line = String::from_utf8_lossy(line);
+        let mut words = line.split_whitespace();
+
+        let mut program = words.next().and_then(|path| path.rsplit('/').next()).unwrap_or_default();
+        if program == "env" {
+            program = words.find(|word| ! word.starts_with('-') && ! word.contains('=')).unwrap_or_default();
+        }
+
+        match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "sh" | "dash" | "ash" | "ksh" | "mksh"  => Self::Sh,
+            "bash"                                  => Self::Bash,
+            "zsh"                                   => Self::Zsh,
+            "fish"                                  => Self::Fish,
+            "python" | "pypy"                       => Self::Python,
+            "node" | "nodejs" | "deno" | "bun"      => Self::Node,
+            "perl"                                  => Self::Perl,
+            "ruby"                                  => Self::Ruby,
+            _                                       => Self::Other,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sh      => "sh",
+            Self::Bash    => "bash",
+            Self::Zsh     => "zsh",
+            Self::Fish    => "fish",
+            Self::Python  => "python",
+            Self::Node    => "node",
+            Self::Perl    => "perl",
+            Self::Ruby    => "ruby",
+            Self::Other   => "script",
+        }
+    }
+}
+
+fn read_header(file: &File<'_>) -> io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    std::fs::File::open(&file.path)?
+        .take(HEADER_LENGTH)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn elf() {
+        assert_eq!(Magic::from_header(b"\x7FELF\x02\x01\x01"), Some(Magic::Elf));
+    }
+
+    #[test]
+    fn mach_o() {
+        assert_eq!(Magic::from_header(b"\xCF\xFA\xED\xFE\x07\x00\x00\x01"), Some(Magic::MachO));
+        assert_eq!(Magic::from_header(b"\xCA\xFE\xBA\xBE\x00\x00\x00\x02"), Some(Magic::MachO));
+    }
+
+    #[test]
+    fn java_class() {
+        assert_eq!(Magic::from_header(b"\xCA\xFE\xBA\xBE\x00\x00\x00\x34"), None);
+    }
+
+    #[test]
+    fn shebangs() {
+        assert_eq!(Magic::from_header(b"#!/bin/bash\necho hi\n"), Some(Magic::Script(Interpreter::Bash)));
+        assert_eq!(Magic::from_header(b"#! /bin/sh -e\n"), Some(Magic::Script(Interpreter::Sh)));
+        assert_eq!(Magic::from_header(b"#!/usr/bin/env python3.11\n"), Some(Magic::Script(Interpreter::Python)));
+        assert_eq!(Magic::from_header(b"#!/usr/bin/env -S node --harmony\n"), Some(Magic::Script(Interpreter::Node)));
+        assert_eq!(Magic::from_header(b"#!/usr/bin/awk -f\n"), Some(Magic::Script(Interpreter::Other)));
+    }
+
+    #[test]
+    fn images() {
+        assert_eq!(Magic::from_header(b"\x89PNG\r\n\x1A\n\0\0\0\x0DIHDR"), Some(Magic::Png));
+        assert_eq!(Magic::from_header(b"GIF89a"), Some(Magic::Gif));
+        assert_eq!(Magic::from_header(b"RIFF\x24\0\0\0WEBPVP8 "), Some(Magic::Webp));
+    }
+
+    #[test]
+    fn archives() {
+        assert_eq!(Magic::from_header(b"PK\x03\x04\x14\0"), Some(Magic::Zip));
+        assert_eq!(Magic::from_header(b"\x1F\x8B\x08\0"), Some(Magic::Gzip));
+
+        let mut tar = vec![ 0; 512 ];
+        tar[257 .. 262].copy_from_slice(b"ustar");
+        assert_eq!(Magic::from_header(&tar), Some(Magic::Tar));
+    }
+
+    #[test]
+    fn pdf() {
+        assert_eq!(Magic::from_header(b"%PDF-1.7\n"), Some(Magic::Pdf));
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(Magic::from_header(b"hello, world\n"), None);
+        assert_eq!(Magic::from_header(b""), None);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod magic;
 pub mod xattr;
 
 #[cfg(feature = "git")]
@@ -29,5 +30,9 @@ pub mod git {
         pub fn get(&self, _index: &Path, _prefix_lookup: bool) -> f::Git {
             unreachable!();
         }
+
+        pub fn set_untracked_depth(&mut self, _depth: Option<usize>) {
+            // Nothing to do
+        }
     }
 }
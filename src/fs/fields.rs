@@ -15,6 +15,10 @@
 #![allow(non_camel_case_types)]
 #![allow(clippy::struct_excessive_bools)]
 
+use std::time::SystemTime;
+
+use crate::fs::File;
+
 
 /// The type of a file’s block count.
 pub type blkcnt_t = u64;
@@ -43,7 +47,7 @@ pub type uid_t = u32;
 /// regular file. (See the `filetype` module for those checks.)
 ///
 /// Its ordering is used when sorting by type.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
 pub enum Type {
     Directory,
     File,
@@ -207,6 +211,49 @@ pub struct Time {
 }
 
 
+/// The types of a file’s time fields. These three fields are standard
+/// across most (all?) operating systems.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum TimeType {
+
+    /// The file’s modified time (`st_mtime`).
+    #[default]
+    Modified,
+
+    /// The file’s changed time (`st_ctime`)
+    Changed,
+
+    /// The file’s accessed time (`st_atime`).
+    Accessed,
+
+    /// The file’s creation time (`btime` or `birthtime`).
+    Created,
+}
+
+impl TimeType {
+
+    /// Returns the text to use for a column’s heading in the columns output.
+    pub fn header(self) -> &'static str {
+        match self {
+            Self::Modified  => "Date Modified",
+            Self::Changed   => "Date Changed",
+            Self::Accessed  => "Date Accessed",
+            Self::Created   => "Date Created",
+        }
+    }
+
+    /// Returns this timestamp of the given file, if it has one.
+    pub fn of_file(self, file: &File<'_>) -> Option<SystemTime> {
+        match self {
+            Self::Modified  => file.modified_time(),
+            Self::Changed   => file.changed_time(),
+            Self::Accessed  => file.accessed_time(),
+            Self::Created   => file.created_time(),
+        }
+    }
+}
+
+
 /// A file’s status in a Git repository. Whether a file is in a repository or
 /// not is handled by the Git module, rather than having a “null” variant in
 /// this enum.
@@ -11,8 +11,8 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use log::*;
 
 use crate::fs::dir::Dir;
+use crate::fs::feature::magic::Magic;
 use crate::fs::fields as f;
-use regex::RegexSet;
 
 
 /// A **File** is a wrapper around one of Rust’s `PathBuf` values, along with
@@ -67,22 +67,43 @@ pub struct File<'dir> {
     /// directory’s children, and are in fact added specifically by exa; this
     /// means that they should be skipped when recursing.
     pub is_all_all: bool,
+
+    /// What the file’s contents say it is, going by its first few bytes.
+    ///
+    /// This is only filled in with `--magic`, as reading each file is slow,
+    /// and only in the details view, where it gets done on the thread pool
+    /// along with the rest of the file’s details. (See `feature::magic`)
+    pub magic: Option<Magic>,
+
+    /// The total size of everything inside this directory, which gets
+    /// shown instead of its own size with `--total-size`.
+    /// (See `fs::total_size`)
+    pub total_size: Option<u64>,
 }
 
 impl<'dir> File<'dir> {
     pub fn from_args<PD, FN>(path: PathBuf, parent_dir: PD, filename: FN) -> io::Result<File<'dir>>
+    where PD: Into<Option<&'dir Dir>>,
+          FN: Into<Option<String>>
+    {
+        debug!("Statting file {:?}", &path);
+        let metadata = std::fs::symlink_metadata(&path)?;
+
+        Ok(File::with_metadata(path, parent_dir, filename, metadata))
+    }
+
+    /// Create a new File object from metadata that has already been looked
+    /// up, such as when its directory was read ahead of being listed.
+    pub fn with_metadata<PD, FN>(path: PathBuf, parent_dir: PD, filename: FN, metadata: std::fs::Metadata) -> File<'dir>
     where PD: Into<Option<&'dir Dir>>,
           FN: Into<Option<String>>
     {
         let parent_dir = parent_dir.into();
         let name       = filename.into().unwrap_or_else(|| File::filename(&path));
         let ext        = File::ext(&path);
-
-        debug!("Statting file {:?}", &path);
-        let metadata   = std::fs::symlink_metadata(&path)?;
         let is_all_all = false;
 
-        Ok(File { name, ext, path, metadata, parent_dir, is_all_all })
+        File { name, ext, path, metadata, parent_dir, is_all_all, magic: None, total_size: None }
     }
 
     pub fn new_aa_current(parent_dir: &'dir Dir) -> io::Result<File<'dir>> {
@@ -94,7 +115,7 @@ impl<'dir> File<'dir> {
         let is_all_all = true;
         let parent_dir = Some(parent_dir);
 
-        Ok(File { path, parent_dir, metadata, ext, name: ".".into(), is_all_all })
+        Ok(File { path, parent_dir, metadata, ext, name: ".".into(), is_all_all, magic: None, total_size: None })
     }
 
     pub fn new_aa_parent(path: PathBuf, parent_dir: &'dir Dir) -> io::Result<File<'dir>> {
@@ -105,7 +126,7 @@ impl<'dir> File<'dir> {
         let is_all_all = true;
         let parent_dir = Some(parent_dir);
 
-        Ok(File { path, parent_dir, metadata, ext, name: "..".into(), is_all_all })
+        Ok(File { path, parent_dir, metadata, ext, name: "..".into(), is_all_all, magic: None, total_size: None })
     }
 
     /// A file’s name is derived from its string. This needs to handle directories
@@ -262,7 +283,7 @@ impl<'dir> File<'dir> {
             Ok(metadata) => {
                 let ext  = File::ext(&path);
                 let name = File::filename(&path);
-                let file = File { parent_dir: None, path, ext, metadata, name, is_all_all: false };
+                let file = File { parent_dir: None, path, ext, metadata, name, is_all_all: false, magic: None, total_size: None };
                 FileTarget::Ok(Box::new(file))
             }
             Err(e) => {
@@ -331,7 +352,7 @@ impl<'dir> File<'dir> {
     #[cfg(unix)]
     pub fn size(&self) -> f::Size {
         if self.is_directory() {
-            f::Size::None
+            self.total_size.map_or(f::Size::None, f::Size::Some)
         }
         else if self.is_char_device() || self.is_block_device() {
             let device_ids = self.metadata.rdev().to_be_bytes();
@@ -353,7 +374,7 @@ impl<'dir> File<'dir> {
     #[cfg(windows)]
     pub fn size(&self) -> f::Size {
         if self.is_directory() {
-            f::Size::None
+            self.total_size.map_or(f::Size::None, f::Size::Some)
         }
         else {
             f::Size::Some(self.metadata.len())
@@ -486,31 +507,6 @@ impl<'dir> File<'dir> {
             reparse_point:  has_bit(0x400),
         }
     }
-
-    /// Whether this file’s extension is any of the strings that get passed in.
-    ///
-    /// This will always return `false` if the file has no extension.
-    pub fn extension_is_one_of(&self, choices: &[&str]) -> bool {
-        match &self.ext {
-            Some(ext)  => choices.contains(&&ext[..]),
-            None       => false,
-        }
-    }
-    pub fn extension_matches_set(&self, set: &RegexSet) -> bool {
-        match &self.ext {
-            Some(ext)  => set.is_match(&&ext[..]),
-            None       => false,
-        }
-    }
-
-    /// Whether this file’s name, including extension, is any of the strings
-    /// that get passed in.
-    pub fn name_is_one_of(&self, choices: &[&str]) -> bool {
-        choices.contains(&&self.name[..])
-    }
-    pub fn name_matches_set(&self, set: &RegexSet) -> bool {
-        set.is_match(&&self.name[..])
-    }
 }
 
 
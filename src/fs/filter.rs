@@ -1,12 +1,19 @@
 //! Filtering and sorting the list of files before displaying them.
 
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::iter::FromIterator;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use crate::fs::DotFilter;
-use crate::fs::File;
+use crate::fs::{Dir, DotFilter, File};
+use crate::fs::dir_action::RecurseOptions;
+use crate::fs::fields as f;
+use crate::fs::ignore::IgnoreCache;
+use crate::fs::total_size::{TotalSize, Totals};
 
 
 /// The **file filter** processes a list of files before displaying them to
@@ -23,7 +30,8 @@ use crate::fs::File;
 /// The filter also governs sorting the list. After being filtered, pairs of
 /// files are compared and sorted based on the result, with the sort field
 /// performing the comparison.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct FileFilter {
 
     /// Whether directories should be listed first, and other types of file
@@ -54,25 +62,126 @@ pub struct FileFilter {
     /// [Linux History: How Dot Files Became Hidden Files](https://linux-audit.com/linux-history-how-dot-files-became-hidden-files/)
     pub dot_filter: DotFilter,
 
-    /// Glob patterns to ignore. Any file name that matches *any* of these
-    /// patterns won’t be displayed in the list.
-    pub ignore_patterns: IgnorePatterns,
+    /// Glob and regex patterns to ignore. Any file that matches *any* of
+    /// these patterns won’t be displayed in the list.
+    pub ignore_patterns: NamePatterns,
+
+    /// Glob and regex patterns to show. If there are any, only files that
+    /// match at least one of them get displayed.
+    pub match_patterns: NamePatterns,
 
     /// Whether to ignore Git-ignored patterns.
     pub git_ignore: GitIgnore,
+
+    /// Whether to add up the sizes of directories’ contents, so they can be
+    /// sorted by size along with everything else, and which size to use.
+    pub total_size: Option<TotalSize>,
+
+    /// Tests on each file’s size, times, type, owner and permissions, which
+    /// it has to pass to be listed.
+    pub predicates: Predicates,
+
+    /// Whether to hide directories that end up with nothing in them once
+    /// their contents have been filtered, when recursing.
+    pub prune_empty: bool,
 }
 
 impl FileFilter {
     /// Remove every file in the given vector that does *not* pass the
     /// filter predicate for files found inside a directory.
     pub fn filter_child_files(&self, files: &mut Vec<File<'_>>) {
-        files.retain(|f| ! self.ignore_patterns.is_ignored(&f.name));
+        files.retain(|f| {
+            let path = Path::new(&f.name);
+            ! self.ignore_patterns.matches(&f.name, path)
+                && self.match_patterns.matches_or_empty(&f.name, path)
+                && self.predicates.matches(f)
+        });
 
         if self.only_dirs {
             files.retain(File::is_directory);
         }
     }
 
+    /// Remove every file in the given vector that does *not* pass the
+    /// filter predicate for files found inside a directory that’s being
+    /// recursed into, with `--recurse` or `--tree`.
+    ///
+    /// This differs from `filter_child_files` in that directories don’t
+    /// have to pass the predicates, as there could be files inside them
+    /// that do, in the same way that `find` searches directories that it
+    /// doesn’t print. Patterns with a `/` in them get tested against each
+    /// file’s path relative to `root`, the directory being listed.
+    ///
+    /// A directory whose name doesn’t match any of the `--match` patterns
+    /// only stays if there’s something inside it that does; and if empty
+    /// directories are being pruned, any directory with nothing left inside
+    /// it after filtering gets removed too. Both of these mean reading
+    /// everything underneath it that would get listed, down to the depth
+    /// that recursion stops at, with the files here being at `depth`, and
+    /// what gets found out is kept in the listing’s `cache`.
+    pub fn filter_recursed_files(&self, files: &mut Vec<File<'_>>, root: &Path, recurse: &RecurseOptions, depth: usize, ignore: Option<&IgnoreCache>, cache: &FilterCache) {
+        files.retain(|f| {
+            let path = f.path.strip_prefix(root).unwrap_or(&f.path);
+
+            // Directories don’t have to pass the predicates, such as
+            // `--type=f` or `--min-size`, as the files inside them still
+            // might, and they’d have nowhere to go in the tree otherwise.
+            ! self.ignore_patterns.matches(&f.name, path)
+                && (f.is_directory() || (self.predicates.matches(f) && self.match_patterns.matches_or_empty(&f.name, path)))
+        });
+
+        if self.only_dirs {
+            files.retain(File::is_directory);
+        }
+
+        files.retain(|f| {
+            if ! f.is_directory() || f.is_all_all {
+                return true;
+            }
+
+            let path = f.path.strip_prefix(root).unwrap_or(&f.path);
+            let is_kept = ! self.prune_empty && self.match_patterns.matches_or_empty(&f.name, path);
+            is_kept || self.has_anything_in(f, root, recurse, depth, ignore, cache)
+        });
+    }
+
+    /// Whether there’s anything left inside the given directory, at the
+    /// given depth, once its contents have been filtered. A directory that
+    /// is too deep for its contents to get listed, or that’s been pruned,
+    /// is assumed to have something in it, rather than everything
+    /// underneath it being read, and so is one that can’t be read, so the
+    /// error still gets shown.
+    ///
+    /// Each answer gets remembered, along with the answers for everything
+    /// underneath, so each directory only gets read once however many
+    /// levels of the listing ask about it.
+    fn has_anything_in(&self, dir: &File<'_>, root: &Path, recurse: &RecurseOptions, depth: usize, ignore: Option<&IgnoreCache>, cache: &FilterCache) -> bool {
+        if recurse.is_too_deep(depth) || recurse.is_pruned(dir) {
+            return true;
+        }
+
+        let key = (dir.path.clone(), depth);
+        if let Some(&answer) = cache.has_anything.lock().unwrap().get(&key) {
+            return answer;
+        }
+
+        let answer = match Dir::read_dir(dir.path.clone()) {
+            Ok(dir) => {
+                let mut files = dir.files(self.dot_filter, ignore)
+                                   .flatten()
+                                   .filter(|f| ! f.is_all_all)
+                                   .collect::<Vec<_>>();
+
+                self.filter_recursed_files(&mut files, root, recurse, depth + 1, ignore, cache);
+                ! files.is_empty()
+            }
+            Err(_) => true,
+        };
+
+        cache.has_anything.lock().unwrap().insert(key, answer);
+        answer
+    }
+
     /// Remove every file in the given vector that does *not* pass the
     /// filter predicate for file names specified on the command-line.
     ///
@@ -84,10 +193,20 @@ impl FileFilter {
     /// from the glob, even though the globbing is done by the shell!
     pub fn filter_argument_files(&self, files: &mut Vec<File<'_>>) {
         files.retain(|f| {
-            ! self.ignore_patterns.is_ignored(&f.name)
+            ! self.ignore_patterns.matches(&f.name, &f.path)
+                && self.match_patterns.matches_or_empty(&f.name, &f.path)
+                && self.predicates.matches(f)
         });
     }
 
+    /// Remove every directory in the given vector whose total is outside
+    /// the size limits. Totals only get added up once everything else has
+    /// been filtered, so that directories don’t get added up just to be
+    /// hidden, which means testing their sizes afterwards.
+    pub fn filter_total_sizes(&self, files: &mut Vec<File<'_>>) {
+        files.retain(|f| ! f.is_directory() || self.predicates.matches_size(f));
+    }
+
     /// Sort the files in the given vector based on the sort field option.
     pub fn sort_files<'a, F>(&self, files: &mut [F])
     where F: AsRef<File<'a>>
@@ -126,7 +245,8 @@ pub enum SortField {
     /// The file’s extension, with extensionless files being listed first.
     Extension(SortCase),
 
-    /// The file’s size, in bytes.
+    /// The file’s size, in bytes, or the total size of a directory’s
+    /// contents if it’s been added up.
     Size,
 
     /// The file’s inode, which usually corresponds to the order in which
@@ -224,7 +344,8 @@ impl SortField {
             Self::Name(ABCabc)  => natord::compare(&a.name, &b.name),
             Self::Name(AaBbCc)  => natord::compare_ignore_case(&a.name, &b.name),
 
-            Self::Size          => a.metadata.len().cmp(&b.metadata.len()),
+            Self::Size          => a.total_size.unwrap_or(a.metadata.len())
+                                       .cmp(&b.total_size.unwrap_or(b.metadata.len())),
             #[cfg(unix)]
             Self::FileInode     => a.metadata.ino().cmp(&b.metadata.ino()),
             Self::ModifiedDate  => a.modified_time().cmp(&b.modified_time()),
@@ -268,28 +389,47 @@ impl SortField {
 }
 
 
-/// The **ignore patterns** are a list of globs that are tested against
-/// each filename, and if any of them match, that file isn’t displayed.
-/// This lets a user hide, say, text files by ignoring `*.txt`.
+/// The **name patterns** are a list of globs and regular expressions that
+/// are tested against each file. They get used both for hiding files, such
+/// as ignoring `*.txt` to hide text files, and for showing only the files
+/// that match.
+///
+/// A glob with a `/` in it is tested against the file’s path relative to
+/// the directory being listed, so `src/**/*.rs` can pick out files in a
+/// tree; any other pattern, including every regex, is tested against the
+/// file name on its own.
 #[derive(PartialEq, Eq, Default, Debug, Clone)]
-pub struct IgnorePatterns {
-    patterns: Vec<glob::Pattern>,
+pub struct NamePatterns {
+    patterns: Vec<NamePattern>,
+}
+
+/// One pattern in a set of name patterns.
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+
+    /// A glob, which has to match the whole name or path. Wildcards don’t
+    /// match across a `/`, except for `**`.
+    Glob(glob::Pattern),
+
+    /// A regular expression, which only has to match somewhere in the name
+    /// or path, so it needs `^` and `$` to match the whole thing.
+    Regex(regex::Regex),
 }
 
-impl FromIterator<glob::Pattern> for IgnorePatterns {
+impl FromIterator<glob::Pattern> for NamePatterns {
 
     fn from_iter<I>(iter: I) -> Self
     where I: IntoIterator<Item = glob::Pattern>
     {
-        let patterns = iter.into_iter().collect();
+        let patterns = iter.into_iter().map(NamePattern::Glob).collect();
         Self { patterns }
     }
 }
 
-impl IgnorePatterns {
+impl NamePatterns {
 
     /// Create a new list from the input glob strings, turning the inputs that
-    /// are valid glob patterns into an `IgnorePatterns`. The inputs that
+    /// are valid glob patterns into a `NamePatterns`. The inputs that
     /// don’t parse correctly are returned separately.
     pub fn parse_from_iter<'a, I: IntoIterator<Item = &'a str>>(iter: I) -> (Self, Vec<glob::PatternError>) {
         let iter = iter.into_iter();
@@ -306,7 +446,7 @@ impl IgnorePatterns {
 
         for input in iter {
             match glob::Pattern::new(input) {
-                Ok(pat) => patterns.push(pat),
+                Ok(pat) => patterns.push(NamePattern::Glob(pat)),
                 Err(e)  => errors.push(e),
             }
         }
@@ -319,25 +459,215 @@ impl IgnorePatterns {
         Self { patterns: Vec::new() }
     }
 
-    /// Test whether the given file should be hidden from the results.
-    fn is_ignored(&self, file: &str) -> bool {
-        self.patterns.iter().any(|p| p.matches(file))
+    /// Adds a regular expression to the set.
+    pub fn push_regex(&mut self, regex: regex::Regex) {
+        self.patterns.push(NamePattern::Regex(regex));
+    }
+
+    /// Whether there are no patterns in the set.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Test whether any of the patterns match the file with the given name
+    /// and relative path.
+    pub fn matches(&self, name: &str, path: &Path) -> bool {
+        self.patterns.iter().any(|p| {
+            if p.is_path_aware() {
+                p.matches(&path.to_string_lossy())
+            }
+            else {
+                p.matches(name)
+            }
+        })
+    }
+
+    /// Test whether the file should be shown by a set of patterns to show,
+    /// which shows everything if it’s empty.
+    pub fn matches_or_empty(&self, name: &str, path: &Path) -> bool {
+        self.is_empty() || self.matches(name, path)
     }
 }
 
+impl NamePattern {
+    /// Whether this pattern gets tested against paths rather than names.
+    /// Only globs can be: a `/` in a regex is just as likely to be part of
+    /// a character class such as `[^/]`, which makes sense for names too.
+    fn is_path_aware(&self) -> bool {
+        match self {
+            Self::Glob(glob)  => glob.as_str().contains('/'),
+            Self::Regex(_)    => false,
+        }
+    }
 
-/// Whether to ignore or display files that Git would ignore.
+    fn matches(&self, text: &str) -> bool {
+        let options = glob::MatchOptions { require_literal_separator: true, .. glob::MatchOptions::new() };
+
+        match self {
+            Self::Glob(glob)    => glob.matches_with(text, options),
+            Self::Regex(regex)  => regex.is_match(text),
+        }
+    }
+}
+
+// Regexes can’t be compared, but the strings they were made from can.
+impl PartialEq for NamePattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Glob(a), Self::Glob(b))    => a == b,
+            (Self::Regex(a), Self::Regex(b))  => a.as_str() == b.as_str(),
+            _                                 => false,
+        }
+    }
+}
+
+impl Eq for NamePattern {}
+
+
+/// Tests on a file’s metadata, in the style of `find`, that it has to pass
+/// to be listed. Every test that’s been given has to pass.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Predicates {
+
+    /// The smallest size a file can be, in bytes. Directories only have
+    /// sizes when their totals have been added up with `--total-size`, so
+    /// the size tests only apply to them then.
+    pub min_size: Option<u64>,
+
+    /// The largest size a file can be, in bytes.
+    pub max_size: Option<u64>,
+
+    /// Which of the file’s timestamps the time tests use.
+    pub time_type: f::TimeType,
+
+    /// The time that a file’s timestamp has to be at or after.
+    pub newer: Option<SystemTime>,
+
+    /// The time that a file’s timestamp has to be before.
+    pub older: Option<SystemTime>,
+
+    /// The types of file to list, or every type if this is empty.
+    pub types: Vec<f::Type>,
+
+    /// The ID of the user that has to own the file.
+    pub owner: Option<u32>,
+
+    /// The ID of the group that has to own the file.
+    pub group: Option<u32>,
+
+    /// The permission bits that the file has to have.
+    pub permissions: Option<PermissionMask>,
+}
+
+/// A test on a file’s permission bits, the same as `find -perm`.
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum PermissionMask {
+
+    /// The file’s permission bits have to be exactly these.
+    Exact(u32),
+
+    /// The file has to have all of these bits set, as with `-perm -MODE`.
+    All(u32),
+
+    /// The file has to have any of these bits set, as with `-perm /MODE`.
+    Any(u32),
+}
+
+impl Predicates {
+
+    /// Whether the file passes every test.
+    pub fn matches(&self, file: &File<'_>) -> bool {
+        if ! self.matches_size(file) {
+            return false;
+        }
+
+        if self.newer.is_some() || self.older.is_some() {
+            // Files without the timestamp can’t be said to be either
+            let time = match self.time_type.of_file(file) {
+                Some(t)  => t,
+                None     => return false,
+            };
+
+            if self.newer.map_or(false, |newer| time < newer) || self.older.map_or(false, |older| time >= older) {
+                return false;
+            }
+        }
+
+        if ! self.types.is_empty() && ! self.types.contains(&file.type_char()) {
+            return false;
+        }
+
+        self.matches_owner_and_permissions(file)
+    }
+
+    /// Whether the file’s size is within the limits, which a directory
+    /// without a total always is.
+    fn matches_size(&self, file: &File<'_>) -> bool {
+        let size = if file.is_directory() { file.total_size }
+                                     else { Some(file.metadata.len()) };
+
+        match size {
+            Some(size)  => self.min_size.map_or(true, |min| size >= min) && self.max_size.map_or(true, |max| size <= max),
+            None        => true,
+        }
+    }
+
+    #[cfg(unix)]
+    fn matches_owner_and_permissions(&self, file: &File<'_>) -> bool {
+        if self.owner.map_or(false, |uid| file.user().0 != uid) || self.group.map_or(false, |gid| file.group().0 != gid) {
+            return false;
+        }
+
+        let bits = file.metadata.mode() & 0o7777;
+        match self.permissions {
+            Some(PermissionMask::Exact(mask))  => bits == mask,
+            Some(PermissionMask::All(mask))    => bits & mask == mask,
+            Some(PermissionMask::Any(mask))    => bits & mask != 0,
+            None                               => true,
+        }
+    }
+
+    #[cfg(windows)]
+    fn matches_owner_and_permissions(&self, _file: &File<'_>) -> bool {
+        true
+    }
+}
+
+
+/// Whether to ignore or display files that Git would ignore.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
 pub enum GitIgnore {
 
     /// Ignore files that Git would ignore.
     CheckAndIgnore,
 
     /// Display files, even if Git would ignore them.
+    #[default]
     Off,
 }
 
 
+/// The results of reading through the directories underneath the ones
+/// being listed, which get remembered so that nothing has to be read twice
+/// while recursing, however many levels of the listing need them.
+///
+/// A new cache gets made for each directory on the command-line, and for
+/// the files on it, and passed along with the filter to everything that
+/// lists them, so it only lasts as long as that listing does. A copy of a
+/// cache shares its results with the original, so the threads that read
+/// directories ahead of the listing and the listing itself only look
+/// through each directory once between them.
+#[derive(Default, Clone)]
+pub struct FilterCache {
+
+    /// Whether each directory has anything left in it after filtering, by
+    /// its path and its depth.
+    has_anything: Arc<Mutex<HashMap<(PathBuf, usize), bool>>>,
+
+    /// The totals of the directories that have been added up.
+    pub totals: Totals,
+}
+
 
 #[cfg(test)]
 mod test_ignores {
@@ -345,32 +675,226 @@ mod test_ignores {
 
     #[test]
     fn empty_matches_nothing() {
-        let pats = IgnorePatterns::empty();
-        assert!(!pats.is_ignored("nothing"));
-        assert!(!pats.is_ignored("test.mp3"));
+        let pats = NamePatterns::empty();
+        assert!(!pats.matches("nothing", Path::new("nothing")));
+        assert!(!pats.matches("test.mp3", Path::new("test.mp3")));
     }
 
     #[test]
     fn ignores_a_glob() {
-        let (pats, fails) = IgnorePatterns::parse_from_iter(vec![ "*.mp3" ]);
+        let (pats, fails) = NamePatterns::parse_from_iter(vec![ "*.mp3" ]);
         assert!(fails.is_empty());
-        assert!(!pats.is_ignored("nothing"));
-        assert!(pats.is_ignored("test.mp3"));
+        assert!(!pats.matches("nothing", Path::new("nothing")));
+        assert!(pats.matches("test.mp3", Path::new("test.mp3")));
     }
 
     #[test]
     fn ignores_an_exact_filename() {
-        let (pats, fails) = IgnorePatterns::parse_from_iter(vec![ "nothing" ]);
+        let (pats, fails) = NamePatterns::parse_from_iter(vec![ "nothing" ]);
         assert!(fails.is_empty());
-        assert!(pats.is_ignored("nothing"));
-        assert!(!pats.is_ignored("test.mp3"));
+        assert!(pats.matches("nothing", Path::new("nothing")));
+        assert!(!pats.matches("test.mp3", Path::new("test.mp3")));
     }
 
     #[test]
     fn ignores_both() {
-        let (pats, fails) = IgnorePatterns::parse_from_iter(vec![ "nothing", "*.mp3" ]);
+        let (pats, fails) = NamePatterns::parse_from_iter(vec![ "nothing", "*.mp3" ]);
+        assert!(fails.is_empty());
+        assert!(pats.matches("nothing", Path::new("nothing")));
+        assert!(pats.matches("test.mp3", Path::new("test.mp3")));
+    }
+
+    #[test]
+    fn path_aware_glob() {
+        let (pats, fails) = NamePatterns::parse_from_iter(vec![ "src/**/*.rs" ]);
+        assert!(fails.is_empty());
+        assert!(pats.matches("main.rs", Path::new("src/main.rs")));
+        assert!(pats.matches("filter.rs", Path::new("src/fs/filter.rs")));
+        assert!(!pats.matches("build.rs", Path::new("build.rs")));
+        assert!(!pats.matches("main.rs", Path::new("tests/src/main.rs")));
+    }
+
+    #[test]
+    fn single_star_stays_in_its_directory() {
+        let (pats, fails) = NamePatterns::parse_from_iter(vec![ "src/*.rs" ]);
         assert!(fails.is_empty());
-        assert!(pats.is_ignored("nothing"));
-        assert!(pats.is_ignored("test.mp3"));
+        assert!(pats.matches("main.rs", Path::new("src/main.rs")));
+        assert!(!pats.matches("filter.rs", Path::new("src/fs/filter.rs")));
+    }
+
+    #[test]
+    fn regex() {
+        let mut pats = NamePatterns::empty();
+        pats.push_regex(regex::Regex::new(r"^test_.*\.py$").unwrap());
+        assert!(pats.matches("test_filter.py", Path::new("test_filter.py")));
+        assert!(!pats.matches("filter.py", Path::new("filter.py")));
+    }
+
+    #[test]
+    fn regex_with_a_slash_matches_the_name() {
+        let mut pats = NamePatterns::empty();
+        pats.push_regex(regex::Regex::new(r"^[^/]*\.tmp$").unwrap());
+        assert!(pats.matches("a.tmp", Path::new("sub/a.tmp")));
+        assert!(!pats.matches("a.txt", Path::new("sub/a.txt")));
+    }
+
+    #[test]
+    fn empty_shows_everything() {
+        let pats = NamePatterns::empty();
+        assert!(pats.matches_or_empty("anything", Path::new("anything")));
+    }
+}
+
+
+#[cfg(test)]
+mod test_recursed {
+    use super::*;
+    use crate::fs::temp_tree::TempTree;
+
+    fn recurse(max_depth: Option<usize>) -> RecurseOptions {
+        RecurseOptions { tree: true, max_depth, max_entries: None, prune: Vec::new() }
+    }
+
+    fn kept(tree: &TempTree, filter: &FileFilter, recurse: &RecurseOptions) -> Vec<String> {
+        kept_with_cache(tree, filter, recurse, &FilterCache::default())
+    }
+
+    fn kept_with_cache(tree: &TempTree, filter: &FileFilter, recurse: &RecurseOptions, cache: &FilterCache) -> Vec<String> {
+        let dir = Dir::read_dir(tree.path.clone()).unwrap();
+        let mut files = dir.files(filter.dot_filter, None).flatten().collect::<Vec<_>>();
+        filter.filter_recursed_files(&mut files, tree.as_path(), recurse, 1, None, cache);
+
+        let mut names = files.into_iter().map(|f| f.name).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn pruning_empty() -> FileFilter {
+        FileFilter { prune_empty: true, .. FileFilter::default() }
+    }
+
+    #[test]
+    fn prune_empty() {
+        let tree = TempTree::new("filter-prune-empty");
+        tree.dir("empty").file("full/a", 1).dir("nested/empty").file("b", 1);
+
+        assert_eq!(kept(&tree, &pruning_empty(), &recurse(None)), vec![ "b", "full" ]);
+    }
+
+    #[test]
+    fn prune_empty_stops_at_the_level() {
+        let tree = TempTree::new("filter-prune-empty-level");
+        tree.dir("empty").dir("nested/empty");
+
+        // The directories inside `nested` are too deep to be listed, so it
+        // isn’t empty as far as the listing is concerned.
+        assert_eq!(kept(&tree, &pruning_empty(), &recurse(Some(2))), vec![ "nested" ]);
+
+        // Neither directory’s contents get listed, so neither gets read.
+        assert_eq!(kept(&tree, &pruning_empty(), &recurse(Some(1))), vec![ "empty", "nested" ]);
+    }
+
+    #[test]
+    fn prune_empty_keeps_pruned_directories() {
+        let tree = TempTree::new("filter-prune-empty-pruned");
+        tree.dir("node_modules/empty").dir("empty");
+
+        let recurse = RecurseOptions { prune: vec![ glob::Pattern::new("node_modules").unwrap() ], .. recurse(None) };
+        assert_eq!(kept(&tree, &pruning_empty(), &recurse), vec![ "node_modules" ]);
+    }
+
+    #[test]
+    fn prune_empty_remembers() {
+        let tree = TempTree::new("filter-prune-empty-remembered");
+        tree.dir("nested/empty");
+
+        let cache = FilterCache::default();
+        assert!(kept_with_cache(&tree, &pruning_empty(), &recurse(None), &cache).is_empty());
+
+        // Everything underneath got looked at the first time round, so
+        // none of it gets read again while the same listing goes on.
+        tree.file("nested/empty/a", 1);
+        assert!(kept_with_cache(&tree, &pruning_empty(), &recurse(None), &cache).is_empty());
+        assert_eq!(kept(&tree, &pruning_empty(), &recurse(None)), vec![ "nested" ]);
+    }
+
+    #[test]
+    fn match_keeps_directories_with_matches() {
+        let tree = TempTree::new("filter-match");
+        tree.file("a.rs", 1).file("b.txt", 1).file("src/fs/c.rs", 1).file("docs/more/d.txt", 1);
+
+        let (match_patterns, _) = NamePatterns::parse_from_iter(vec![ "*.rs" ]);
+        let filter = FileFilter { match_patterns, .. FileFilter::default() };
+        assert_eq!(kept(&tree, &filter, &recurse(None)), vec![ "a.rs", "src" ]);
+
+        // Only the levels that get listed are searched for matches.
+        assert_eq!(kept(&tree, &filter, &recurse(Some(2))), vec![ "a.rs", "docs", "src" ]);
+    }
+
+    #[test]
+    fn match_paths_from_the_root() {
+        let tree = TempTree::new("filter-match-paths");
+        tree.file("src/a.rs", 1).file("tests/src/b.rs", 1);
+
+        let (match_patterns, _) = NamePatterns::parse_from_iter(vec![ "src/**/*.rs" ]);
+        let filter = FileFilter { match_patterns, .. FileFilter::default() };
+        assert_eq!(kept(&tree, &filter, &recurse(None)), vec![ "src" ]);
+    }
+
+    #[test]
+    fn directories_skip_predicates() {
+        let tree = TempTree::new("filter-predicates");
+        tree.file("big", 100).file("small", 1).file("dir/big", 100);
+
+        let predicates = Predicates { min_size: Some(10), .. Predicates::default() };
+        let filter = FileFilter { predicates, .. FileFilter::default() };
+        assert_eq!(kept(&tree, &filter, &recurse(None)), vec![ "big", "dir" ]);
+    }
+}
+
+
+#[cfg(test)]
+mod test_sizes {
+    use super::*;
+    use crate::fs::temp_tree::TempTree;
+
+    /// Lists the top of the tree, with the given totals for its
+    /// directories, and filters it on the sizes.
+    fn kept(tree: &TempTree, totals: &[(&str, u64)], predicates: Predicates) -> Vec<String> {
+        let dir = Dir::read_dir(tree.path.clone()).unwrap();
+        let mut files = dir.files(DotFilter::JustFiles, None).flatten().collect::<Vec<_>>();
+        for file in &mut files {
+            file.total_size = totals.iter().find(|(name, _)| *name == file.name).map(|(_, total)| *total);
+        }
+
+        let filter = FileFilter { predicates, .. FileFilter::default() };
+        filter.filter_child_files(&mut files);
+        filter.filter_total_sizes(&mut files);
+
+        let mut names = files.into_iter().map(|f| f.name).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn directories_without_totals_pass() {
+        let tree = TempTree::new("filter-sizes-no-totals");
+        tree.file("big", 100).file("small", 1).dir("dir");
+
+        let predicates = Predicates { min_size: Some(10), .. Predicates::default() };
+        assert_eq!(kept(&tree, &[], predicates), vec![ "big", "dir" ]);
+    }
+
+    #[test]
+    fn directories_with_totals_get_tested() {
+        let tree = TempTree::new("filter-sizes-totals");
+        tree.file("big/a", 100).file("small/b", 1).file("c", 50);
+
+        let totals = [ ("big", 100), ("small", 1) ];
+        let predicates = Predicates { min_size: Some(10), .. Predicates::default() };
+        assert_eq!(kept(&tree, &totals, predicates), vec![ "big", "c" ]);
+
+        let predicates = Predicates { max_size: Some(60), .. Predicates::default() };
+        assert_eq!(kept(&tree, &totals, predicates), vec![ "c", "small" ]);
     }
 }
@@ -0,0 +1,453 @@
+//! Working out which files are ignored by `.gitignore` files, for
+//! `--git-ignore`.
+//!
+//! This reads the ignore files itself rather than asking Git, which means
+//! it works without the `git` feature, and doesn’t have to get the status of
+//! every file in the repository first. The files it reads, from lowest to
+//! highest priority, are:
+//!
+//! - the global excludes file, from `core.excludesFile` in the Git config or
+//!   `~/.config/git/ignore`;
+//! - the repository’s `.git/info/exclude`;
+//! - the `.gitignore`, `.ignore` and `.exaignore` files in each directory
+//!   from the root of the repository down to the one being listed, with the
+//!   deeper ones taking priority.
+//!
+//! The first two and `.gitignore` files only apply inside a Git repository,
+//! but `.ignore` and `.exaignore` files apply anywhere, from the root of the
+//! filesystem down.
+//!
+//! As with Git, once a directory is ignored, nothing inside it can be
+//! un-ignored again with a `!` pattern.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::*;
+
+
+/// The names of the ignore files that get read in each directory, from
+/// lowest to highest priority, and whether they only apply inside a Git
+/// repository.
+const IGNORE_FILES: &[(&str, bool)] = &[
+    (".gitignore", true),
+    (".ignore",    false),
+    (".exaignore", false),
+];
+
+
+/// A cache of the ignore rules that apply inside each directory, so that
+/// each ignore file only gets read once however many times its directory
+/// gets looked at. Copies of the cache share the same rules, so the threads
+/// that read directories ahead of the listing can have one each.
+#[derive(Default, Clone)]
+pub struct IgnoreCache {
+    dirs: Arc<Mutex<HashMap<PathBuf, Arc<DirIgnores>>>>,
+}
+
+/// The ignore rules that apply to the files inside one directory.
+#[derive(Debug)]
+pub struct DirIgnores {
+
+    /// The directory’s canonical path.
+    path: PathBuf,
+
+    /// Whether the directory is inside the working directory of a Git
+    /// repository, which is when the Git-specific files apply.
+    in_repo: bool,
+
+    /// Whether the directory itself is ignored, which means everything
+    /// inside it is too.
+    ignored: bool,
+
+    /// The ignore files that apply, from lowest to highest priority.
+    files: Vec<Arc<IgnoreFile>>,
+}
+
+/// The rules read from one ignore file.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct IgnoreFile {
+
+    /// The directory that the patterns with slashes in them are relative
+    /// to. This is the directory the file is in, or the root of the
+    /// repository for the global and `info/exclude` files.
+    base: PathBuf,
+
+    /// The rules in the file, in the order they were written.
+    rules: Vec<Rule>,
+
+    /// Whether this file only applies inside a Git repository, as opposed
+    /// to being an `.ignore` or `.exaignore` file.
+    git_only: bool,
+}
+
+/// One line of an ignore file.
+#[derive(PartialEq, Eq, Debug)]
+struct Rule {
+
+    /// The glob to match, with any leading `/` or trailing `/` removed.
+    glob: glob::Pattern,
+
+    /// Whether the line started with `!`, which un-ignores matching files.
+    negated: bool,
+
+    /// Whether the line ended with `/`, which means it only matches
+    /// directories.
+    dir_only: bool,
+
+    /// Whether the pattern had a `/` at the start or in the middle, which
+    /// means it gets matched against the path relative to the base rather
+    /// than against the file name.
+    anchored: bool,
+}
+
+
+impl IgnoreCache {
+
+    /// Gets the rules for the files inside the given directory, reading any
+    /// ignore files in it or its parents that haven’t been read yet.
+    pub fn for_dir(&self, path: &Path) -> Arc<DirIgnores> {
+        let path = match fs::canonicalize(path) {
+            Ok(p)   => p,
+            Err(e)  => {
+                debug!("Error canonicalising {:?} to find its ignore files: {}", path, e);
+                path.to_path_buf()
+            }
+        };
+
+        self.for_canonical_dir(&path)
+    }
+
+    fn for_canonical_dir(&self, path: &Path) -> Arc<DirIgnores> {
+        if let Some(ignores) = self.dirs.lock().unwrap().get(path) {
+            return Arc::clone(ignores);
+        }
+
+        let ignores = match path.parent() {
+            Some(parent)  => self.for_canonical_dir(parent).child(path),
+            None          => DirIgnores::root(path),
+        };
+
+        let ignores = Arc::new(ignores);
+        self.dirs.lock().unwrap().insert(path.to_path_buf(), Arc::clone(&ignores));
+        ignores
+    }
+}
+
+impl DirIgnores {
+
+    /// The rules for the root directory of the filesystem.
+    fn root(path: &Path) -> Self {
+        let mut ignores = Self { path: path.to_path_buf(), in_repo: false, ignored: false, files: Vec::new() };
+        ignores.read_own_files();
+        ignores
+    }
+
+    /// The rules for a directory inside this one.
+    fn child(&self, path: &Path) -> Self {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut ignores = Self { path: path.to_path_buf(), in_repo: self.in_repo, ignored: self.is_ignored(&name, || true), files: Vec::new() };
+
+        // A repository inside another one, such as a submodule, doesn’t
+        // use the outer one’s rules, but `.ignore` files still apply.
+        if path.join(".git").exists() {
+            ignores.in_repo = true;
+            ignores.files.extend(self.files.iter().filter(|f| ! f.git_only).cloned());
+            ignores.read_repo_files();
+        }
+        else {
+            ignores.files.extend(self.files.iter().cloned());
+        }
+
+        ignores.read_own_files();
+        ignores
+    }
+
+    /// Reads the files that apply to the whole of the repository that this
+    /// directory is the root of.
+    fn read_repo_files(&mut self) {
+        if let Some(global) = global_excludes_path(&self.path) {
+            self.push_file(&global, true);
+        }
+
+        let exclude = self.path.join(".git").join("info").join("exclude");
+        self.push_file(&exclude, true);
+    }
+
+    /// Reads the ignore files in this directory.
+    fn read_own_files(&mut self) {
+        for (name, git_only) in IGNORE_FILES {
+            if *git_only && ! self.in_repo {
+                continue;
+            }
+
+            let path = self.path.join(name);
+            self.push_file(&path, *git_only);
+        }
+    }
+
+    /// Reads an ignore file whose patterns are relative to this directory.
+    fn push_file(&mut self, path: &Path, git_only: bool) {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                debug!("Reading ignore file {:?}", path);
+                let file = IgnoreFile::parse(&self.path, &contents, git_only);
+                self.files.push(Arc::new(file));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                debug!("Error reading ignore file {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// Whether the file with the given name inside this directory is
+    /// ignored. Working out whether it’s a directory means looking at its
+    /// metadata, so that only gets done if a rule needs to know.
+    pub fn is_ignored(&self, name: &str, is_dir: impl Fn() -> bool) -> bool {
+        if self.ignored {
+            return true;
+        }
+
+        let path = self.path.join(name);
+        let mut is_dir_cache = None;
+        let mut is_dir = || *is_dir_cache.get_or_insert_with(&is_dir);
+
+        for file in self.files.iter().rev() {
+            if let Some(ignored) = file.matches(&path, &mut is_dir) {
+                return ignored;
+            }
+        }
+
+        false
+    }
+}
+
+impl IgnoreFile {
+
+    /// Parses the contents of an ignore file, skipping any lines that
+    /// aren’t valid patterns.
+    pub fn parse(base: &Path, contents: &str, git_only: bool) -> Self {
+        let rules = contents.lines().filter_map(Rule::parse).collect();
+        Self { base: base.to_path_buf(), rules, git_only }
+    }
+
+    /// Returns whether the file at the given path is ignored by the last
+    /// rule that matches it, or `None` if none of them do.
+    fn matches(&self, path: &Path, is_dir: &mut impl FnMut() -> bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?;
+        let relative = relative.to_string_lossy();
+        let name = path.file_name()?.to_string_lossy();
+
+        let options = glob::MatchOptions { require_literal_separator: true, .. glob::MatchOptions::new() };
+
+        self.rules.iter().rev()
+            .find(|rule| {
+                let text = if rule.anchored { &*relative } else { &*name };
+                rule.glob.matches_with(text, options) && (! rule.dir_only || is_dir())
+            })
+            .map(|rule| ! rule.negated)
+    }
+}
+
+impl Rule {
+
+    /// Parses one line of an ignore file, returning `None` for blank lines,
+    /// comments, and patterns that can’t be turned into globs.
+    fn parse(line: &str) -> Option<Self> {
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let line = trim_trailing_spaces(line);
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest)  => (true, rest),
+            None        => (false, line),
+        };
+
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest)  => (true, rest),
+            None        => (false, line),
+        };
+
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+
+        match glob::Pattern::new(&escape_for_glob(line)) {
+            Ok(glob) => {
+                Some(Self { glob, negated, dir_only, anchored })
+            }
+            Err(e) => {
+                debug!("Skipping ignore pattern {:?}: {}", line, e);
+                None
+            }
+        }
+    }
+}
+
+/// Removes spaces from the end of a line, unless they’re escaped with a
+/// backslash.
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        &line[..= trimmed.len()]
+    }
+    else {
+        trimmed
+    }
+}
+
+/// Turns the backslash escapes in a Git pattern into the square-bracket
+/// escapes that globs use, and turns any `**` that isn’t a whole path
+/// component into `*`, which is what Git treats it as.
+fn escape_for_glob(pattern: &str) -> String {
+    let mut glob = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                match chars.next() {
+                    Some(c @ ('*' | '?' | '[' | ']'))  => { glob.push('['); glob.push(c); glob.push(']'); }
+                    Some(c)                            => glob.push(c),
+                    None                               => {}
+                }
+            }
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let starts_component = glob.is_empty() || glob.ends_with('/');
+                let ends_component = chars.peek().map_or(true, |c| *c == '/');
+                glob.push_str(if starts_component && ends_component { "**" } else { "*" });
+            }
+            c => glob.push(c),
+        }
+    }
+
+    glob
+}
+
+/// Finds the global excludes file: the one in the `core.excludesFile`
+/// setting of the repository’s or the user’s Git config, or the default
+/// one in the user’s config directory.
+fn global_excludes_path(repo: &Path) -> Option<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let config_home = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+                          .or_else(|| home.as_ref().map(|h| h.join(".config")));
+
+    let configs = [
+        Some(repo.join(".git").join("config")),
+        home.as_ref().map(|h| h.join(".gitconfig")),
+        config_home.as_ref().map(|c| c.join("git").join("config")),
+    ];
+
+    for config in configs.iter().flatten() {
+        if let Some(path) = fs::read_to_string(config).ok().and_then(|c| excludes_file_setting(&c)) {
+            return Some(match (path.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home))  => home.join(rest),
+                _                         => PathBuf::from(path),
+            });
+        }
+    }
+
+    config_home.map(|c| c.join("git").join("ignore"))
+}
+
+/// Reads the `excludesFile` setting from the `[core]` section of a Git
+/// config file.
+fn excludes_file_setting(config: &str) -> Option<String> {
+    let mut in_core = false;
+
+    for line in config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_core = line.trim_start_matches('[').trim_end_matches(']').trim().eq_ignore_ascii_case("core");
+        }
+        else if let (true, Some((key, value))) = (in_core, line.split_once('=')) {
+            if key.trim().eq_ignore_ascii_case("excludesfile") {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn is_ignored(contents: &str, path: &str, is_dir: bool) -> Option<bool> {
+        let file = IgnoreFile::parse(Path::new("/repo"), contents, true);
+        file.matches(&Path::new("/repo").join(path), &mut || is_dir)
+    }
+
+    #[test]
+    fn name_anywhere() {
+        assert_eq!(is_ignored("*.o\n", "main.o", false), Some(true));
+        assert_eq!(is_ignored("*.o\n", "src/lib/main.o", false), Some(true));
+        assert_eq!(is_ignored("*.o\n", "main.c", false), None);
+    }
+
+    #[test]
+    fn comments_and_blanks() {
+        assert_eq!(IgnoreFile::parse(Path::new("/"), "# comment\n\n   \n", false).rules, Vec::new());
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(is_ignored("*.log\n!keep.log\n", "debug.log", false), Some(true));
+        assert_eq!(is_ignored("*.log\n!keep.log\n", "keep.log", false), Some(false));
+        assert_eq!(is_ignored("!keep.log\n*.log\n", "keep.log", false), Some(true));
+    }
+
+    #[test]
+    fn anchored() {
+        assert_eq!(is_ignored("/build\n", "build", true), Some(true));
+        assert_eq!(is_ignored("/build\n", "src/build", true), None);
+        assert_eq!(is_ignored("doc/*.txt\n", "doc/notes.txt", false), Some(true));
+        assert_eq!(is_ignored("doc/*.txt\n", "doc/server/notes.txt", false), None);
+    }
+
+    #[test]
+    fn directory_only() {
+        assert_eq!(is_ignored("target/\n", "target", true), Some(true));
+        assert_eq!(is_ignored("target/\n", "target", false), None);
+    }
+
+    #[test]
+    fn double_stars() {
+        assert_eq!(is_ignored("**/logs\n", "logs", true), Some(true));
+        assert_eq!(is_ignored("**/logs\n", "a/b/logs", true), Some(true));
+        assert_eq!(is_ignored("a/**/b\n", "a/x/y/b", false), Some(true));
+        assert_eq!(is_ignored("abc/**\n", "abc/d/e", false), Some(true));
+        assert_eq!(is_ignored("foo**bar\n", "fooxbar", false), Some(true));
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(is_ignored("\\#notes\n", "#notes", false), Some(true));
+        assert_eq!(is_ignored("\\!important\n", "!important", false), Some(true));
+        assert_eq!(is_ignored("what\\?\n", "what?", false), Some(true));
+        assert_eq!(is_ignored("what\\?\n", "whats", false), None);
+    }
+
+    #[test]
+    fn trailing_spaces() {
+        assert_eq!(trim_trailing_spaces("file   "), "file");
+        assert_eq!(trim_trailing_spaces("file\\  "), "file\\ ");
+    }
+
+    #[test]
+    fn excludes_file() {
+        let config = "[user]\n\tname = Someone\n[core]\n\teditor = vim\n\texcludesFile = ~/.gitignore_global\n";
+        assert_eq!(excludes_file_setting(config), Some("~/.gitignore_global".into()));
+        assert_eq!(excludes_file_setting("[user]\n\texcludesfile = nope\n"), None);
+    }
+}
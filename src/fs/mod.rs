@@ -8,3 +8,9 @@ pub mod dir_action;
 pub mod feature;
 pub mod fields;
 pub mod filter;
+pub mod ignore;
+pub mod read_ahead;
+pub mod total_size;
+
+#[cfg(test)]
+pub mod temp_tree;
@@ -0,0 +1,579 @@
+//! Reading the directories in a tree before they get listed, for
+//! `--recurse` and `--tree`.
+//!
+//! Listing a tree one directory at a time means waiting for each directory
+//! to be read, and for each file in it to be statted, before moving on to
+//! the next one, which adds up on slow or network filesystems. Instead, a
+//! few threads read the directories in the background while the listing
+//! goes on. The threads get started once, and then get given the
+//! directories of one listing at a time.
+//!
+//! Each thread has its own queue of directories to read. Once it’s read a
+//! directory, the ones inside it go on the back of its queue, and it
+//! carries on from the back, so each thread works its way down its own
+//! part of the tree in the order it’ll get listed in. A thread with an
+//! empty queue steals a directory from the front of another one’s, which
+//! is the one furthest from being listed, so a thread that’s free never
+//! waits for the others to finish a level. This is the same work-stealing
+//! scheme as in crates such as `rayon`, except that each queue has a lock
+//! rather than being lock-free: every job reads a whole directory, which
+//! takes far longer than taking a lock, so it isn’t worth another
+//! dependency.
+//!
+//! The directories that have been read are kept until the listing gets to
+//! them, so everything still gets listed in the same order as it would
+//! have been. Only so many get kept at once, so a huge tree doesn’t have
+//! to fit in memory before any of it gets listed: the threads wait for the
+//! listing to catch up instead. When the listing gets to a directory that
+//! hasn’t been read yet, it reads it there and then, and its
+//! subdirectories get read ahead the same as any other. Once the listing
+//! is done with a directory, anything from inside it that it didn’t take
+//! gets thrown away, so nothing can take up room in the window for good.
+//!
+//! Only the directories that would get recursed into are read: the same
+//! filter, `--level` and `--prune` options apply. What gets found out about
+//! the directories underneath, such as which ones are empty, goes in the
+//! listing’s cache, so the same directories don’t get looked through
+//! twice. When a directory has more entries than `--max-entries` allows,
+//! none of its subdirectories get read ahead, as which of them get listed
+//! depends on how they sort, so they’re read when the listing reaches them
+//! instead.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use log::*;
+
+use crate::fs::{Dir, File};
+use crate::fs::dir_action::RecurseOptions;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::ignore::IgnoreCache;
+
+
+/// The most directories that get read ahead of the listing at once, either
+/// waiting to be listed or being read.
+const WINDOW: usize = 1024;
+
+
+/// The threads that read directories ahead of them being listed. Without
+/// any threads, as by default, every directory gets read when the listing
+/// gets to it.
+#[derive(Default)]
+pub struct ReadAhead {
+    shared: Arc<Shared>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+/// What the listing and the threads share.
+#[derive(Default)]
+struct Shared {
+
+    /// The directories waiting to be read, with a queue for each thread.
+    queues: Vec<Mutex<VecDeque<Job>>>,
+
+    /// How many directories are waiting in the queues, which the threads
+    /// check before looking through them.
+    queued: AtomicUsize,
+
+    state: Mutex<State>,
+
+    /// Signalled whenever a directory has been queued, read, or taken, and
+    /// when the program is over.
+    changed: Condvar,
+}
+
+/// A directory to read, along with the listing it’s being read for.
+struct Job {
+    path: PathBuf,
+
+    /// The depth of the files inside it.
+    depth: usize,
+
+    walk: Arc<Walk>,
+}
+
+#[derive(Default)]
+struct State {
+
+    /// The listing that directories are being read for. Anything read for
+    /// an earlier one gets thrown away.
+    walk: Option<Arc<Walk>>,
+
+    /// The directories that are being read right now.
+    reading: HashSet<PathBuf>,
+
+    /// The directories that have been read, along with the errors from the
+    /// ones that couldn’t be, in order of their paths, so everything inside
+    /// a directory comes right after it.
+    read: BTreeMap<PathBuf, io::Result<Dir>>,
+
+    /// Whether the program is over, so the threads should stop.
+    finished: bool,
+}
+
+impl ReadAhead {
+
+    /// Starts a thread for each CPU, which waits for directories to read.
+    pub fn spawn() -> Self {
+        let n_threads = num_cpus::get().max(1);
+        debug!("Reading directories ahead on {} threads", n_threads);
+
+        let shared = Arc::new(Shared {
+            queues: (0 .. n_threads).map(|_| Mutex::default()).collect(),
+            .. Shared::default()
+        });
+
+        let threads = (0 .. n_threads).map(|index| {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.work(index))
+        }).collect();
+
+        Self { shared, threads }
+    }
+
+    /// Starts reading every directory among the given files, which are at
+    /// the given depth, that would get recursed into, along with everything
+    /// underneath them that would get recursed into as well. Anything still
+    /// waiting from an earlier listing gets thrown away.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(&self, files: &[File<'_>], depth: usize, recurse: &RecurseOptions, filter: &FileFilter, ignore: Option<&IgnoreCache>, root: &Path, cache: &FilterCache) {
+        if self.threads.is_empty() {
+            return;
+        }
+
+        let walk = Arc::new(Walk {
+            recurse: recurse.clone(),
+            filter:  filter.clone(),
+            ignore:  ignore.cloned(),
+            root:    root.to_path_buf(),
+            cache:   cache.clone(),
+        });
+
+        let mut state = self.shared.state.lock().unwrap();
+        state.walk = Some(Arc::clone(&walk));
+        state.read.clear();
+
+        for queue in &self.shared.queues {
+            let mut queue = queue.lock().unwrap();
+            self.shared.queued.fetch_sub(queue.len(), Ordering::SeqCst);
+            queue.clear();
+        }
+
+        let jobs = walk.dirs_to_read(files, depth);
+        self.shared.push(0, &walk, jobs);
+        drop(state);
+
+        self.shared.changed.notify_all();
+    }
+
+    /// Takes out the directory at the given path if it’s been read ahead,
+    /// waiting for it if it’s being read, or reads it now if it hasn’t.
+    pub fn read_dir(&self, path: &Path) -> io::Result<Dir> {
+        match self.shared.take(path) {
+            Some(result)  => result,
+            None          => Dir::read_dir(path.to_path_buf()),
+        }
+    }
+
+    /// Throws away everything that’s been read from inside the directory
+    /// at the given path, once the listing is done with it. Anything still
+    /// there didn’t get listed, so it would never get taken out.
+    pub fn done_with(&self, path: &Path) {
+        let mut state = self.shared.state.lock().unwrap();
+        let unlisted = state.read.range(path.to_path_buf() ..)
+                                 .take_while(|(p, _)| p.starts_with(path))
+                                 .map(|(p, _)| p.clone())
+                                 .collect::<Vec<_>>();
+
+        if unlisted.is_empty() {
+            return;
+        }
+
+        debug!("Throwing away {} directories read from inside {:?}", unlisted.len(), path);
+        for p in &unlisted {
+            state.read.remove(p);
+        }
+
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+
+    /// Waits until there’s nothing left to read, for the tests to see what
+    /// got read ahead.
+    #[cfg(test)]
+    fn wait(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        while self.shared.queued.load(Ordering::SeqCst) > 0 || ! state.reading.is_empty() {
+            state = self.shared.changed.wait(state).unwrap();
+        }
+    }
+}
+
+impl Drop for ReadAhead {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().finished = true;
+        self.shared.changed.notify_all();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+
+    /// Reads directories until the program is over, taking them from the
+    /// thread’s own queue if there are any, or from another thread’s if
+    /// not, and waiting whenever there’s nothing to read or the window is
+    /// full.
+    fn work(&self, index: usize) {
+        loop {
+            let mut state = self.state.lock().unwrap();
+            while ! state.finished && (self.queued.load(Ordering::SeqCst) == 0 || state.read.len() + state.reading.len() >= WINDOW) {
+                state = self.changed.wait(state).unwrap();
+            }
+
+            if state.finished {
+                return;
+            }
+
+            drop(state);
+
+            // Another thread could have got to the last directory first.
+            let job = match self.pop(index) {
+                Some(job)  => job,
+                None       => continue,
+            };
+
+            let mut state = self.state.lock().unwrap();
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            if ! state.is_current(&job.walk) {
+                continue;
+            }
+
+            state.reading.insert(job.path.clone());
+            drop(state);
+
+            self.read(index, job);
+        }
+    }
+
+    /// Takes the next directory from the back of the thread’s own queue,
+    /// or steals one from the front of another thread’s.
+    fn pop(&self, index: usize) -> Option<Job> {
+        if let Some(job) = self.queues[index].lock().unwrap().pop_back() {
+            return Some(job);
+        }
+
+        let count = self.queues.len();
+        (1 .. count).find_map(|offset| {
+            self.queues[(index + offset) % count].lock().unwrap().pop_front()
+        })
+    }
+
+    /// Puts directories on the back of a thread’s queue, so that the first
+    /// of them is the next one it reads. This has to happen before the
+    /// directory they were found in gets put with the others that have
+    /// been read, so a directory never goes missing between the two.
+    fn push(&self, index: usize, walk: &Arc<Walk>, dirs: Vec<(PathBuf, usize)>) {
+        let count = dirs.len();
+        let mut queue = self.queues[index].lock().unwrap();
+        for (path, depth) in dirs.into_iter().rev() {
+            queue.push_back(Job { path, depth, walk: Arc::clone(walk) });
+        }
+
+        self.queued.fetch_add(count, Ordering::SeqCst);
+    }
+
+    /// Reads a directory and the metadata of its files, then queues up the
+    /// directories inside it that would get recursed into on the given
+    /// thread’s queue.
+    fn read(&self, index: usize, job: Job) {
+        let Job { path, depth, walk } = job;
+        let result = Dir::read_dir(path.clone()).map(|mut dir| {
+            dir.stat_contents(walk.filter.dot_filter);
+            dir
+        });
+
+        if let Ok(dir) = &result {
+            self.push(index, &walk, walk.subdirs_to_read(dir, depth + 1));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.reading.remove(&path);
+        if state.is_current(&walk) {
+            state.read.insert(path, result);
+        }
+
+        drop(state);
+        self.changed.notify_all();
+    }
+
+    /// Takes out the directory at the given path, if it’s going to get read
+    /// ahead at all. One that’s still waiting its turn gets read now, and
+    /// the directories inside it go on the queue it was taken from.
+    fn take(&self, path: &Path) -> Option<io::Result<Dir>> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(result) = state.read.remove(path) {
+                drop(state);
+                self.changed.notify_all();
+                return Some(result);
+            }
+
+            if state.reading.contains(path) {
+                state = self.changed.wait(state).unwrap();
+                continue;
+            }
+
+            // A thread could have just taken it off a queue without having
+            // started to read it, in which case it gets read twice, and the
+            // copy that doesn’t get listed gets thrown away later.
+            let (index, job) = self.queues.iter().enumerate().find_map(|(index, queue)| {
+                let mut queue = queue.lock().unwrap();
+                let position = queue.iter().position(|job| job.path == path)?;
+                queue.remove(position).map(|job| (index, job))
+            })?;
+
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            if ! state.is_current(&job.walk) {
+                return None;
+            }
+
+            state.reading.insert(job.path.clone());
+            drop(state);
+
+            self.read(index, job);
+            state = self.state.lock().unwrap();
+        }
+    }
+}
+
+impl State {
+
+    /// Whether a directory is being read for the listing that’s going on
+    /// now, rather than an earlier one.
+    fn is_current(&self, walk: &Arc<Walk>) -> bool {
+        self.walk.as_ref().map_or(false, |w| Arc::ptr_eq(w, walk))
+    }
+}
+
+
+/// The options that decide which directories get recursed into.
+struct Walk {
+    recurse: RecurseOptions,
+    filter: FileFilter,
+    ignore: Option<IgnoreCache>,
+    root: PathBuf,
+    cache: FilterCache,
+}
+
+impl Walk {
+
+    /// Returns the paths of the directories among the given files, which
+    /// are at the given depth, that would get recursed into.
+    fn dirs_to_read(&self, files: &[File<'_>], depth: usize) -> Vec<(PathBuf, usize)> {
+        if self.recurse.is_too_deep(depth) || self.recurse.entries_to_list(files.len()) < files.len() {
+            return Vec::new();
+        }
+
+        files.iter()
+             .filter(|f| self.recurse.descends_into(f, depth))
+             .map(|f| (f.path.clone(), depth))
+             .collect()
+    }
+
+    /// Returns the paths of the directories in a directory that has just
+    /// been read, whose files are at the given depth, that would get
+    /// recursed into, in the order they’ll get listed in, after filtering
+    /// its files the same way as the listing will.
+    fn subdirs_to_read(&self, dir: &Dir, depth: usize) -> Vec<(PathBuf, usize)> {
+        if self.recurse.is_too_deep(depth) {
+            return Vec::new();
+        }
+
+        let ignore = self.ignore.as_ref();
+        let mut files = dir.files(self.filter.dot_filter, ignore)
+                           .filter_map(Result::ok)
+                           .collect::<Vec<_>>();
+
+        self.filter.filter_recursed_files(&mut files, &self.root, &self.recurse, depth, ignore, &self.cache);
+        self.filter.sort_files(&mut files);
+        self.dirs_to_read(&files, depth)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::DotFilter;
+    use crate::fs::temp_tree::TempTree;
+
+    fn recurse() -> RecurseOptions {
+        RecurseOptions { tree: false, max_depth: None, max_entries: None, prune: Vec::new() }
+    }
+
+    /// Starts reading ahead everything under the top of the tree, and waits
+    /// for it to finish.
+    fn read_ahead(tree: &TempTree, recurse: &RecurseOptions, filter: &FileFilter) -> ReadAhead {
+        let dir = Dir::read_dir(tree.path.clone()).unwrap();
+        let files = dir.files(filter.dot_filter, None).flatten().collect::<Vec<_>>();
+
+        let read_ahead = ReadAhead::spawn();
+        read_ahead.start(&files, 1, recurse, filter, None, tree.as_path(), &FilterCache::default());
+        read_ahead.wait();
+        read_ahead
+    }
+
+    fn names(dir: &Dir) -> Vec<String> {
+        dir.files(DotFilter::Dotfiles, None).flatten().map(|f| f.name).collect()
+    }
+
+    /// Whether the directory got read ahead, which is when it doesn’t
+    /// notice a file that got added afterwards.
+    fn was_read_ahead(tree: &TempTree, read_ahead: &ReadAhead, path: &str) -> bool {
+        tree.file(&format!("{}/late", path), 1);
+        ! names(&read_ahead.read_dir(&tree.join(path)).unwrap()).iter().any(|name| name == "late")
+    }
+
+    #[test]
+    fn same_as_reading_serially() {
+        let tree = TempTree::new("read-ahead-serial");
+        tree.file("a/1", 1).file("a/2", 2).file("a/b/3", 3).file("a/b/c/4", 4).file("d/5", 5);
+
+        let read_ahead = read_ahead(&tree, &recurse(), &FileFilter::default());
+        for path in &[ "a", "a/b", "a/b/c", "d" ] {
+            let serial = Dir::read_dir(tree.join(path)).unwrap();
+            assert_eq!(names(&read_ahead.read_dir(&tree.join(path)).unwrap()), names(&serial));
+        }
+    }
+
+    #[test]
+    fn stops_at_the_level() {
+        let tree = TempTree::new("read-ahead-level");
+        tree.dir("a/b/c");
+
+        let recurse = RecurseOptions { max_depth: Some(2), .. recurse() };
+        let read_ahead = read_ahead(&tree, &recurse, &FileFilter::default());
+        assert!(was_read_ahead(&tree, &read_ahead, "a"));
+        assert!(! was_read_ahead(&tree, &read_ahead, "a/b"));
+    }
+
+    #[test]
+    fn stops_at_pruned_directories() {
+        let tree = TempTree::new("read-ahead-pruned");
+        tree.dir("a/node_modules/b");
+
+        let recurse = RecurseOptions { prune: vec![ glob::Pattern::new("node_modules").unwrap() ], .. recurse() };
+        let read_ahead = read_ahead(&tree, &recurse, &FileFilter::default());
+        assert!(was_read_ahead(&tree, &read_ahead, "a"));
+        assert!(! was_read_ahead(&tree, &read_ahead, "a/node_modules"));
+        assert!(! was_read_ahead(&tree, &read_ahead, "a/node_modules/b"));
+    }
+
+    #[test]
+    fn stops_at_too_many_entries() {
+        let tree = TempTree::new("read-ahead-entries");
+        tree.dir("a/b").dir("a/c").dir("a/d").dir("e/f");
+
+        let recurse = RecurseOptions { max_entries: Some(2), .. recurse() };
+        let read_ahead = read_ahead(&tree, &recurse, &FileFilter::default());
+        assert!(was_read_ahead(&tree, &read_ahead, "a"));
+        assert!(! was_read_ahead(&tree, &read_ahead, "a/b"));
+        assert!(was_read_ahead(&tree, &read_ahead, "e/f"));
+    }
+
+    #[test]
+    fn keeps_errors() {
+        let tree = TempTree::new("read-ahead-errors");
+        tree.dir("a");
+
+        let dir = Dir::read_dir(tree.path.clone()).unwrap();
+        let files = dir.files(DotFilter::JustFiles, None).flatten().collect::<Vec<_>>();
+        std::fs::remove_dir(tree.join("a")).unwrap();
+
+        let read_ahead = ReadAhead::spawn();
+        read_ahead.start(&files, 1, &recurse(), &FileFilter::default(), None, tree.as_path(), &FilterCache::default());
+        read_ahead.wait();
+
+        // The error came from reading it ahead, as it’s readable now.
+        tree.dir("a");
+        assert_eq!(read_ahead.read_dir(&tree.join("a")).err().map(|e| e.kind()), Some(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn throws_away_what_isnt_listed() {
+        let tree = TempTree::new("read-ahead-done");
+        tree.dir("a/b").dir("c");
+
+        let read_ahead = read_ahead(&tree, &recurse(), &FileFilter::default());
+        read_ahead.done_with(&tree.join("a"));
+        assert!(! was_read_ahead(&tree, &read_ahead, "a/b"));
+        assert!(was_read_ahead(&tree, &read_ahead, "c"));
+    }
+
+    #[test]
+    fn starts_again_for_each_listing() {
+        let tree = TempTree::new("read-ahead-again");
+        tree.dir("a/b");
+
+        let read_ahead = read_ahead(&tree, &recurse(), &FileFilter::default());
+        let dir = Dir::read_dir(tree.join("a")).unwrap();
+        let files = dir.files(DotFilter::JustFiles, None).flatten().collect::<Vec<_>>();
+
+        // The next listing only starts from `b`, so `a` doesn’t get kept.
+        read_ahead.start(&files, 2, &recurse(), &FileFilter::default(), None, tree.as_path(), &FilterCache::default());
+        read_ahead.wait();
+        assert!(! was_read_ahead(&tree, &read_ahead, "a"));
+        assert!(was_read_ahead(&tree, &read_ahead, "a/b"));
+    }
+
+    #[test]
+    fn reads_serially_without_threads() {
+        let tree = TempTree::new("read-ahead-none");
+        tree.dir("a");
+
+        let dir = Dir::read_dir(tree.path.clone()).unwrap();
+        let files = dir.files(DotFilter::JustFiles, None).flatten().collect::<Vec<_>>();
+
+        let read_ahead = ReadAhead::default();
+        read_ahead.start(&files, 1, &recurse(), &FileFilter::default(), None, tree.as_path(), &FilterCache::default());
+        assert!(! was_read_ahead(&tree, &read_ahead, "a"));
+    }
+
+    #[test]
+    fn stats_visible_files() {
+        let tree = TempTree::new("read-ahead-stat");
+        tree.file(".hidden", 1).file("visible", 1);
+
+        let mut dir = Dir::read_dir(tree.path.clone()).unwrap();
+        dir.stat_contents(DotFilter::JustFiles);
+        tree.file(".hidden", 2).file("visible", 2);
+
+        // Only the visible file got statted before it grew.
+        for file in dir.files(DotFilter::Dotfiles, None).flatten() {
+            let expected = if file.name == "visible" { 1 } else { 2 };
+            assert_eq!(file.metadata.len(), expected, "{}", file.name);
+        }
+    }
+
+    #[test]
+    fn stats_dotfiles_with_all() {
+        let tree = TempTree::new("read-ahead-stat-all");
+        tree.file(".hidden", 1).file("visible", 1);
+
+        let mut dir = Dir::read_dir(tree.path.clone()).unwrap();
+        dir.stat_contents(DotFilter::Dotfiles);
+        tree.file(".hidden", 2).file("visible", 2);
+
+        for file in dir.files(DotFilter::Dotfiles, None).flatten() {
+            assert_eq!(file.metadata.len(), 1, "{}", file.name);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Trees of files for the tests that need to read real directories.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+
+/// A directory made for one test, which gets deleted again, along with
+/// everything in it, once the test is done with it.
+pub struct TempTree {
+    pub path: PathBuf,
+}
+
+impl TempTree {
+
+    /// Makes a new, empty directory, named after the test so tests running
+    /// at the same time don’t get in each other’s way.
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("exa-test-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// Makes a directory, and any of its parents, inside the tree.
+    pub fn dir(&self, path: &str) -> &Self {
+        fs::create_dir_all(self.path.join(path)).unwrap();
+        self
+    }
+
+    /// Makes a file inside the tree with the given number of bytes in it,
+    /// making its parent directories first.
+    pub fn file(&self, path: &str, size: usize) -> &Self {
+        let path = self.path.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; size]).unwrap();
+        self
+    }
+
+    /// Makes a hard link to a file inside the tree.
+    pub fn hard_link(&self, from: &str, to: &str) -> &Self {
+        fs::hard_link(self.path.join(from), self.path.join(to)).unwrap();
+        self
+    }
+
+    /// Returns the path to something inside the tree.
+    pub fn join(&self, path: &str) -> PathBuf {
+        self.path.join(path)
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,471 @@
+//! Adding up the sizes of everything inside a directory, for
+//! `--total-size`.
+//!
+//! The directories that need totals get walked through together on a
+//! pool of threads, without following symlinks. The threads share one
+//! stack of directories to read, so a single large directory gets read
+//! in parallel as much as a listing of lots of small ones. Files that
+//! the filter would hide with `--ignore-glob`, `--ignore-regex` or
+//! `--git-ignore` don’t get counted, but hidden files do, as they take
+//! up space all the same. As with the listing, patterns with a `/` in
+//! them get tested against each file’s path relative to the directory
+//! being listed.
+//!
+//! A directory’s total gets added to its parent’s once everything
+//! inside it has been added up, and the total of every directory on the
+//! way gets remembered by the listing’s cache, so when a tree gets
+//! listed, the directories further down it don’t get added up again at
+//! each level.
+//!
+//! A file with more than one hard link only gets counted once for each
+//! directory that needs a total, in the same way as `du`: the first
+//! directory underneath it that the file gets found in is the one that
+//! counts it. So no total counts the same file twice, but which of the
+//! directories further down a tree counts it depends on which thread got
+//! there first. A file linked from two directories in the same listing
+//! still counts towards both of them.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+use log::*;
+use scoped_threadpool::Pool;
+
+use crate::fs::File;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::ignore::IgnoreCache;
+
+
+/// Which size of each file gets added up.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TotalSize {
+
+    /// The number of bytes in each file, which is the size that gets shown
+    /// for files on their own.
+    Apparent,
+
+    /// The space that each file takes up on disk, in blocks. This can be
+    /// less than the apparent size for sparse or compressed files, and more
+    /// for small ones.
+    DiskUsage,
+}
+
+
+/// Fills in the totals for every directory in the list that doesn’t have
+/// one yet, if totals have been asked for, adding up the ones that the
+/// listing’s cache doesn’t have on the threads in the pool. The `.` and
+/// `..` entries don’t get totals, as adding up the parent directory could
+/// take a very long time. The root is the directory being listed.
+pub fn fill_total_sizes(pool: &mut Pool, files: &mut [File<'_>], root: &Path, filter: &FileFilter, ignore: Option<&IgnoreCache>, cache: &FilterCache) {
+    let kind = match filter.total_size {
+        Some(kind)  => kind,
+        None        => return,
+    };
+
+    let mut state = WalkState::default();
+    for file in files.iter_mut().filter(|f| f.is_directory() && ! f.is_all_all && f.total_size.is_none()) {
+        file.total_size = cache.totals.get(&file.path);
+        if file.total_size.is_none() {
+            state.add_top(file.path.clone());
+        }
+    }
+
+    if state.queue.is_empty() {
+        return;
+    }
+
+    let walk = Walk { kind, root, filter, ignore, cache, state: Mutex::new(state), changed: Condvar::new() };
+    let n_threads = pool.thread_count();
+    pool.scoped(|scoped| {
+        for _ in 0 .. n_threads {
+            scoped.execute(|| walk.work());
+        }
+    });
+
+    for file in files.iter_mut().filter(|f| f.is_directory() && ! f.is_all_all && f.total_size.is_none()) {
+        file.total_size = cache.totals.get(&file.path);
+    }
+}
+
+
+/// The totals of the directories that have been added up so far in a
+/// listing, by their paths, which are shared with any copies.
+#[derive(Default, Clone)]
+pub struct Totals {
+    dirs: Arc<Mutex<HashMap<PathBuf, u64>>>,
+}
+
+impl Totals {
+    fn get(&self, path: &Path) -> Option<u64> {
+        self.dirs.lock().unwrap().get(path).copied()
+    }
+
+    fn insert(&self, path: PathBuf, total: u64) {
+        self.dirs.lock().unwrap().insert(path, total);
+    }
+}
+
+
+/// The directories being added up, along with the options for which files
+/// get counted.
+struct Walk<'a> {
+    kind: TotalSize,
+    root: &'a Path,
+    filter: &'a FileFilter,
+    ignore: Option<&'a IgnoreCache>,
+    cache: &'a FilterCache,
+    state: Mutex<WalkState>,
+
+    /// Signalled whenever a directory has been read, as there could be
+    /// more to read inside it, or nothing left to read at all.
+    changed: Condvar,
+}
+
+/// What the threads adding up the directories share.
+#[derive(Default)]
+struct WalkState {
+
+    /// Every directory that’s been found, by the index the others refer
+    /// to them with.
+    dirs: Vec<DirNode>,
+
+    /// The directories waiting to be read, with the next one on the end.
+    queue: Vec<usize>,
+
+    /// The files with more than one link that have been counted, by device
+    /// and inode, with one set for each directory that needs a total.
+    linked: Vec<HashSet<(u64, u64)>>,
+
+    /// How many threads are reading a directory right now, any of which
+    /// could find more directories to read.
+    reading: usize,
+}
+
+/// A directory that’s being added up.
+struct DirNode {
+    path: PathBuf,
+
+    /// The directory it’s inside, which it gets added to once it’s done.
+    parent: Option<usize>,
+
+    /// Which set of linked files it counts towards.
+    top: usize,
+
+    /// What it adds up to so far.
+    total: u64,
+
+    /// How many of the directories inside it haven’t been added up yet,
+    /// plus one until it’s been read itself.
+    waiting: usize,
+}
+
+/// What was found by reading a directory.
+#[derive(Default)]
+struct Contents {
+
+    /// The sizes of the files with only one link, added together.
+    single: u64,
+
+    /// The files with more than one link, by device and inode, and their
+    /// sizes.
+    linked: Vec<((u64, u64), u64)>,
+
+    /// The directories inside it, which still need to be read.
+    subdirs: Vec<PathBuf>,
+}
+
+impl WalkState {
+
+    /// Adds a directory that needs a total, with its own set of linked
+    /// files.
+    fn add_top(&mut self, path: PathBuf) {
+        let top = self.linked.len();
+        self.linked.push(HashSet::new());
+        self.add_dir(path, None, top);
+    }
+
+    fn add_dir(&mut self, path: PathBuf, parent: Option<usize>, top: usize) {
+        self.queue.push(self.dirs.len());
+        self.dirs.push(DirNode { path, parent, top, total: 0, waiting: 1 });
+    }
+
+    /// Adds what was found inside a directory to its total, queueing up the
+    /// directories inside it, and finishes it if it has none.
+    fn add_contents(&mut self, index: usize, contents: Contents, cache: &FilterCache) {
+        let top = self.dirs[index].top;
+        let linked = &mut self.linked[top];
+        let linked_total = contents.linked.into_iter()
+                                   .filter(|(inode, _)| linked.insert(*inode))
+                                   .map(|(_, size)| size)
+                                   .sum::<u64>();
+
+        let dir = &mut self.dirs[index];
+        dir.total += contents.single + linked_total;
+        dir.waiting += contents.subdirs.len();
+        dir.waiting -= 1;
+
+        for path in contents.subdirs {
+            self.add_dir(path, Some(index), top);
+        }
+
+        self.finish(index, cache);
+    }
+
+    /// Remembers the totals of the directory and the ones above it that
+    /// have nothing left to wait for, adding each to its parent’s.
+    fn finish(&mut self, mut index: usize, cache: &FilterCache) {
+        while self.dirs[index].waiting == 0 {
+            let dir = &mut self.dirs[index];
+            let total = dir.total;
+            cache.totals.insert(std::mem::take(&mut dir.path), total);
+
+            match dir.parent {
+                Some(parent) => {
+                    let parent_dir = &mut self.dirs[parent];
+                    parent_dir.total += total;
+                    parent_dir.waiting -= 1;
+                    index = parent;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl<'a> Walk<'a> {
+
+    /// Reads directories off the stack until there are none left, and none
+    /// being read that could add any more.
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(index) = state.queue.pop() {
+                let path = state.dirs[index].path.clone();
+                state.reading += 1;
+                drop(state);
+
+                let contents = self.read(&path);
+
+                state = self.state.lock().unwrap();
+                state.reading -= 1;
+                state.add_contents(index, contents, self.cache);
+                self.changed.notify_all();
+            }
+            else if state.reading == 0 {
+                return;
+            }
+            else {
+                state = self.changed.wait(state).unwrap();
+            }
+        }
+    }
+
+    fn read(&self, path: &Path) -> Contents {
+        let mut contents = Contents::default();
+
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Error reading {:?} to add up its size: {}", path, e);
+                return contents;
+            }
+        };
+
+        let ignores = self.ignore.map(|i| i.for_dir(path));
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            // This doesn’t follow symlinks, so they can’t cause loops
+            let metadata = match entry.metadata() {
+                Ok(m)   => m,
+                Err(e)  => { debug!("Error statting {:?} to add up its size: {}", path, e); continue; }
+            };
+
+            let relative = path.strip_prefix(self.root).unwrap_or(&path);
+            if self.filter.ignore_patterns.matches(&name, relative)
+                || ignores.as_ref().map_or(false, |i| i.is_ignored(&name, || metadata.is_dir()))
+            {
+                continue;
+            }
+
+            if metadata.is_dir() {
+                contents.subdirs.push(path);
+            }
+            else {
+                self.add_file(&mut contents, &metadata);
+            }
+        }
+
+        contents
+    }
+
+    #[cfg(unix)]
+    fn add_file(&self, contents: &mut Contents, metadata: &fs::Metadata) {
+        let size = match self.kind {
+            TotalSize::Apparent   => metadata.len(),
+            TotalSize::DiskUsage  => metadata.blocks() * 512,
+        };
+
+        if metadata.nlink() <= 1 {
+            contents.single += size;
+        }
+        else {
+            contents.linked.push(((metadata.dev(), metadata.ino()), size));
+        }
+    }
+
+    #[cfg(windows)]
+    fn add_file(&self, contents: &mut Contents, metadata: &fs::Metadata) {
+        contents.single += metadata.len();
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::filter::NamePatterns;
+    use crate::fs::temp_tree::TempTree;
+
+    fn total(tree: &TempTree, path: &str, filter: &FileFilter) -> Option<u64> {
+        total_with_cache(tree, path, filter, &FilterCache::default())
+    }
+
+    fn total_with_cache(tree: &TempTree, path: &str, filter: &FileFilter, cache: &FilterCache) -> Option<u64> {
+        totals(tree, &[ path ], filter, cache)[0]
+    }
+
+    /// Adds up the directories at the given paths all at once.
+    fn totals(tree: &TempTree, paths: &[&str], filter: &FileFilter, cache: &FilterCache) -> Vec<Option<u64>> {
+        let mut files = paths.iter()
+                             .map(|path| File::from_args(tree.join(path), None, None).unwrap())
+                             .collect::<Vec<_>>();
+
+        fill_total_sizes(&mut Pool::new(4), &mut files, tree.as_path(), filter, None, cache);
+        files.into_iter().map(|f| f.total_size).collect()
+    }
+
+    fn filter() -> FileFilter {
+        FileFilter { total_size: Some(TotalSize::Apparent), .. FileFilter::default() }
+    }
+
+    #[test]
+    fn adds_up_everything() {
+        let tree = TempTree::new("total-everything");
+        tree.file("a", 100).file("sub/b", 20).file("sub/deeper/c", 3).file("sub/.hidden", 4000);
+
+        let filter = filter();
+        assert_eq!(total(&tree, "sub", &filter), Some(4023));
+        assert_eq!(total(&tree, "", &filter), Some(4123));
+    }
+
+    #[test]
+    fn not_without_the_option() {
+        let tree = TempTree::new("total-off");
+        tree.file("sub/a", 100);
+
+        assert_eq!(total(&tree, "sub", &FileFilter::default()), None);
+    }
+
+    #[test]
+    fn not_for_files() {
+        let tree = TempTree::new("total-files");
+        tree.file("a", 100);
+
+        assert_eq!(total(&tree, "a", &filter()), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hard_links_count_once() {
+        let tree = TempTree::new("total-links");
+        tree.file("one/a", 100).hard_link("one/a", "one/b")
+            .dir("two").hard_link("one/a", "two/c")
+            .file("two/d", 7);
+
+        let filter = filter();
+        assert_eq!(total(&tree, "one", &filter), Some(100));
+        assert_eq!(total(&tree, "two", &filter), Some(107));
+        assert_eq!(total(&tree, "", &filter), Some(107));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hard_links_count_for_each_directory_listed() {
+        let tree = TempTree::new("total-links-listed");
+        tree.file("one/a", 100).dir("two").hard_link("one/a", "two/b");
+
+        // Each directory in the listing has its own set of linked files.
+        let totals = totals(&tree, &[ "one", "two" ], &filter(), &FilterCache::default());
+        assert_eq!(totals, vec![ Some(100), Some(100) ]);
+    }
+
+    #[test]
+    fn adds_up_wide_and_deep_trees() {
+        let tree = TempTree::new("total-wide");
+        for n in 0 .. 50 {
+            tree.file(&format!("top/{}/a", n), 1).file(&format!("top/{}/deeper/b", n), 2);
+        }
+
+        let cache = FilterCache::default();
+        assert_eq!(total_with_cache(&tree, "top", &filter(), &cache), Some(150));
+        assert_eq!(total_with_cache(&tree, "top/7", &filter(), &cache), Some(3));
+        assert_eq!(total_with_cache(&tree, "top/7/deeper", &filter(), &cache), Some(2));
+    }
+
+    #[test]
+    fn remembers_totals_further_down() {
+        let tree = TempTree::new("total-remembered");
+        tree.file("sub/deeper/a", 100);
+
+        let cache = FilterCache::default();
+        assert_eq!(total_with_cache(&tree, "", &filter(), &cache), Some(100));
+
+        // The directory underneath was added up along with its parent, so
+        // it doesn’t get read again while the same listing goes on.
+        std::fs::write(tree.join("sub/deeper/b"), vec![b'x'; 50]).unwrap();
+        assert_eq!(total_with_cache(&tree, "sub/deeper", &filter(), &cache), Some(100));
+        assert_eq!(total(&tree, "sub/deeper", &filter()), Some(150));
+    }
+
+    #[test]
+    fn skips_ignored_files() {
+        let tree = TempTree::new("total-ignored");
+        tree.file("sub/a.txt", 100).file("sub/b.tmp", 20).file("sub/cache/c.txt", 3);
+
+        let (ignore_patterns, _) = NamePatterns::parse_from_iter(vec![ "*.tmp", "cache" ]);
+        let filter = FileFilter { ignore_patterns, .. filter() };
+        assert_eq!(total(&tree, "sub", &filter), Some(100));
+    }
+
+    #[test]
+    fn skips_ignored_paths_from_the_root() {
+        let tree = TempTree::new("total-ignored-paths");
+        tree.file("sub/a.tmp", 100).file("sub/deeper/b.tmp", 20).file("c.tmp", 3);
+
+        let (ignore_patterns, _) = NamePatterns::parse_from_iter(vec![ "sub/*.tmp" ]);
+        let filter = FileFilter { ignore_patterns, .. filter() };
+        assert_eq!(total(&tree, "sub", &filter), Some(20));
+        assert_eq!(total(&tree, "", &filter), Some(23));
+    }
+
+    #[test]
+    fn counts_pruned_directories() {
+        let tree = TempTree::new("total-pruned");
+        tree.file("sub/a", 100).file("sub/node_modules/b", 20);
+
+        // Pruning only stops the listing from going inside a directory, so
+        // it still gets a total, and still counts towards its parent’s.
+        let filter = filter();
+        assert_eq!(total(&tree, "sub", &filter), Some(120));
+        assert_eq!(total(&tree, "sub/node_modules", &filter), Some(20));
+    }
+}
@@ -1,296 +1,508 @@
 //! Tests for various types of file (video, image, compressed, etc).
 //!
-//! Currently this is dependent on the file’s name and extension, because
-//! those are the only metadata that we have access to without reading the
-//! file’s contents.
+//! This is mostly dependent on the file’s name and extension, because those
+//! are the only metadata that we have access to without reading the file’s
+//! contents. With `--magic`, the first few bytes of each regular file get
+//! read as well, which picks out binaries, scripts and other files that
+//! don’t have an extension to go on.
+//!
+//! Each type of file is described by a **rule**, which matches glob or
+//! regex patterns against a file’s name, its extension, the name of a
+//! directory, or the kind of file its contents say it is, and says which
+//! style and icon it should get. The built-in
+//! rules below are the default ruleset; the user can add their own in front
+//! of them with a rules file.
 
 use ansi_term::Style;
+use regex::RegexSet;
+
+use lazy_static::lazy_static;
+use log::*;
 
 use crate::fs::File;
-use crate::output::icons::FileIcon;
+use crate::output::icons::{FileIcon, Icons};
 use crate::theme::FileColours;
-use regex::RegexSet;
 
-use lazy_static::lazy_static;
 
-#[derive(Debug, Default, PartialEq, Eq)]
-pub struct FileExtensions;
-
-impl FileExtensions {
-
-    /// An “immediate” file is something that can be run or activated somehow
-    /// in order to kick off the build of a project. It’s usually only present
-    /// in directories full of source code.
-    #[allow(clippy::case_sensitive_file_extension_comparisons)]
-    fn is_immediate(&self, file: &File<'_>) -> bool {
-        file.name.ends_with(".ninja") ||
-        file.name_is_one_of( &[
-            "Makefile", "Cargo.toml", "SConstruct", "CMakeLists.txt",
-            "build.gradle", "pom.xml", "Rakefile", "package.json", "Gruntfile.js",
-            "Gruntfile.coffee", "BUILD", "BUILD.bazel", "WORKSPACE", "build.xml", "Podfile",
-            "webpack.config.js", "meson.build", "composer.json", "RoboFile.php", "PKGBUILD",
-            "Justfile", "Procfile", "Dockerfile", "Containerfile", "Vagrantfile", "Brewfile",
-            "Gemfile", "Pipfile", "build.sbt", "mix.exs", "bsconfig.json", "tsconfig.json",
-        ])
+/// One rule for classifying files, in the order they get tried.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Rule {
+
+    /// The name of the class of files this rule picks out, such as
+    /// “image” or “terraform”.
+    pub class: String,
+
+    /// The patterns a file can match for this rule to apply to it. Only one
+    /// of them has to match.
+    pub matchers: Vec<Matcher>,
+
+    /// The style to paint the file’s name with, if any.
+    pub style: Option<Style>,
+
+    /// The icon to display next to the file’s name, if any.
+    pub icon: Option<char>,
+}
+
+/// A pattern that a file has to match for a rule to apply to it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Matcher {
+
+    /// A glob, which has to match the whole of the target.
+    Glob(Target, String),
+
+    /// A regular expression, which can match anywhere in the target unless
+    /// it gets anchored with `^` or `$`.
+    Regex(Target, String),
+
+    /// Files that have a source file next to them, such as `foo.js` next to
+    /// `foo.coffee`. (See `File::get_source_files`)
+    HasSource,
+}
+
+/// Which part of a file a pattern gets matched against.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Target {
+
+    /// The whole name of a file that isn’t a directory.
+    Name,
+
+    /// The extension of a file that isn’t a directory.
+    Extension,
+
+    /// The name of a directory.
+    Directory,
+
+    /// The name of the kind of file that a file’s contents say it is, such
+    /// as `elf` or `python`, for files that have been read with `--magic`.
+    /// (See `Magic::name`)
+    Magic,
+}
+
+impl Rule {
+    pub fn new(class: &str) -> Self {
+        Self { class: class.into(), matchers: Vec::new(), style: None, icon: None }
     }
 
-    fn is_image(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "png", "jfi", "jfif", "jif", "jpe", "jpeg", "jpg", "gif", "bmp",
-            "tiff", "tif", "ppm", "pgm", "pbm", "pnm", "webp", "raw", "arw",
-            "svg", "stl", "eps", "dvi", "ps", "cbr", "jpf", "cbz", "xpm",
-            "ico", "cr2", "orf", "nef", "heif", "avif", "jxl", "j2k", "jp2",
-            "j2c", "jpx",
-        ])
+    fn style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
     }
 
-    fn is_video(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "avi", "flv", "m2v", "m4v", "mkv", "mov", "mp4", "mpeg",
-            "mpg", "ogm", "ogv", "vob", "wmv", "webm", "m2ts", "heic",
-        ])
+    fn icon(mut self, icon: char) -> Self {
+        self.icon = Some(icon);
+        self
     }
 
-    fn is_music(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "aac", "m4a", "mp3", "ogg", "wma", "mka", "opus",
-        ])
+    fn globs(mut self, target: Target, globs: &[&str]) -> Self {
+        self.matchers.extend(globs.iter().map(|g| Matcher::Glob(target, (*g).into())));
+        self
     }
 
-    // Lossless music, rather than any other kind of data...
-    fn is_lossless(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "alac", "ape", "flac", "wav",
-        ])
+    fn regexes(mut self, target: Target, regexes: &[&str]) -> Self {
+        self.matchers.extend(regexes.iter().map(|r| Matcher::Regex(target, (*r).into())));
+        self
     }
 
-    fn is_crypto(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "asc", "enc", "gpg", "pgp", "sig", "signature", "pfx", "p12",
-        ])
+    fn has_source(mut self) -> Self {
+        self.matchers.push(Matcher::HasSource);
+        self
     }
 
-    fn is_document(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "djvu", "doc", "docx", "dvi", "eml", "eps", "fotd", "key",
-            "keynote", "numbers", "odp", "odt", "pages", "pdf", "ppt",
-            "pptx", "rtf", "xls", "xlsx", 
-            "txt", "md", "log", "changelog"
-        ]) || file.name_is_one_of( &[
-            "readme", "LICENSE", "LICENCE"
-        ])
+    /// Whether this rule picks out directories, rather than other files.
+    pub fn is_for_directories(&self) -> bool {
+        self.matchers.iter().any(|m| matches!(m, Matcher::Glob(Target::Directory, _) | Matcher::Regex(Target::Directory, _)))
     }
+}
+
+
+/// The rules that exa uses when the user hasn’t got any of their own, or
+/// for files that their own rules don’t match.
+#[allow(clippy::too_many_lines)]
+pub fn builtin_rules() -> Vec<Rule> {
+    use ansi_term::Colour::*;
+    use self::Target::*;
+
+    vec![
+        Rule::new("temp").style(Fixed(244).normal())
+            .globs(Name, &[ "*~", "#*#" ])
+            .globs(Extension, &[ "tmp", "swp", "swo", "swn", "bak", "bkp", "bk" ]),
+
+        // An “immediate” file is something that can be run or activated
+        // somehow in order to kick off the build of a project. It’s usually
+        // only present in directories full of source code.
+        Rule::new("immediate").style(Fixed(1).bold().underline())
+            .globs(Name, &[
+                "*.ninja",
+                "Makefile", "Cargo.toml", "SConstruct", "CMakeLists.txt",
+                "build.gradle", "pom.xml", "Rakefile", "package.json", "Gruntfile.js",
+                "Gruntfile.coffee", "BUILD", "BUILD.bazel", "WORKSPACE", "build.xml", "Podfile",
+                "webpack.config.js", "meson.build", "composer.json", "RoboFile.php", "PKGBUILD",
+                "Justfile", "Procfile", "Dockerfile", "Containerfile", "Vagrantfile", "Brewfile",
+                "Gemfile", "Pipfile", "build.sbt", "mix.exs", "bsconfig.json", "tsconfig.json",
+            ]),
+
+        Rule::new("image").style(Fixed(37).normal()).icon(Icons::Image.value())
+            .globs(Extension, &[
+                "png", "jfi", "jfif", "jif", "jpe", "jpeg", "jpg", "gif", "bmp",
+                "tiff", "tif", "ppm", "pgm", "pbm", "pnm", "webp", "raw", "arw",
+                "svg", "stl", "eps", "dvi", "ps", "cbr", "jpf", "cbz", "xpm",
+                "ico", "cr2", "orf", "nef", "heif", "avif", "jxl", "j2k", "jp2",
+                "j2c", "jpx",
+            ]),
+
+        Rule::new("video").style(Fixed(135).normal()).icon(Icons::Video.value())
+            .globs(Extension, &[
+                "avi", "flv", "m2v", "m4v", "mkv", "mov", "mp4", "mpeg",
+                "mpg", "ogm", "ogv", "vob", "wmv", "webm", "m2ts", "heic",
+            ]),
+
+        Rule::new("music").style(Fixed(92).normal()).icon(Icons::Audio.value())
+            .globs(Extension, &[
+                "aac", "m4a", "mp3", "ogg", "wma", "mka", "opus",
+            ]),
+
+        // Lossless music, rather than any other kind of data...
+        Rule::new("lossless").style(Fixed(93).normal()).icon(Icons::Audio.value())
+            .globs(Extension, &[
+                "alac", "ape", "flac", "wav",
+            ]),
+
+        Rule::new("crypto").style(Fixed(109).normal())
+            .globs(Extension, &[
+                "asc", "enc", "gpg", "pgp", "sig", "signature", "pfx", "p12",
+            ]),
+
+        Rule::new("document").style(Fixed(187).normal())
+            .globs(Extension, &[
+                "djvu", "doc", "docx", "dvi", "eml", "eps", "fotd", "key",
+                "keynote", "numbers", "odp", "odt", "pages", "pdf", "ppt",
+                "pptx", "rtf", "xls", "xlsx",
+                "txt", "md", "log", "changelog",
+            ])
+            .globs(Name, &[
+                "readme", "LICENSE", "LICENCE",
+            ]),
+
+        Rule::new("compressed").style(Red.normal())
+            .globs(Extension, &[
+                "zip", "tar", "Z", "z", "gz", "bz2", "a", "ar", "7z",
+                "iso", "dmg", "tc", "rar", "par", "tgz", "xz", "txz",
+                "lz", "tlz", "lzma", "deb", "rpm", "zst", "lz4", "cpio",
+            ]),
+
+        Rule::new("compiled").style(Fixed(137).normal())
+            .globs(Extension, &[ "class", "elc", "hi", "o", "pyc", "zwc", "ko" ])
+            .has_source(),
+
+        Rule::new("pretty_data").style(Fixed(178).normal())
+            .globs(Extension, &[
+                "json", "xml", "hx", "hxml", "ctp", "haml",
+                "html", "slim", "sql",
+            ]),
+
+        Rule::new("script").style(Fixed(173).normal()).icon('\u{ebc7}')
+            .regexes(Extension, &[ r".*bash.*", r".*zsh.*", r"^sh_", r"_sh$" ]),
+
+        Rule::new("script").style(Fixed(173).normal())
+            .globs(Extension, &[
+                "bashrc", "zshrc", "sh", "zsh", "bash",
+                "profile", "bash_profile", "fish",
+            ]),
+
+        Rule::new("config").style(Fixed(65).normal())
+            .globs(Extension, &[
+                "toml", "ini", "conf", "config",
+                "yml", "gitignore", "gitmodules",
+            ])
+            .globs(Name, &[ "conf", "config" ])
+            .regexes(Extension, &[ r".*ignore", r".*settings.*", r".*theme.*" ])
+            .regexes(Name, &[ r".*settings.*", r".*theme.*" ]),
+
+        Rule::new("vim").style(Fixed(71).normal()).icon('\u{e62b}')
+            .regexes(Name, &[ r".*vim.*" ])
+            .regexes(Extension, &[ r".*vim.*" ]),
+
+        Rule::new("language").style(Fixed(75).normal())
+            .globs(Extension, &[
+                "as", "ai", "applescript", "scpt", "cs", "csproj", "cpp",
+                "hpp", "c", "h", "clj", "coffee", "css", "erb", "less",
+                "sass", "liquid", "lua", "scss", "styl", "d", "ex",
+                "erl", "eot", "otf", "ttf", "woff", "go", "dot", "gv",
+                "js", "hbs", "hs", "jade", "java", "jsp", "jl", "lisp",
+                "matlab", "mustache", "m", "mm", "ml", "pl", "php",
+                "psd", "pug", "py", "pp", "r", "rails", "rb", "rs", "scala", "gradle",
+                "swift", "tcl", "tex", "textile", "vb",
+            ]),
+
+        // Files that only get picked out by their contents with `--magic`,
+        // which come after everything that matches a name or an extension.
+        Rule::new("binary").style(Fixed(137).normal()).icon('\u{f471}')
+            .globs(Magic, &[ "elf", "mach-o" ]),
+
+        Rule::new("script").style(Fixed(173).normal()).icon('\u{ebc7}')
+            .globs(Magic, &[ "sh", "bash", "zsh", "fish", "script" ]),
+
+        Rule::new("language").style(Fixed(75).normal()).icon('\u{e606}')
+            .globs(Magic, &[ "python" ]),
+
+        Rule::new("language").style(Fixed(75).normal()).icon('\u{e74e}')
+            .globs(Magic, &[ "node" ]),
+
+        Rule::new("language").style(Fixed(75).normal()).icon('\u{e67e}')
+            .globs(Magic, &[ "perl" ]),
+
+        Rule::new("language").style(Fixed(75).normal()).icon('\u{e21e}')
+            .globs(Magic, &[ "ruby" ]),
+
+        Rule::new("image").style(Fixed(37).normal()).icon(Icons::Image.value())
+            .globs(Magic, &[ "png", "jpeg", "gif", "webp" ]),
+
+        Rule::new("compressed").style(Red.normal()).icon('\u{f410}')
+            .globs(Magic, &[ "zip", "gzip", "bzip2", "xz", "zstd", "7z", "tar" ]),
+
+        Rule::new("document").style(Fixed(187).normal()).icon('\u{f1c1}')
+            .globs(Magic, &[ "pdf" ]),
+
+        Rule::new("file").style(Fixed(244).bold())
+            .globs(Name, &[ "*" ]),
+
+        Rule::new("config_folder").style(Fixed(65).bold())
+            .globs(Directory, &[ "docker" ]),
+
+        Rule::new("language_folder").style(Fixed(75).bold())
+            .globs(Directory, &[ "src", "lib" ])
+            .regexes(Directory, &[ r".*test.*" ]),
+
+        Rule::new("exe_folder").style(Fixed(173).bold())
+            .globs(Directory, &[ "target", "bin" ])
+            .regexes(Directory, &[ r".*script.*" ]),
+
+        Rule::new("document_folder").style(Fixed(187).bold())
+            .globs(Directory, &[ "doc" ])
+            .regexes(Directory, &[ r"man.*" ]),
+
+        Rule::new("folder").style(Fixed(244).bold())
+            .globs(Directory, &[ "*" ]),
+    ]
+}
+
+
+lazy_static! {
+    static ref BUILTIN: Ruleset = Ruleset::new(builtin_rules())
+        .expect("Built-in file type rules should compile");
+}
 
-    fn is_config(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref SET_EXT: RegexSet = RegexSet::new(&[
-                r".*ignore",
-                r".*settings.*",
-                r".*theme.*",
-                r".*settings.*",
-                r".*theme.*",
-            ]).unwrap();
-            static ref SET_NAME: RegexSet = RegexSet::new(&[
-                r".*settings.*",
-                r".*theme.*",
-                r".*settings.*",
-                r".*theme.*",
-            ]).unwrap();
+
+/// A list of rules, with all their patterns compiled into one regex set for
+/// each target, so a file only has to be checked once per target rather
+/// than once per rule.
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    rules: Vec<Rule>,
+    names: TargetSet,
+    extensions: TargetSet,
+    directories: TargetSet,
+    magics: TargetSet,
+}
+
+/// The patterns for one target, along with the index of the rule each of
+/// them came from.
+#[derive(Debug, Clone)]
+struct TargetSet {
+    set: RegexSet,
+    owners: Vec<usize>,
+}
+
+impl Ruleset {
+
+    /// Compiles the given rules, which get tried in order.
+    pub fn new(rules: Vec<Rule>) -> Result<Self, regex::Error> {
+        let mut patterns = [ Vec::new(), Vec::new(), Vec::new(), Vec::new() ];
+        let mut owners = [ Vec::new(), Vec::new(), Vec::new(), Vec::new() ];
+
+        for (index, rule) in rules.iter().enumerate() {
+            for matcher in &rule.matchers {
+                let (target, regex) = match matcher {
+                    Matcher::Glob(target, glob)    => (*target, glob_to_regex(glob)),
+                    Matcher::Regex(target, regex)  => (*target, regex.clone()),
+                    Matcher::HasSource             => continue,
+                };
+
+                patterns[target as usize].push(regex);
+                owners[target as usize].push(index);
+            }
         }
-        file.extension_is_one_of( &[
-            "toml", "ini", "conf", "config",
-            "yml", "gitignore", "gitmodules",
-        ]) || file.name_is_one_of( &[
-            "conf", "config"
-        ]) || file.extension_matches_set(&SET_EXT)
-           || file.name_matches_set(&SET_NAME)
+
+        let [ name_owners, extension_owners, directory_owners, magic_owners ] = owners;
+        let [ name_patterns, extension_patterns, directory_patterns, magic_patterns ] = patterns;
+
+        Ok(Self {
+            names:       TargetSet { set: RegexSet::new(name_patterns)?,      owners: name_owners },
+            extensions:  TargetSet { set: RegexSet::new(extension_patterns)?, owners: extension_owners },
+            directories: TargetSet { set: RegexSet::new(directory_patterns)?, owners: directory_owners },
+            magics:      TargetSet { set: RegexSet::new(magic_patterns)?,     owners: magic_owners },
+            rules,
+        })
     }
 
-    fn is_compressed(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "zip", "tar", "Z", "z", "gz", "bz2", "a", "ar", "7z",
-            "iso", "dmg", "tc", "rar", "par", "tgz", "xz", "txz",
-            "lz", "tlz", "lzma", "deb", "rpm", "zst", "lz4", "cpio",
-        ])
+    /// The user’s own rules, followed by the built-in ones. If they don’t
+    /// compile, only the built-in ones get used.
+    pub fn with_builtins(mut user_rules: Vec<Rule>) -> Self {
+        if user_rules.is_empty() {
+            return Self::builtin();
+        }
+
+        user_rules.extend(builtin_rules());
+        Self::new(user_rules).unwrap_or_else(|e| {
+            warn!("Couldn't compile file type rules: {}", e);
+            Self::builtin()
+        })
     }
 
-    fn is_temp(&self, file: &File<'_>) -> bool {
-        file.name.ends_with('~')
-            || (file.name.starts_with('#') && file.name.ends_with('#'))
-            || file.extension_is_one_of( &[ "tmp", "swp", "swo", "swn", "bak", "bkp", "bk" ])
-            || file.extension_is_one_of( &[ "tmp", "swp", "swo", "swn", "bak", "bkp", "bk" ])
+    /// A copy of the built-in ruleset. The patterns only get compiled once,
+    /// and compiled regex sets are cheap to clone.
+    pub fn builtin() -> Self {
+        BUILTIN.clone()
     }
 
-    fn is_compiled(&self, file: &File<'_>) -> bool {
-        if file.extension_is_one_of( &[ "class", "elc", "hi", "o", "pyc", "zwc", "ko" ]) {
-            true
-        }
-        else if let Some(dir) = file.parent_dir {
-            file.get_source_files().iter().any(|path| dir.contains(path))
+    /// Returns the first rule that matches the file and has the field that
+    /// `pick` extracts, along with that field.
+    fn find<T>(&self, file: &File<'_>, as_dir: bool, pick: impl Fn(&Rule) -> Option<T>) -> Option<T> {
+        let mut matched = vec![ false; self.rules.len() ];
+
+        if as_dir {
+            self.directories.mark(&file.name, &mut matched);
         }
         else {
-            false
+            self.names.mark(&file.name, &mut matched);
+            if let Some(ext) = &file.ext {
+                self.extensions.mark(ext, &mut matched);
+            }
+            if let Some(magic) = file.magic {
+                self.magics.mark(magic.name(), &mut matched);
+            }
         }
+
+        self.rules.iter().zip(matched)
+            .filter(|(rule, matched)| *matched || (! as_dir && rule.matchers.contains(&Matcher::HasSource) && has_source(file)))
+            .find_map(|(rule, _)| pick(rule))
     }
+}
 
-    fn is_script(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref SET_SH_EXT: RegexSet = RegexSet::new(&[
-                r".*bash.*",
-                r".*zsh.*",
-                r"^sh_",
-                r"_sh$",
-            ]).unwrap();
+impl TargetSet {
+    fn mark(&self, text: &str, matched: &mut [bool]) {
+        for index in &self.set.matches(text) {
+            matched[self.owners[index]] = true;
         }
-        file.extension_is_one_of( &[
-            "bashrc", "zshrc", "sh", "zsh", "bash", 
-            "profile", "bash_profile", "fish",
-        ]) || file.extension_matches_set( &SET_SH_EXT )
-        
     }
+}
 
-    fn is_language(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "as", "ai", "applescript", "scpt", "cs", "csproj", "cpp", 
-            "hpp", "c", "h", "clj", "coffee", "css", "erb", "less",
-            "sass", "erb", "liquid", "lua", "scss", "styl", "d", "ex",
-            "erl", "eot", "otf", "ttf", "woff", "go", "dot", "gv",
-            "js", "hbs", "hs", "jade", "java", "jsp", "jl", "lisp",
-            "matlab", "js", "mustache", "m", "mm", "ml", "pl", "php",
-            "psd", "pug", "py", "pp", "r", "rails", "rb", "rs", "scala", "gradle",
-            "swift", "tcl", "tex", "textile", "vb", 
-        ])      
+/// Whether the file is the compiled version of a source file in the same
+/// directory, such as `foo.js` next to `foo.coffee`.
+fn has_source(file: &File<'_>) -> bool {
+    match file.parent_dir {
+        Some(dir)  => file.get_source_files().iter().any(|path| dir.contains(path)),
+        None       => false,
     }
+}
 
-    fn is_pretty_data(&self, file: &File<'_>) -> bool {
-        file.extension_is_one_of( &[
-            "json", "xml", "hx", "hxml", "ctp", "haml", 
-            "html", "slim", "sql", 
-        ])
-    }
+/// Turns a glob into a regex that matches the whole of a string.
+/// Character classes such as `[a-z]` and `[!0-9]` are kept.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
 
-    fn is_vim(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref NAME_EXT: RegexSet = RegexSet::new(&[
-                r".*vim.*",
-            ]).unwrap();
-            static ref SET_EXT: RegexSet = RegexSet::new(&[
-                r".*vim.*",
-            ]).unwrap();
-        }
-        file.name_matches_set( &NAME_EXT ) || 
-        file.extension_matches_set( &SET_EXT )
-    }
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
 
-    fn is_config_folder(&self, file: &File<'_>) -> bool {
-        file.name_is_one_of( &[
-            "docker",
-        ])
-    }
+                // A ‘]’ straight after the opening bracket is part of the class
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    regex.push_str("\\]");
+                }
 
-    fn is_folder_with_language_files(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref SET_NAME: RegexSet = RegexSet::new(&[
-                r".*test.*",
-            ]).unwrap();
-        }
-        file.name_is_one_of( &[
-            "src", "lib"
-        ]) || file.name_matches_set( &SET_NAME )
-    }
+                for c in chars.by_ref() {
+                    match c {
+                        ']'                          => break,
+                        '\\' | '[' | '&' | '~' | '^' => { regex.push('\\'); regex.push(c); }
+                        _                            => regex.push(c),
+                    }
+                }
 
-    fn is_folder_with_exe_files(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref SET_NAME: RegexSet = RegexSet::new(&[
-                r".*script.*",
-            ]).unwrap();
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
         }
-        file.name_is_one_of( &[
-            "target", "bin"
-        ]) || file.name_matches_set( &SET_NAME )
     }
 
-    fn is_folder_with_document_files(&self, file: &File<'_>) -> bool {
-        lazy_static! {
-            static ref SET_NAME: RegexSet = RegexSet::new(&[
-                r"man.*",
-            ]).unwrap();
-        }
-        file.name_is_one_of( &[
-            "doc"
-        ]) || file.name_matches_set( &SET_NAME )
-    }
+    regex.push('$');
+    regex
 }
 
-impl FileColours for FileExtensions {
+
+impl FileColours for Ruleset {
     fn colour_file(&self, file: &File<'_>) -> Option<Style> {
-        use ansi_term::Colour::*;
-
-        Some(match file {
-            f if self.is_temp(f)        => Fixed(244).normal(),
-            f if self.is_immediate(f)   => Fixed(1).bold().underline(),
-            f if self.is_image(f)       => Fixed(37).normal(),
-            f if self.is_video(f)       => Fixed(135).normal(),
-            f if self.is_music(f)       => Fixed(92).normal(),
-            f if self.is_lossless(f)    => Fixed(93).normal(),
-            f if self.is_crypto(f)      => Fixed(109).normal(),
-            f if self.is_document(f)    => Fixed(187).normal(),
-            f if self.is_compressed(f)  => Red.normal(),
-            f if self.is_compiled(f)    => Fixed(137).normal(),
-            f if self.is_pretty_data(f)    => Fixed(178).normal(),
-            f if self.is_script(f)    => Fixed(173).normal(),
-            f if self.is_config(f)    => Fixed(65).normal(),
-            f if self.is_vim(f)    => Fixed(71).normal(),
-            f if self.is_language(f)    => Fixed(75).normal(),
-            _                           => Fixed(244).bold(),
-        })
+        self.find(file, false, |rule| rule.style)
     }
+
     fn colour_dir(&self, file: &File<'_>) -> Option<Style> {
-        use ansi_term::Colour::*;
-
-        Some(match file {
-            f if self.is_config_folder(f)    => Fixed(65).bold(),
-            f if self.is_folder_with_language_files(f)    => Fixed(75).bold(),
-            f if self.is_folder_with_exe_files(f)    => Fixed(173).bold(),
-            f if self.is_folder_with_document_files(f)    => Fixed(187).bold(),
-            _                                => Fixed(244).bold(),
-        })
+        self.find(file, true, |rule| rule.style)
     }
 }
 
-impl FileIcon for FileExtensions {
+impl FileIcon for Ruleset {
     fn icon_file(&self, file: &File<'_>) -> Option<char> {
-        use crate::output::icons::Icons;
-        lazy_static! {
-            static ref SET_VIM_NAME: RegexSet = RegexSet::new(&[
-                r".*vim.*",
-            ]).unwrap();
-            static ref SET_VIM_EXT: RegexSet = RegexSet::new(&[
-                r".*vim.*",
-            ]).unwrap();
-            static ref SET_SH_EXT: RegexSet = RegexSet::new(&[
-                r".*bash.*",
-                r".*zsh.*",
-                r"^sh_",
-                r"_sh$",
-            ]).unwrap();
-        }
-        if self.is_music(file) || self.is_lossless(file) {
-            Some(Icons::Audio.value())
-        }
-        else if self.is_image(file) {
-            Some(Icons::Image.value())
-        }
-        else if self.is_video(file) {
-            Some(Icons::Video.value())
-        } else if file.extension_matches_set( &SET_VIM_EXT ) ||
-                  file.name_matches_set( &SET_VIM_NAME ) {
-            Some('\u{e62b}')
-        } else if file.extension_matches_set( &SET_SH_EXT ) {
-            Some('\u{ebc7}')
-        }
-        else {
-            None
-        }
+        self.find(file, file.points_to_directory(), |rule| rule.icon)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn is_match(glob: &str, text: &str) -> bool {
+        regex::Regex::new(&glob_to_regex(glob)).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn builtins_compile() {
+        assert!(Ruleset::new(builtin_rules()).is_ok());
+    }
+
+    #[test]
+    fn glob_literal() {
+        assert!(is_match("Cargo.toml", "Cargo.toml"));
+        assert!(! is_match("Cargo.toml", "Cargo_toml"));
+        assert!(! is_match("Cargo.toml", "Cargo.toml.bak"));
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(is_match("*.ninja", "build.ninja"));
+        assert!(is_match("#*#", "#notes#"));
+        assert!(is_match("?.c", "a.c"));
+        assert!(! is_match("?.c", "ab.c"));
+    }
+
+    #[test]
+    fn glob_classes() {
+        assert!(is_match("[a-c]x", "bx"));
+        assert!(! is_match("[!a-c]x", "bx"));
+        assert!(is_match("[]]", "]"));
+    }
+
+    #[test]
+    fn bad_regex() {
+        let rules = vec![ Rule::new("broken").regexes(Target::Name, &[ "(" ]) ];
+        assert!(Ruleset::new(rules).is_err());
     }
 }
@@ -12,7 +12,7 @@ impl<'a> File<'a> {
     /// their source file `foo.coffee` exists in the same directory.
     /// For example, `foo.js` is perfectly valid without `foo.coffee`, so we
     /// don’t want to always blindly highlight `*.js` as compiled.
-    /// (See also `FileExtensions#is_compiled`)
+    /// (See also the “compiled” rule in `filetype`)
     pub fn get_source_files(&self) -> Vec<PathBuf> {
         if let Some(ext) = &self.ext {
             match &ext[..] {
@@ -25,17 +25,24 @@
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::io::{self, Write, ErrorKind};
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
 
 use ansi_term::{ANSIStrings, Style};
+use scoped_threadpool::Pool;
 
 use log::*;
 
 use crate::fs::{Dir, File};
 use crate::fs::feature::git::GitCache;
-use crate::fs::filter::GitIgnore;
-use crate::options::{Options, Vars, vars, OptionsResult};
-use crate::output::{escape, lines, grid, grid_details, details, View, Mode};
+use crate::fs::filter::{FilterCache, GitIgnore};
+use crate::fs::ignore::IgnoreCache;
+use crate::fs::read_ahead::ReadAhead;
+use crate::fs::total_size::fill_total_sizes;
+use crate::options::{Config, Options, Vars, vars, OptionsResult};
+use crate::output::{escape, lines, grid, grid_details, details, json, thread_pool, View, Mode};
+use crate::output::summary::{Summary, Remainder};
+use crate::output::table::ENVIRONMENT;
 use crate::theme::Theme;
 
 mod fs;
@@ -62,8 +69,27 @@ fn main() {
     }
 
     let args: Vec<_> = env::args_os().skip(1).collect();
-    match Options::parse(args.iter().map(std::convert::AsRef::as_ref), &LiveVars) {
-        OptionsResult::Ok(options, mut input_paths) => {
+    let config = match Config::load(&LiveVars) {
+        Ok(c)  => c,
+        Err(e) => {
+            // A broken config file shouldn’t stop the help text or the
+            // version from being shown, as they might be needed to fix it.
+            let default = Config::default();
+            match Options::parse(args.iter().map(std::convert::AsRef::as_ref), &default, &LiveVars) {
+                OptionsResult::Help(_) | OptionsResult::Version(_) => {
+                    eprintln!("exa: warning: {}", e);
+                    default
+                }
+                _ => {
+                    eprintln!("exa: {}", e);
+                    exit(exits::OPTIONS_ERROR);
+                }
+            }
+        }
+    };
+
+    match Options::parse(args.iter().map(std::convert::AsRef::as_ref), &config, &LiveVars) {
+        OptionsResult::Ok(mut options, mut input_paths) => {
 
             // List the current directory by default.
             // (This has to be done here, otherwise git_options won’t see it.)
@@ -72,11 +98,20 @@ fn main() {
             }
 
             let git = git_options(&options, &input_paths);
+            let ignore = ignore_options(&options);
+            let pool = thread_pool();
+            let read_ahead = read_ahead_options(&options);
             let writer = io::stdout();
 
             let console_width = options.view.width.actual_terminal_width();
-            let theme = options.theme.to_theme(console_width.is_some());
-            let exa = Exa { options, writer, input_paths, theme, console_width, git };
+            let theme = options.theme.to_theme(console_width.is_some(), &config.rules);
+
+            // Quoting depends on whether stdout is a terminal, rather than
+            // on whether a width has been set.
+            let quoting_style = &mut options.view.file_style.quoting_style;
+            *quoting_style = quoting_style.for_output(terminal_size::terminal_size().is_some());
+
+            let exa = Exa { options, writer, input_paths, theme, console_width, git, ignore, pool, read_ahead, grand_total: Summary::default(), listings: 0 };
 
             match exa.run() {
                 Ok(exit_status) => {
@@ -142,6 +177,25 @@ pub struct Exa<'args> {
     /// This has to last the lifetime of the program, because the user might
     /// want to list several directories in the same repository.
     pub git: Option<GitCache>,
+
+    /// A global cache of ignore files, if ignored files are being skipped.
+    /// Like the Git cache, this lasts the lifetime of the program, so each
+    /// ignore file only gets read once.
+    pub ignore: Option<IgnoreCache>,
+
+    /// The threads that add up the totals of the directories being listed,
+    /// which get used for one listing at a time.
+    pub pool: Pool,
+
+    /// The threads that read directories ahead of them being listed, if
+    /// they’re being recursed into. Like the pool, these get started once,
+    /// and get given the directories of one listing at a time.
+    pub read_ahead: ReadAhead,
+
+    /// The counts of every listing so far, which get printed as a grand
+    /// total at the end when there’s been more than one listing.
+    pub grand_total: Summary,
+    pub listings: usize,
 }
 
 /// The “real” environment variables type.
@@ -158,13 +212,37 @@ impl Vars for LiveVars {
 /// listed before they’re actually listed, if the options demand it.
 fn git_options(options: &Options, args: &[&OsStr]) -> Option<GitCache> {
     if options.should_scan_for_git() {
-        Some(args.iter().map(PathBuf::from).collect())
+        let mut git: GitCache = args.iter().map(PathBuf::from).collect();
+        git.set_untracked_depth(options.git_untracked_depth);
+        Some(git)
     }
     else {
         None
     }
 }
 
+/// Create a cache for the ignore files if the options say to skip files
+/// that they ignore.
+fn ignore_options(options: &Options) -> Option<IgnoreCache> {
+    if options.filter.git_ignore == GitIgnore::CheckAndIgnore {
+        Some(IgnoreCache::default())
+    }
+    else {
+        None
+    }
+}
+
+/// Start the threads that read directories ahead of listing them if the
+/// options say to recurse into them.
+fn read_ahead_options(options: &Options) -> ReadAhead {
+    if options.dir_action.recurse_options().is_some() {
+        ReadAhead::spawn()
+    }
+    else {
+        ReadAhead::default()
+    }
+}
+
 impl<'args> Exa<'args> {
     /// # Errors
     ///
@@ -204,75 +282,190 @@ impl<'args> Exa<'args> {
         let no_files = files.is_empty();
         let is_only_dir = dirs.len() == 1 && no_files;
 
+        let cache = FilterCache::default();
         self.options.filter.filter_argument_files(&mut files);
-        self.print_files(None, files)?;
+        fill_total_sizes(&mut self.pool, &mut files, Path::new(""), &self.options.filter, self.ignore.as_ref(), &cache);
+        self.options.filter.filter_total_sizes(&mut files);
+        self.print_files(None, files, None, &cache)?;
+
+        let exit_status = self.print_dirs(dirs, None, None, no_files, is_only_dir, exit_status)?;
 
-        self.print_dirs(dirs, no_files, is_only_dir, exit_status)
+        if self.listings > 1 {
+            writeln!(&mut self.writer, "\nGrand total: {}", self.grand_total)?;
+        }
+
+        Ok(exit_status)
     }
 
-    fn print_dirs(&mut self, dir_files: Vec<Dir>, mut first: bool, is_only_dir: bool, exit_status: i32) -> io::Result<i32> {
+    /// Prints the contents of each directory, recursing into them if asked
+    /// to. The root is the directory given on the command-line that these
+    /// ones were found inside, which path-aware patterns are relative to;
+    /// it’s `None` for the directories on the command-line themselves.
+    /// Each of those starts a new listing, with a cache of what’s been
+    /// found out about the directories in it that gets passed down along
+    /// with the root, and everything underneath it gets read on other
+    /// threads, a little ahead of being listed.
+    fn print_dirs(&mut self, dir_files: Vec<Dir>, root: Option<&Path>, cache: Option<&FilterCache>, mut first: bool, is_only_dir: bool, exit_status: i32) -> io::Result<i32> {
+        // JSON output is one object per line, so it can’t have any headings
+        // or gaps between directories. It also does its own recursion, so
+        // that children end up nested inside their directory’s object.
+        // NUL-separated output is the same, with full paths instead of
+        // nesting.
+        let is_script_output = matches!(self.options.view.mode, Mode::Json(_) | Mode::Print0);
+
         for dir in dir_files {
 
             // Put a gap between directories, or between the list of files and
             // the first directory.
-            if first {
+            if first || is_script_output {
                 first = false;
             }
             else {
                 writeln!(&mut self.writer)?;
             }
 
-            if ! is_only_dir {
+            if ! is_only_dir && ! is_script_output {
                 let mut bits = Vec::new();
-                escape(dir.path.display().to_string(), &mut bits, Style::default(), Style::default());
+                escape(dir.path.display().to_string(), &mut bits, Style::default(), Style::default(), self.options.view.file_style.quoting_style);
                 writeln!(&mut self.writer, "{}:", ANSIStrings(&bits))?;
             }
 
             let mut children = Vec::new();
-            let git_ignore = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
-            for file in dir.files(self.options.filter.dot_filter, self.git.as_ref(), git_ignore) {
+            for file in dir.files(self.options.filter.dot_filter, self.ignore.as_ref()) {
                 match file {
                     Ok(file)        => children.push(file),
                     Err((path, e))  => writeln!(io::stderr(), "[{}: {}]", path.display(), e)?,
                 }
             };
 
-            self.options.filter.filter_child_files(&mut children);
+            // The script views nest each directory’s children from the
+            // top of the listing, whereas `--recurse` counts how deep its
+            // children are from the current directory.
+            let depth = if is_script_output { 0 }
+                        else { dir.path.components().filter(|&c| c != Component::CurDir).count() + 1 };
+
+            let is_new_listing = root.is_none();
+            let root = root.unwrap_or(&dir.path);
+            let new_cache = FilterCache::default();
+            let cache = cache.unwrap_or(&new_cache);
+
+            if let Some(recurse_opts) = self.options.dir_action.recurse_options() {
+                self.options.filter.filter_recursed_files(&mut children, root, recurse_opts, depth, self.ignore.as_ref(), cache);
+            }
+            else {
+                self.options.filter.filter_child_files(&mut children);
+            }
+
+            fill_total_sizes(&mut self.pool, &mut children, root, &self.options.filter, self.ignore.as_ref(), cache);
+
+            // Directories get recursed into whatever size they add up to,
+            // as there could be files inside them that are the right size.
+            if self.options.dir_action.recurse_options().is_none() {
+                self.options.filter.filter_total_sizes(&mut children);
+            }
             self.options.filter.sort_files(&mut children);
 
+            let remainder = if is_script_output { None }
+                                           else { self.limit_entries(&mut children) };
+
             if let Some(recurse_opts) = self.options.dir_action.recurse_options() {
-                let depth = dir.path.components().filter(|&c| c != Component::CurDir).count() + 1;
-                if ! recurse_opts.tree && ! recurse_opts.is_too_deep(depth) {
+                if ! recurse_opts.tree && ! is_script_output && ! recurse_opts.is_too_deep(depth) {
+                    if is_new_listing {
+                        self.read_ahead.start(&children, depth, recurse_opts, &self.options.filter, self.ignore.as_ref(), root, cache);
+                    }
 
                     let mut child_dirs = Vec::new();
-                    for child_dir in children.iter().filter(|f| f.is_directory() && ! f.is_all_all) {
-                        match child_dir.to_dir() {
+                    for child_dir in children.iter().filter(|f| f.is_directory() && ! f.is_all_all && ! recurse_opts.is_pruned(f)) {
+                        match self.read_ahead.read_dir(&child_dir.path) {
                             Ok(d)   => child_dirs.push(d),
                             Err(e)  => writeln!(io::stderr(), "{}: {}", child_dir.path.display(), e)?,
                         }
                     }
 
-                    self.print_files(Some(&dir), children)?;
-                    match self.print_dirs(child_dirs, false, false, exit_status) {
+                    self.print_files(Some(&dir), children, remainder, cache)?;
+                    match self.print_dirs(child_dirs, Some(root), Some(cache), false, false, exit_status) {
                         Ok(_)   => (),
                         Err(e)  => return Err(e),
                     }
+
+                    self.read_ahead.done_with(&dir.path);
                     continue;
                 }
             }
 
-            self.print_files(Some(&dir), children)?;
+            self.print_files(Some(&dir), children, remainder, cache)?;
         }
 
         Ok(exit_status)
     }
 
-    /// Prints the list of files using whichever view is selected.
-    fn print_files(&mut self, dir: Option<&Dir>, files: Vec<File<'_>>) -> io::Result<()> {
+    /// Leaves only the first entries of a directory’s sorted files when
+    /// recursing with `--max-entries`, returning what the rest add up to.
+    /// Trees leave out their entries as they’re being drawn instead.
+    fn limit_entries(&self, files: &mut Vec<File<'_>>) -> Option<Remainder> {
+        let recurse = self.options.dir_action.recurse_options()?;
+        let count = recurse.entries_to_list(files.len());
+
+        if recurse.tree || count == files.len() {
+            return None;
+        }
+
+        let rest = files.split_off(count);
+        Some(Remainder::of_files(&rest))
+    }
+
+    /// Prints the list of files using whichever view is selected, along
+    /// with the total number of blocks before it and a summary after it,
+    /// if they were asked for. The remainder is the files that were left
+    /// out of the list, which get counted after it.
+    fn print_files(&mut self, dir: Option<&Dir>, files: Vec<File<'_>>, remainder: Option<Remainder>, cache: &FilterCache) -> io::Result<()> {
         if files.is_empty() {
             return Ok(());
         }
 
+        // Summaries would get in the way of output meant for scripts.
+        let summary = match self.options.view.mode {
+            Mode::Json(_) | Mode::Print0    => None,
+            _ if self.options.view.summary  => Some(Mutex::new(Summary::of_files(&files, self.git.as_ref()))),
+            _                               => None,
+        };
+
+        #[cfg(unix)]
+        if dir.is_some() && self.shows_blocks() {
+            writeln!(&mut self.writer, "total {}", output::summary::total_blocks(&files))?;
+        }
+
+        self.render_files(dir, files, summary.as_ref(), cache)?;
+
+        if let Some(remainder) = remainder {
+            let description = remainder.describe(ENVIRONMENT.numeric());
+            writeln!(&mut self.writer, "{}", self.theme.ui.punctuation.paint(description))?;
+        }
+
+        if let Some(summary) = summary {
+            let summary = summary.into_inner().unwrap();
+            writeln!(&mut self.writer, "{}", summary)?;
+            self.grand_total.add(&summary);
+            self.listings += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the blocks column is being shown in a long view, which gets
+    /// a `total` line before each directory’s listing, like `ls -ls` does.
+    #[cfg(unix)]
+    fn shows_blocks(&self) -> bool {
+        match self.options.view.mode {
+            Mode::Details(details::Options { table: Some(ref table), .. }) |
+            Mode::GridDetails(grid_details::Options { details: details::Options { table: Some(ref table), .. }, .. }) => table.columns.shows_blocks(),
+            _ => false,
+        }
+    }
+
+    /// Renders the list of files using whichever view is selected. The
+    /// details view counts the files further down a tree in the summary.
+    fn render_files(&mut self, dir: Option<&Dir>, files: Vec<File<'_>>, summary: Option<&Mutex<Summary>>, cache: &FilterCache) -> io::Result<()> {
         let theme = &self.theme;
         let View { ref mode, ref file_style, .. } = self.options.view;
 
@@ -290,13 +483,33 @@ impl<'args> Exa<'args> {
                 r.render(&mut self.writer)
             }
 
+            (Mode::Json(ref opts), _) => {
+                let filter = &self.options.filter;
+                let recurse = self.options.dir_action.recurse_options();
+                let ignore = self.ignore.as_ref();
+                let git = self.git.as_ref();
+
+                let r = json::Render { dir, files, opts, recurse, filter, ignore, cache, git };
+                r.render(&mut self.writer)
+            }
+
+            (Mode::Print0, _) => {
+                let filter = &self.options.filter;
+                let recurse = self.options.dir_action.recurse_options();
+                let ignore = self.ignore.as_ref();
+
+                let r = lines::Print0Render { dir, files, recurse, filter, ignore, cache };
+                r.render(&mut self.writer)
+            }
+
             (Mode::Details(ref opts), _) => {
                 let filter = &self.options.filter;
                 let recurse = self.options.dir_action.recurse_options();
 
-                let git_ignoring = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+                let ignore = self.ignore.as_ref();
+                let read_ahead = &self.read_ahead;
                 let git = self.git.as_ref();
-                let r = details::Render { dir, files, theme, file_style, opts, recurse, filter, git_ignoring, git };
+                let r = details::Render { dir, files, theme, file_style, opts, recurse, filter, ignore, cache, read_ahead, git, summary };
                 r.render(&mut self.writer)
             }
 
@@ -306,10 +519,11 @@ impl<'args> Exa<'args> {
                 let row_threshold = opts.row_threshold;
 
                 let filter = &self.options.filter;
-                let git_ignoring = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+                let ignore = self.ignore.as_ref();
+                let read_ahead = &self.read_ahead;
                 let git = self.git.as_ref();
 
-                let r = grid_details::Render { dir, files, theme, file_style, grid, details, filter, row_threshold, git_ignoring, git, console_width };
+                let r = grid_details::Render { dir, files, theme, file_style, grid, details, filter, row_threshold, ignore, cache, read_ahead, git, console_width };
                 r.render(&mut self.writer)
             }
 
@@ -317,10 +531,11 @@ impl<'args> Exa<'args> {
                 let opts = &opts.to_details_options();
                 let filter = &self.options.filter;
                 let recurse = self.options.dir_action.recurse_options();
-                let git_ignoring = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+                let ignore = self.ignore.as_ref();
+                let read_ahead = &self.read_ahead;
 
                 let git = self.git.as_ref();
-                let r = details::Render { dir, files, theme, file_style, opts, recurse, filter, git_ignoring, git };
+                let r = details::Render { dir, files, theme, file_style, opts, recurse, filter, ignore, cache, read_ahead, git, summary };
                 r.render(&mut self.writer)
             }
         }
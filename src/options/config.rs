@@ -0,0 +1,570 @@
+//! Loading the configuration file.
+//!
+//! The configuration file lets the user set default flags, define named
+//! aliases for sets of flags, and override the theme, without having to go
+//! through shell aliases and environment variables. It gets read from
+//! `$XDG_CONFIG_HOME/exa/config.toml` (or `~/.config/exa/config.toml`), or
+//! from wherever the `EXA_CONFIG` variable points.
+//!
+//! It’s written in a small subset of TOML:
+//!
+//! ```toml
+//! # Flags that get used every time, before the command-line ones.
+//! flags = "--group-directories-first --icons"
+//!
+//! [aliases]
+//! # `exa ll src` becomes `exa -lah --git src`
+//! ll = "-lah --git"
+//! lt = ["--tree", "--level=2"]
+//!
+//! [theme]
+//! # The same keys and values as `EXA_COLORS`
+//! di = "1;34"
+//! "*.rs" = "38;5;208"
+//! ```
+//!
+//! Only strings, arrays of strings, and booleans are supported as values,
+//! and arrays can’t span more than one line.
+//!
+//! File type rules live in their own file, `rules.toml` next to this one,
+//! unless the `rules` key points somewhere else. (See the `rules` module)
+//!
+//! The flags from the file are put in front of the ones from the command
+//! line, so the usual rule of later arguments taking priority means that
+//! the command line always wins.
+
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::info::filetype::Rule;
+use crate::options::{flags, rules, vars, Vars, OptionsError};
+use crate::options::parser::{Matches, Strictness};
+
+
+/// The contents of the configuration file, if there is one.
+#[derive(PartialEq, Debug, Default)]
+pub struct Config {
+
+    /// The path the configuration was read from, for error messages.
+    pub path: PathBuf,
+
+    /// Flags that get used as though they were at the start of the
+    /// command-line arguments.
+    pub flags: Vec<OsString>,
+
+    /// Named sets of arguments that can be given as the first argument.
+    pub aliases: Vec<(String, Vec<OsString>)>,
+
+    /// Theme overrides, in the format of the `EXA_COLORS` variable.
+    pub theme: Option<String>,
+
+    /// Where to read the file type rules from, if not `rules.toml` in the
+    /// same directory as this file.
+    pub rules_file: Option<PathBuf>,
+
+    /// The user’s own file type rules, which go in front of the built-in
+    /// ones.
+    pub rules: Vec<Rule>,
+
+    /// Keys that were in the file but don’t mean anything. These are only
+    /// an error in strict mode, so the file can be shared between versions.
+    pub unknown_keys: Vec<String>,
+}
+
+impl Config {
+
+    /// Finds and reads the configuration file, and the rules file next to
+    /// it. A missing file isn’t an error: it just means there’s nothing to
+    /// configure.
+    pub fn load<V: Vars>(vars: &V) -> Result<Self, OptionsError> {
+        let path = match Self::path(vars) {
+            Some(p)  => p,
+            None     => return Ok(Self::default()),
+        };
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                Self::read(path, &contents)?
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("No config file at {:?}", path);
+                Self { path, .. Self::default() }
+            }
+            Err(e) => {
+                return Err(OptionsError::BadConfig(path, e.to_string()));
+            }
+        };
+
+        config.rules = config.load_rules()?;
+        Ok(config)
+    }
+
+    /// Reads the file type rules. The file only has to exist if the
+    /// configuration file names it explicitly.
+    fn load_rules(&self) -> Result<Vec<Rule>, OptionsError> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        let path = match self.rules_file {
+            Some(ref file)  => dir.join(file),
+            None            => dir.join("rules.toml"),
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                rules::read(&path, &contents)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && self.rules_file.is_none() => {
+                debug!("No rules file at {:?}", path);
+                Ok(Vec::new())
+            }
+            Err(e) => {
+                Err(OptionsError::BadConfig(path, e.to_string()))
+            }
+        }
+    }
+
+    /// Works out where the configuration file should be. Setting
+    /// `EXA_CONFIG` to the empty string turns the file off entirely.
+    pub(super) fn path<V: Vars>(vars: &V) -> Option<PathBuf> {
+        if let Some(path) = vars.get(vars::EXA_CONFIG) {
+            return if path.is_empty() { None } else { Some(PathBuf::from(path)) };
+        }
+
+        let config_dir = match vars.get(vars::XDG_CONFIG_HOME) {
+            Some(dir) if ! dir.is_empty()  => PathBuf::from(dir),
+            _                              => PathBuf::from(vars.get(vars::HOME)?).join(".config"),
+        };
+
+        Some(config_dir.join("exa").join("config.toml"))
+    }
+
+    /// Parses the contents of a configuration file.
+    pub fn read(path: PathBuf, contents: &str) -> Result<Self, OptionsError> {
+        let mut config = Self { path, .. Self::default() };
+        let mut theme = Vec::new();
+        let mut section = Section::Top;
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match name.trim() {
+                    "aliases"  => Section::Aliases,
+                    "theme"    => Section::Theme,
+                    other      => Section::Unknown(other.into()),
+                };
+
+                if let Section::Unknown(ref name) = section {
+                    config.unknown_keys.push(name.clone());
+                }
+
+                continue;
+            }
+
+            let (key, value) = match parse_pair(line) {
+                Ok(pair)  => pair,
+                Err(e)    => return Err(config.error(format!("line {}: {}", index + 1, e))),
+            };
+
+            let valid = match (&section, value) {
+                (Section::Top, value) if key == "flags" => {
+                    match value.into_words() {
+                        Some(words) => {
+                            config.flags = words.into_iter().map(OsString::from).collect();
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                (Section::Top, Value::String(file)) if key == "rules" => {
+                    config.rules_file = Some(PathBuf::from(file));
+                    true
+                }
+                (Section::Top, _) => {
+                    config.unknown_keys.push(key.clone());
+                    true
+                }
+                (Section::Aliases, value) => {
+                    match value.into_words() {
+                        Some(words) => {
+                            config.aliases.push((key.clone(), words.into_iter().map(OsString::from).collect()));
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                (Section::Theme, Value::Bool(true)) if key == "reset" => {
+                    theme.insert(0, String::from("reset"));
+                    true
+                }
+                (Section::Theme, Value::String(style)) => {
+                    let is_separator = |c: char| c == ':' || c == '=';
+                    if key.contains(is_separator) || style.contains(is_separator) {
+                        false
+                    }
+                    else {
+                        theme.push(format!("{}={}", key, style));
+                        true
+                    }
+                }
+                (Section::Unknown(_), _) => {
+                    // Already complained about the section as a whole
+                    true
+                }
+                (_, _) => {
+                    false
+                }
+            };
+
+            if ! valid {
+                return Err(config.error(format!("line {}: invalid value for {:?}", index + 1, key)));
+            }
+        }
+
+        if ! theme.is_empty() {
+            config.theme = Some(theme.join(":"));
+        }
+
+        config.check_flags()?;
+        Ok(config)
+    }
+
+    /// Checks that the default flags are ones that exa knows about, as
+    /// otherwise they’d be reported as though the user had typed them.
+    fn check_flags(&self) -> Result<(), OptionsError> {
+        let args = self.flags.iter().map(OsString::as_os_str);
+
+        match flags::ALL_ARGS.parse(args, Strictness::UseLastArguments) {
+            Ok(Matches { ref frees, .. }) if ! frees.is_empty() => {
+                Err(self.error(format!("default flags can’t include file names, but got {:?}", frees[0])))
+            }
+            Ok(_) => {
+                Ok(())
+            }
+            Err(e) => {
+                Err(self.error(e.to_string()))
+            }
+        }
+    }
+
+    /// Replaces the first argument with its definition, if it’s the name of
+    /// an alias and not the path of an existing file, so a directory called
+    /// `ll` still gets listed.
+    pub fn expand_alias<'args, I>(&'args self, args: I) -> Vec<&'args OsStr>
+    where I: IntoIterator<Item = &'args OsStr>
+    {
+        let mut args = args.into_iter();
+        let first = match args.next() {
+            Some(a)  => a,
+            None     => return Vec::new(),
+        };
+
+        let alias = self.aliases.iter().find(|(name, _)| first == name.as_str());
+        let mut result = match alias {
+            Some((_, words)) if Path::new(first).symlink_metadata().is_err() => {
+                words.iter().map(OsString::as_os_str).collect()
+            }
+            Some((name, _)) => {
+                debug!("Not expanding alias {:?}, as there’s a file with that name", name);
+                vec![ first ]
+            }
+            None => vec![ first ],
+        };
+
+        result.extend(args);
+        result
+    }
+
+    /// Combines the theme from this file with the value of the
+    /// `EXA_COLORS` variable, which gets to override it.
+    pub fn colours(&self, env: Option<String>) -> Option<String> {
+        let theme = match self.theme {
+            Some(ref t)  => t,
+            None         => return env,
+        };
+
+        match env {
+            None => {
+                Some(theme.clone())
+            }
+            Some(env) if env == "reset" || env.starts_with("reset:") => {
+                Some(format!("reset:{}{}", theme, &env[5..]))
+            }
+            Some(env) => {
+                Some(format!("{}:{}", theme, env))
+            }
+        }
+    }
+
+    pub fn error(&self, message: String) -> OptionsError {
+        OptionsError::BadConfig(self.path.clone(), message)
+    }
+}
+
+
+/// Which table the current line of the file is in.
+enum Section {
+    Top,
+    Aliases,
+    Theme,
+    Unknown(String),
+}
+
+/// A value on the right-hand side of an equals sign.
+#[derive(PartialEq, Eq, Debug)]
+pub(super) enum Value {
+    String(String),
+    Array(Vec<String>),
+    Bool(bool),
+}
+
+impl Value {
+
+    /// Turns a string or an array of strings into a list of words, with
+    /// strings getting split on whitespace.
+    pub(super) fn into_words(self) -> Option<Vec<String>> {
+        match self {
+            Self::String(s)  => Some(s.split_whitespace().map(String::from).collect()),
+            Self::Array(a)   => Some(a),
+            Self::Bool(_)    => None,
+        }
+    }
+}
+
+/// Removes a comment from the end of a line, taking care not to remove
+/// anything that’s inside a string.
+pub(super) fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#')              => return &line[.. index],
+            (None, '"' | '\'')       => quote = Some(c),
+            (Some(q), c) if q == c   => quote = None,
+            _                        => {}
+        }
+    }
+
+    line
+}
+
+pub(super) fn parse_pair(line: &str) -> Result<(String, Value), String> {
+    let (key, rest) = if line.starts_with('"') || line.starts_with('\'') {
+        let (key, rest) = parse_string(line)?;
+        (key, rest.trim_start())
+    }
+    else {
+        let end = line.find(|c: char| ! (c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                      .unwrap_or(line.len());
+        (line[.. end].to_string(), line[end ..].trim_start())
+    };
+
+    if key.is_empty() {
+        return Err(String::from("missing key"));
+    }
+
+    let rest = match rest.strip_prefix('=') {
+        Some(r)  => r.trim(),
+        None     => return Err(format!("expected ‘=’ after {:?}", key)),
+    };
+
+    let value = if rest == "true" {
+        Value::Bool(true)
+    }
+    else if rest == "false" {
+        Value::Bool(false)
+    }
+    else if let Some(mut items) = rest.strip_prefix('[') {
+        let mut words = Vec::new();
+
+        loop {
+            items = items.trim_start();
+            if let Some(end) = items.strip_prefix(']') {
+                if ! end.trim().is_empty() {
+                    return Err(String::from("unexpected text after array"));
+                }
+
+                break;
+            }
+
+            let (word, after) = parse_string(items)?;
+            words.push(word);
+
+            items = after.trim_start();
+            items = items.strip_prefix(',').unwrap_or(items);
+        }
+
+        Value::Array(words)
+    }
+    else {
+        let (string, after) = parse_string(rest)?;
+        if ! after.trim().is_empty() {
+            return Err(String::from("unexpected text after string"));
+        }
+
+        Value::String(string)
+    };
+
+    Ok((key, value))
+}
+
+/// Parses a basic (double-quoted) or literal (single-quoted) string from
+/// the start of the input, returning it and whatever comes after it.
+fn parse_string(input: &str) -> Result<(String, &str), String> {
+    let mut chars = input.char_indices();
+    let quote = match chars.next() {
+        Some((_, c)) if c == '"' || c == '\'' => c,
+        _ => return Err(format!("expected a string, but got {:?}", input)),
+    };
+
+    let mut string = String::new();
+    while let Some((index, c)) = chars.next() {
+        if c == quote {
+            return Ok((string, &input[index + 1 ..]));
+        }
+        else if c == '\\' && quote == '"' {
+            match chars.next() {
+                Some((_, 'n'))   => string.push('\n'),
+                Some((_, 't'))   => string.push('\t'),
+                Some((_, '\\'))  => string.push('\\'),
+                Some((_, '"'))   => string.push('"'),
+                Some((_, e)) if e == 'u' || e == 'U' => {
+                    let digits = if e == 'u' { 4 } else { 8 };
+                    let hex = chars.by_ref().take(digits).map(|(_, c)| c).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().filter(|_| hex.len() == digits).and_then(char::from_u32) {
+                        Some(c)  => string.push(c),
+                        None     => return Err(format!("invalid escape ‘\\{}{}’", e, hex)),
+                    }
+                }
+                Some((_, c))     => return Err(format!("unknown escape ‘\\{}’", c)),
+                None             => break,
+            }
+        }
+        else {
+            string.push(c);
+        }
+    }
+
+    Err(String::from("unterminated string"))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::temp_tree::TempTree;
+
+    fn read(contents: &str) -> Result<Config, OptionsError> {
+        Config::read(PathBuf::from("config.toml"), contents)
+    }
+
+    fn words(words: &[&str]) -> Vec<OsString> {
+        words.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(read(""), Ok(Config { path: PathBuf::from("config.toml"), .. Config::default() }));
+    }
+
+    #[test]
+    fn default_flags() {
+        let config = read("flags = \"--long --git\"  # always\n").unwrap();
+        assert_eq!(config.flags, words(&[ "--long", "--git" ]));
+    }
+
+    #[test]
+    fn default_flags_array() {
+        let config = read("flags = [ \"--sort\", 'size' ]").unwrap();
+        assert_eq!(config.flags, words(&[ "--sort", "size" ]));
+    }
+
+    #[test]
+    fn aliases() {
+        let config = read("[aliases]\nll = \"-lah --git\"\n").unwrap();
+        assert_eq!(config.aliases, vec![ (String::from("ll"), words(&[ "-lah", "--git" ])) ]);
+    }
+
+    #[test]
+    fn expand_alias() {
+        let config = read("[aliases]\nll = \"-l\"\n").unwrap();
+        let args = vec![ OsStr::new("ll"), OsStr::new("src") ];
+        assert_eq!(config.expand_alias(args), vec![ OsStr::new("-l"), OsStr::new("src") ]);
+    }
+
+    #[test]
+    fn expand_alias_only_first() {
+        let config = read("[aliases]\nll = \"-l\"\n").unwrap();
+        let args = vec![ OsStr::new("src"), OsStr::new("ll") ];
+        assert_eq!(config.expand_alias(args), vec![ OsStr::new("src"), OsStr::new("ll") ]);
+    }
+
+    #[test]
+    fn expand_alias_not_over_files() {
+        let tree = TempTree::new("config-alias-file");
+        tree.dir("ll");
+
+        let path = tree.join("ll");
+        let missing = tree.join("lt");
+        let config = read(&format!("[aliases]\n{:?} = \"-l\"\n{:?} = \"-T\"\n", path, missing)).unwrap();
+
+        let args = vec![ path.as_os_str() ];
+        assert_eq!(config.expand_alias(args), vec![ path.as_os_str() ]);
+
+        let args = vec![ missing.as_os_str() ];
+        assert_eq!(config.expand_alias(args), vec![ OsStr::new("-T") ]);
+    }
+
+    #[test]
+    fn theme() {
+        let config = read("[theme]\ndi = \"1;34\"\n\"*.rs\" = \"31\"\nreset = true\n").unwrap();
+        assert_eq!(config.theme, Some(String::from("reset:di=1;34:*.rs=31")));
+    }
+
+    #[test]
+    fn theme_under_env() {
+        let config = read("[theme]\ndi = \"1;34\"\n").unwrap();
+        assert_eq!(config.colours(Some("di=32".into())), Some(String::from("di=1;34:di=32")));
+        assert_eq!(config.colours(Some("reset:di=32".into())), Some(String::from("reset:di=1;34:di=32")));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let config = read("colour = true\n[columns]\nsize = true\n").unwrap();
+        assert_eq!(config.unknown_keys, vec![ String::from("colour"), String::from("columns") ]);
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert!(read("flags = \"--wibble\"").is_err());
+    }
+
+    #[test]
+    fn file_in_flags() {
+        assert!(read("flags = \"-l src\"").is_err());
+    }
+
+    #[test]
+    fn rules_file() {
+        let config = read("rules = \"/etc/exa/rules.toml\"").unwrap();
+        assert_eq!(config.rules_file, Some(PathBuf::from("/etc/exa/rules.toml")));
+    }
+
+    #[test]
+    fn unicode_escape() {
+        assert_eq!(parse_string("\"\\uf001\""), Ok((String::from("\u{f001}"), "")));
+        assert!(parse_string("\"\\u12\"").is_err());
+    }
+
+    #[test]
+    fn unterminated() {
+        let error = read("\n[aliases]\nll = \"-l").unwrap_err();
+        assert_eq!(error, OptionsError::BadConfig(PathBuf::from("config.toml"), String::from("line 3: unterminated string")));
+    }
+}
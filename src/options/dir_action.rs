@@ -1,6 +1,6 @@
 //! Parsing the options for `DirAction`.
 
-use crate::options::parser::MatchedFlags;
+use crate::options::parser::{Arg, MatchedFlags};
 use crate::options::{flags, OptionsError, NumberSource};
 
 use crate::fs::dir_action::{DirAction, RecurseOptions};
@@ -22,6 +22,21 @@ impl DirAction {
             if ! recurse && ! tree && matches.count(&flags::LEVEL) > 0 {
                 return Err(OptionsError::Useless2(&flags::LEVEL, &flags::RECURSE, &flags::TREE));
             }
+            else if ! recurse && ! tree && matches.count(&flags::MAX_ENTRIES) > 0 {
+                return Err(OptionsError::Useless2(&flags::MAX_ENTRIES, &flags::RECURSE, &flags::TREE));
+            }
+            else if ! recurse && ! tree && matches.count(&flags::PRUNE) > 0 {
+                return Err(OptionsError::Useless2(&flags::PRUNE, &flags::RECURSE, &flags::TREE));
+            }
+            else if ! tree && matches.count(&flags::TREE_STYLE) > 0 {
+                return Err(OptionsError::Useless(&flags::TREE_STYLE, false, &flags::TREE));
+            }
+            else if ! tree && matches.count(&flags::TREE_COLORS) + matches.count(&flags::TREE_COLOURS) > 0 {
+                return Err(OptionsError::Useless(&flags::TREE_COLORS, false, &flags::TREE));
+            }
+            else if ! tree && matches.count(&flags::TREE_COMPACT) > 0 {
+                return Err(OptionsError::Useless(&flags::TREE_COMPACT, false, &flags::TREE));
+            }
             else if recurse && as_file {
                 return Err(OptionsError::Conflict(&flags::RECURSE, &flags::LIST_DIRS));
             }
@@ -52,23 +67,54 @@ impl RecurseOptions {
 
     /// Determine which files should be recursed into, based on the `--level`
     /// flag’s value, and whether the `--tree` flag was passed, which was
-    /// determined earlier. The maximum level should be a number, and this
-    /// will fail with an `Err` if it isn’t.
+    /// determined earlier, along with how many entries of each directory
+    /// to list with `--max-entries`. Both of these should be numbers, and
+    /// this will fail with an `Err` if they aren’t.
     pub fn deduce(matches: &MatchedFlags<'_>, tree: bool) -> Result<Self, OptionsError> {
-        if let Some(level) = matches.get(&flags::LEVEL)? {
-            let arg_str = level.to_string_lossy();
+        let max_depth = Self::deduce_number(matches, &flags::LEVEL)?;
+        let max_entries = Self::deduce_number(matches, &flags::MAX_ENTRIES)?;
+
+        // Listing no entries at all would leave nothing but placeholders.
+        if max_entries == Some(0) {
+            return Err(OptionsError::BadArgument(&flags::MAX_ENTRIES, "0".into()));
+        }
+
+        let prune = Self::deduce_prune(matches)?;
+        Ok(Self { tree, max_depth, max_entries, prune })
+    }
+
+    /// Determines which directories to list without recursing into them,
+    /// as a list of globs separated by pipe (`|`) characters, in the same
+    /// way as `--ignore-glob`.
+    fn deduce_prune(matches: &MatchedFlags<'_>) -> Result<Vec<glob::Pattern>, OptionsError> {
+        let inputs = match matches.get(&flags::PRUNE)? {
+            Some(inputs)  => inputs.to_string_lossy(),
+            None          => return Ok(Vec::new()),
+        };
+
+        let mut patterns = Vec::new();
+        for input in inputs.split('|') {
+            patterns.push(glob::Pattern::new(input)?);
+        }
+
+        Ok(patterns)
+    }
+
+    fn deduce_number(matches: &MatchedFlags<'_>, flag: &'static Arg) -> Result<Option<usize>, OptionsError> {
+        if let Some(number) = matches.get(flag)? {
+            let arg_str = number.to_string_lossy();
             match arg_str.parse() {
-                Ok(l) => {
-                    Ok(Self { tree, max_depth: Some(l) })
+                Ok(n) => {
+                    Ok(Some(n))
                 }
                 Err(e) => {
-                    let source = NumberSource::Arg(&flags::LEVEL);
+                    let source = NumberSource::Arg(flag);
                     Err(OptionsError::FailedParse(arg_str.to_string(), source, e))
                 }
             }
         }
         else {
-            Ok(Self { tree, max_depth: None })
+            Ok(None)
         }
     }
 }
@@ -84,11 +130,12 @@ mod test {
         ($name:ident: $type:ident <- $inputs:expr; $stricts:expr => $result:expr) => {
             #[test]
             fn $name() {
-                use crate::options::parser::Arg;
                 use crate::options::test::parse_for_test;
                 use crate::options::test::Strictnesses::*;
 
-                static TEST_ARGS: &[&Arg] = &[&flags::RECURSE, &flags::LIST_DIRS, &flags::TREE, &flags::LEVEL ];
+                static TEST_ARGS: &[&Arg] = &[&flags::RECURSE, &flags::LIST_DIRS, &flags::TREE, &flags::LEVEL,
+                                              &flags::TREE_STYLE, &flags::TREE_COLORS, &flags::TREE_COLOURS,
+                                              &flags::TREE_COMPACT, &flags::MAX_ENTRIES, &flags::PRUNE ];
                 for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf, true)) {
                     assert_eq!(result, $result);
                 }
@@ -106,27 +153,51 @@ mod test {
 
     // Recursing
     use self::DirAction::Recurse;
-    test!(rec_short:       DirAction <- ["-R"];                           Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(rec_long:        DirAction <- ["--recurse"];                    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(rec_lim_short:   DirAction <- ["-RL4"];                         Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(4) })));
-    test!(rec_lim_short_2: DirAction <- ["-RL=5"];                        Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(5) })));
-    test!(rec_lim_long:    DirAction <- ["--recurse", "--level", "666"];  Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(666) })));
-    test!(rec_lim_long_2:  DirAction <- ["--recurse", "--level=0118"];    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(118) })));
-    test!(tree:            DirAction <- ["--tree"];                       Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
-    test!(rec_tree:        DirAction <- ["--recurse", "--tree"];          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
-    test!(rec_short_tree:  DirAction <- ["-TR"];                          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
+    test!(rec_short:       DirAction <- ["-R"];                           Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, max_entries: None, prune: Vec::new() })));
+    test!(rec_long:        DirAction <- ["--recurse"];                    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, max_entries: None, prune: Vec::new() })));
+    test!(rec_lim_short:   DirAction <- ["-RL4"];                         Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(4), max_entries: None, prune: Vec::new() })));
+    test!(rec_lim_short_2: DirAction <- ["-RL=5"];                        Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(5), max_entries: None, prune: Vec::new() })));
+    test!(rec_lim_long:    DirAction <- ["--recurse", "--level", "666"];  Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(666), max_entries: None, prune: Vec::new() })));
+    test!(rec_lim_long_2:  DirAction <- ["--recurse", "--level=0118"];    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(118), max_entries: None, prune: Vec::new() })));
+    test!(tree:            DirAction <- ["--tree"];                       Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: None, prune: Vec::new() })));
+    test!(rec_tree:        DirAction <- ["--recurse", "--tree"];          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: None, prune: Vec::new() })));
+    test!(rec_short_tree:  DirAction <- ["-TR"];                          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: None, prune: Vec::new() })));
 
     // Overriding --list-dirs, --recurse, and --tree
-    test!(dirs_recurse:    DirAction <- ["--list-dirs", "--recurse"];     Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(dirs_tree:       DirAction <- ["--list-dirs", "--tree"];        Last => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
+    test!(dirs_recurse:    DirAction <- ["--list-dirs", "--recurse"];     Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, max_entries: None, prune: Vec::new() })));
+    test!(dirs_tree:       DirAction <- ["--list-dirs", "--tree"];        Last => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: None, prune: Vec::new() })));
     test!(just_level:      DirAction <- ["--level=4"];                    Last => Ok(DirAction::List));
 
     test!(dirs_recurse_2:  DirAction <- ["--list-dirs", "--recurse"]; Complain => Err(OptionsError::Conflict(&flags::RECURSE, &flags::LIST_DIRS)));
     test!(dirs_tree_2:     DirAction <- ["--list-dirs", "--tree"];    Complain => Err(OptionsError::Conflict(&flags::TREE,    &flags::LIST_DIRS)));
     test!(just_level_2:    DirAction <- ["--level=4"];                Complain => Err(OptionsError::Useless2(&flags::LEVEL, &flags::RECURSE, &flags::TREE)));
 
+    // Tree guides without a tree
+    test!(just_style:      DirAction <- ["--tree-style=ascii"];       Last => Ok(DirAction::List));
+    test!(just_style_2:    DirAction <- ["--tree-style=ascii"];   Complain => Err(OptionsError::Useless(&flags::TREE_STYLE, false, &flags::TREE)));
+    test!(just_colours:    DirAction <- ["--tree-colours"];       Complain => Err(OptionsError::Useless(&flags::TREE_COLORS, false, &flags::TREE)));
+    test!(just_compact:    DirAction <- ["--tree-compact"];       Complain => Err(OptionsError::Useless(&flags::TREE_COMPACT, false, &flags::TREE)));
+    test!(style_tree:      DirAction <- ["--tree", "--tree-style=heavy"];  Both => Ok(Recurse(RecurseOptions { tree: true, max_depth: None, max_entries: None, prune: Vec::new() })));
+
+
+    // Limiting entries
+    test!(entries_tree:    DirAction <- ["--tree", "--max-entries=20"];   Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: Some(20), prune: Vec::new() })));
+    test!(entries_rec:     DirAction <- ["-RL2", "--max-entries", "5"];   Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(2), max_entries: Some(5), prune: Vec::new() })));
+    test!(no_entries:      DirAction <- ["--tree", "--max-entries=0"];    Both => Err(OptionsError::BadArgument(&flags::MAX_ENTRIES, "0".into())));
+    test!(just_entries:    DirAction <- ["--max-entries=5"];              Last => Ok(DirAction::List));
+    test!(just_entries_2:  DirAction <- ["--max-entries=5"];          Complain => Err(OptionsError::Useless2(&flags::MAX_ENTRIES, &flags::RECURSE, &flags::TREE)));
+
+    // Pruning directories
+    test!(prune_tree:      DirAction <- ["--tree", "--prune=target"];     Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, max_entries: None, prune: vec![ pat("target") ] })));
+    test!(prune_two:       DirAction <- ["-R", "--prune", "node_modules|.git"];  Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, max_entries: None, prune: vec![ pat("node_modules"), pat(".git") ] })));
+    test!(just_prune:      DirAction <- ["--prune=target"];           Complain => Err(OptionsError::Useless2(&flags::PRUNE, &flags::RECURSE, &flags::TREE)));
+    test!(prune_bad:       DirAction <- ["--tree", "--prune=[a"];         Both => Err(OptionsError::FailedGlobPattern("Pattern syntax error near position 0: invalid range pattern".into())));
+
+    fn pat(string: &'static str) -> glob::Pattern {
+        glob::Pattern::new(string).unwrap()
+    }
 
     // Overriding levels
-    test!(overriding_1:    DirAction <- ["-RL=6", "-L=7"];                Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(7) })));
+    test!(overriding_1:    DirAction <- ["-RL=6", "-L=7"];                Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(7), max_entries: None, prune: Vec::new() })));
     test!(overriding_2:    DirAction <- ["-RL=6", "-L=7"];            Complain => Err(OptionsError::Duplicate(Flag::Short(b'L'), Flag::Short(b'L'))));
 }
@@ -1,9 +1,11 @@
 use std::ffi::OsString;
 use std::fmt;
 use std::num::ParseIntError;
+use std::path::PathBuf;
 
 use crate::options::flags;
 use crate::options::parser::{Arg, Flag, ParseError};
+use crate::output::table::ColumnName;
 
 
 /// Something wrong with the combination of options the user has picked.
@@ -39,8 +41,19 @@ pub enum OptionsError {
     /// A numeric option was given that failed to be parsed as a number.
     FailedParse(String, NumberSource, ParseIntError),
 
+    /// A column spec contained a name that isn’t a column.
+    UnknownColumn(String, NumberSource),
+
     /// A glob ignore was given that failed to be parsed as a pattern.
     FailedGlobPattern(String),
+
+    /// A regex given to `--ignore-regex` or `--match-regex` failed to be
+    /// parsed.
+    FailedRegexPattern(String),
+
+    /// The configuration file couldn’t be read, or had something wrong
+    /// with it.
+    BadConfig(PathBuf, String),
 }
 
 /// The source of a string that failed to be parsed as a number.
@@ -60,6 +73,12 @@ impl From<glob::PatternError> for OptionsError {
     }
 }
 
+impl From<regex::Error> for OptionsError {
+    fn from(error: regex::Error) -> Self {
+        Self::FailedRegexPattern(error.to_string())
+    }
+}
+
 impl fmt::Display for NumberSource {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -92,7 +111,10 @@ impl fmt::Display for OptionsError {
             Self::Useless2(a, b1, b2)        => write!(f, "Option {} is useless without options {} or {}", a, b1, b2),
             Self::TreeAllAll                 => write!(f, "Option --tree is useless given --all --all"),
             Self::FailedParse(s, n, e)       => write!(f, "Value {:?} not valid for {}: {}", s, n, e),
+            Self::UnknownColumn(s, n)        => write!(f, "Unknown column {:?} in {} ({})", s, n, Choices(ColumnName::ALL)),
             Self::FailedGlobPattern(ref e)   => write!(f, "Failed to parse glob pattern: {}", e),
+            Self::FailedRegexPattern(ref e)  => write!(f, "Failed to parse regex: {}", e),
+            Self::BadConfig(path, e)         => write!(f, "Error in config file {}: {}", path.display(), e),
         }
     }
 }
@@ -101,14 +123,17 @@ impl OptionsError {
 
     /// Try to second-guess what the user was trying to do, depending on what
     /// went wrong.
-    pub fn suggestion(&self) -> Option<&'static str> {
+    pub fn suggestion(&self) -> Option<String> {
         // ‘ls -lt’ and ‘ls -ltr’ are common combinations
         match self {
             Self::BadArgument(time, r) if *time == &flags::TIME && r == "r" => {
-                Some("To sort oldest files last, try \"--sort oldest\", or just \"-sold\"")
+                Some("To sort oldest files last, try \"--sort oldest\", or just \"-sold\"".into())
             }
             Self::Parse(ParseError::NeedsValue { ref flag, .. }) if *flag == Flag::Short(b't') => {
-                Some("To sort newest files last, try \"--sort newest\", or just \"-snew\"")
+                Some("To sort newest files last, try \"--sort newest\", or just \"-snew\"".into())
+            }
+            Self::UnknownColumn(name, _) => {
+                closest(name, ColumnName::ALL).map(|c| format!("Did you mean {:?}?", c))
             }
             _ => {
                 None
@@ -118,6 +143,37 @@ impl OptionsError {
 }
 
 
+/// Picks the choice that’s closest to what the user typed, as long as it’s
+/// close enough that it was probably a typo.
+fn closest(attempt: &str, choices: &[&'static str]) -> Option<&'static str> {
+    choices.iter()
+           .map(|c| (edit_distance(attempt, c), *c))
+           .filter(|&(distance, c)| distance > 0 && distance <= c.len() / 2)
+           .min_by_key(|&(distance, _)| distance)
+           .map(|(_, c)| c)
+}
+
+/// The number of single-character insertions, deletions, or substitutions
+/// it takes to turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0 ..= b.len()).collect();
+
+    for (i, ac) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, bc) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ac != *bc);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+
 /// A list of legal choices for an argument-taking option.
 #[derive(PartialEq, Eq, Debug)]
 pub struct Choices(pub &'static [&'static str]);
@@ -127,3 +183,30 @@ impl fmt::Display for Choices {
         write!(f, "choices: {}", self.0.join(", "))
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn typo() {
+        assert_eq!(closest("prem", ColumnName::ALL), Some("perm"));
+    }
+
+    #[test]
+    fn missing_letter() {
+        assert_eq!(closest("mtim", ColumnName::ALL), Some("mtime"));
+    }
+
+    #[test]
+    fn nothing_close() {
+        assert_eq!(closest("colour", ColumnName::ALL), None);
+    }
+
+    #[test]
+    fn suggestion() {
+        let error = OptionsError::UnknownColumn("szie".into(), NumberSource::Arg(&flags::COLUMNS));
+        assert_eq!(error.suggestion(), Some("Did you mean \"size\"?".into()));
+    }
+}
@@ -2,15 +2,39 @@ use crate::options::{flags, OptionsError, NumberSource};
 use crate::options::parser::MatchedFlags;
 use crate::options::vars::{self, Vars};
 
-use crate::output::file_name::{Options, Classify, ShowIcons};
+use crate::output::QuotingStyle;
+use crate::output::file_name::{Options, Classify, ShowIcons, EmbedHyperlinks};
 
 
 impl Options {
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let classify = Classify::deduce(matches)?;
         let show_icons = ShowIcons::deduce(matches, vars)?;
+        let max_width = deduce_max_width(matches)?;
+        let embed_hyperlinks = EmbedHyperlinks::deduce(matches)?;
+        let quoting_style = QuotingStyle::deduce(matches, vars)?;
 
-        Ok(Self { classify, show_icons })
+        Ok(Self { classify, show_icons, max_width, embed_hyperlinks, quoting_style })
+    }
+}
+
+/// Determine how wide file names can get before they’re truncated, which
+/// also limits the path column in the long view.
+pub fn deduce_max_width(matches: &MatchedFlags<'_>) -> Result<Option<usize>, OptionsError> {
+    if let Some(width) = matches.get(&flags::MAX_NAME_WIDTH)? {
+        let arg_str = width.to_string_lossy();
+        match arg_str.parse() {
+            Ok(w) => {
+                Ok(Some(w))
+            }
+            Err(e) => {
+                let source = NumberSource::Arg(&flags::MAX_NAME_WIDTH);
+                Err(OptionsError::FailedParse(arg_str.to_string(), source, e))
+            }
+        }
+    }
+    else {
+        Ok(None)
     }
 }
 
@@ -23,6 +47,43 @@ impl Classify {
     }
 }
 
+impl EmbedHyperlinks {
+    fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+        let flagged = matches.has(&flags::HYPERLINK)?;
+
+        if flagged { Ok(Self::On) }
+              else { Ok(Self::Off) }
+    }
+}
+
+impl QuotingStyle {
+
+    /// Determine how to quote file names. Without the option or the
+    /// environment variable, this depends on whether exa is printing to a
+    /// terminal, which gets worked out later.
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        let word =
+            if let Some(w) = matches.get(&flags::QUOTING_STYLE)? {
+                w.to_os_string()
+            }
+            else {
+                match vars.get(vars::QUOTING_STYLE) {
+                    Some(ref s) if ! s.is_empty()  => s.clone(),
+                    _                              => return Ok(Self::Automatic),
+                }
+            };
+
+        match word.to_str() {
+            Some("literal")       => Ok(Self::Literal),
+            Some("shell")         => Ok(Self::Shell),
+            Some("shell-escape")  => Ok(Self::ShellEscape),
+            Some("c")             => Ok(Self::C),
+            Some("escape")        => Ok(Self::Escape),
+            _                     => Err(OptionsError::BadArgument(&flags::QUOTING_STYLE, word)),
+        }
+    }
+}
+
 impl ShowIcons {
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         if matches.has(&flags::NO_ICONS)? || !matches.has(&flags::ICONS)? {
@@ -44,3 +105,54 @@ impl ShowIcons {
         }
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::OsString;
+    use crate::options::flags;
+    use crate::options::parser::{Flag, Arg};
+
+    use crate::options::test::parse_for_test;
+    use crate::options::test::Strictnesses::*;
+
+    static TEST_ARGS: &[&Arg] = &[ &flags::QUOTING_STYLE ];
+
+    macro_rules! test {
+        ($name:ident: $type:ident <- $inputs:expr, $vars:expr; $stricts:expr => $result:expr) => {
+            #[test]
+            fn $name() {
+                for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf, &$vars)) {
+                    assert_eq!(result, $result);
+                }
+            }
+        };
+    }
+
+    mod quoting_styles {
+        use super::*;
+
+        // Default behaviour
+        test!(empty:         QuotingStyle <- [], None;                                    Both => Ok(QuotingStyle::Automatic));
+
+        // Individual settings
+        test!(literal:       QuotingStyle <- ["--quoting-style=literal"], None;           Both => Ok(QuotingStyle::Literal));
+        test!(shell:         QuotingStyle <- ["--quoting-style", "shell"], None;          Both => Ok(QuotingStyle::Shell));
+        test!(shell_escape:  QuotingStyle <- ["--quoting-style=shell-escape"], None;      Both => Ok(QuotingStyle::ShellEscape));
+        test!(c:             QuotingStyle <- ["--quoting-style=c"], None;                 Both => Ok(QuotingStyle::C));
+        test!(escape:        QuotingStyle <- ["--quoting-style=escape"], None;            Both => Ok(QuotingStyle::Escape));
+
+        // The environment variable gets used when the option isn’t given
+        test!(use_env:       QuotingStyle <- [], Some("c".into());                        Both => Ok(QuotingStyle::C));
+        test!(override_env:  QuotingStyle <- ["--quoting-style=shell"], Some("c".into());  Both => Ok(QuotingStyle::Shell));
+
+        // Overriding
+        test!(overridden:    QuotingStyle <- ["--quoting-style=c", "--quoting-style=shell"], None;  Last => Ok(QuotingStyle::Shell));
+        test!(overridden_2:  QuotingStyle <- ["--quoting-style=c", "--quoting-style=shell"], None;  Complain => Err(OptionsError::Duplicate(Flag::Long("quoting-style"), Flag::Long("quoting-style"))));
+
+        // Errors
+        test!(locale:        QuotingStyle <- ["--quoting-style=locale"], None;            Both => Err(OptionsError::BadArgument(&flags::QUOTING_STYLE, OsString::from("locale"))));
+        test!(bad_env:       QuotingStyle <- [], Some("clocale".into());                  Both => Err(OptionsError::BadArgument(&flags::QUOTING_STYLE, OsString::from("clocale"))));
+    }
+}
@@ -1,10 +1,16 @@
 //! Parsing the options for `FileFilter`.
 
+use std::ffi::OsStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use crate::fs::DotFilter;
-use crate::fs::filter::{FileFilter, SortField, SortCase, IgnorePatterns, GitIgnore};
+use crate::fs::fields as f;
+use crate::fs::fields::TimeType;
+use crate::fs::filter::{FileFilter, SortField, SortCase, NamePatterns, GitIgnore, Predicates, PermissionMask};
+use crate::fs::total_size::TotalSize;
 
 use crate::options::{flags, OptionsError};
-use crate::options::parser::MatchedFlags;
+use crate::options::parser::{Arg, MatchedFlags};
 
 
 impl FileFilter {
@@ -17,10 +23,24 @@ impl FileFilter {
             only_dirs:        matches.has(&flags::ONLY_DIRS)?,
             sort_field:       SortField::deduce(matches)?,
             dot_filter:       DotFilter::deduce(matches)?,
-            ignore_patterns:  IgnorePatterns::deduce(matches)?,
+            ignore_patterns:  NamePatterns::deduce(matches, &flags::IGNORE_GLOB, &flags::IGNORE_REGEX)?,
+            match_patterns:   NamePatterns::deduce(matches, &flags::MATCH, &flags::MATCH_REGEX)?,
             git_ignore:       GitIgnore::deduce(matches)?,
+            total_size:       TotalSize::deduce(matches)?,
+            predicates:       Predicates::deduce(matches, SystemTime::now())?,
+            prune_empty:      Self::deduce_prune_empty(matches)?,
         })
     }
+
+    fn deduce_prune_empty(matches: &MatchedFlags<'_>) -> Result<bool, OptionsError> {
+        let prune_empty = matches.has(&flags::PRUNE_EMPTY)?;
+
+        if prune_empty && matches.is_strict() && ! matches.has(&flags::RECURSE)? && ! matches.has(&flags::TREE)? {
+            return Err(OptionsError::Useless2(&flags::PRUNE_EMPTY, &flags::RECURSE, &flags::TREE));
+        }
+
+        Ok(prune_empty)
+    }
 }
 
 impl SortField {
@@ -176,30 +196,41 @@ impl DotFilter {
 }
 
 
-impl IgnorePatterns {
+impl NamePatterns {
 
-    /// Determines the set of glob patterns to use based on the
-    /// `--ignore-glob` argument’s value. This is a list of strings
-    /// separated by pipe (`|`) characters, given in any order.
-    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+    /// Determines the set of patterns to use based on the values of a glob
+    /// argument, such as `--ignore-glob`, and a regex argument, such as
+    /// `--ignore-regex`. The globs are a list of strings separated by pipe
+    /// (`|`) characters, given in any order; the regex is one regex, as
+    /// they use pipes themselves.
+    pub fn deduce(matches: &MatchedFlags<'_>, glob_flag: &'static Arg, regex_flag: &'static Arg) -> Result<Self, OptionsError> {
 
         // If there are no inputs, we return a set of patterns that doesn’t
         // match anything, rather than, say, `None`.
-        let inputs = match matches.get(&flags::IGNORE_GLOB)? {
-            Some(is)  => is,
-            None      => return Ok(Self::empty()),
-        };
+        let mut patterns = match matches.get(glob_flag)? {
+            Some(inputs) => {
+                // Awkwardly, though, a glob pattern can be invalid, and we
+                // need to deal with invalid patterns somehow.
+                let (patterns, mut errors) = Self::parse_from_iter(inputs.to_string_lossy().split('|'));
+
+                // It can actually return more than one glob error,
+                // but we only use one. (TODO)
+                if let Some(e) = errors.pop() {
+                    return Err(e.into());
+                }
 
-        // Awkwardly, though, a glob pattern can be invalid, and we need to
-        // deal with invalid patterns somehow.
-        let (patterns, mut errors) = Self::parse_from_iter(inputs.to_string_lossy().split('|'));
+                patterns
+            }
+            None => {
+                Self::empty()
+            }
+        };
 
-        // It can actually return more than one glob error,
-        // but we only use one. (TODO)
-        match errors.pop() {
-            Some(e)  => Err(e.into()),
-            None     => Ok(patterns),
+        if let Some(input) = matches.get(regex_flag)? {
+            patterns.push_regex(regex::Regex::new(&input.to_string_lossy())?);
         }
+
+        Ok(patterns)
     }
 }
 
@@ -216,6 +247,286 @@ impl GitIgnore {
 }
 
 
+impl TotalSize {
+
+    /// Determines whether to add up the sizes of directories, and which
+    /// size to use: `--disk-usage` switches from the apparent size to the
+    /// space taken up on disk, and does nothing without `--total-size`.
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<Self>, OptionsError> {
+        let disk_usage = matches.has(&flags::DISK_USAGE)?;
+
+        if ! matches.has(&flags::TOTAL_SIZE)? {
+            if disk_usage && matches.is_strict() {
+                return Err(OptionsError::Useless(&flags::DISK_USAGE, false, &flags::TOTAL_SIZE));
+            }
+
+            Ok(None)
+        }
+        else if disk_usage {
+            Ok(Some(Self::DiskUsage))
+        }
+        else {
+            Ok(Some(Self::Apparent))
+        }
+    }
+}
+
+
+impl Predicates {
+
+    /// Determines the tests that files have to pass. Durations given to
+    /// `--newer` and `--older-than` count back from the given time.
+    pub fn deduce(matches: &MatchedFlags<'_>, now: SystemTime) -> Result<Self, OptionsError> {
+        let (owner, group) = match matches.get(&flags::OWNER)? {
+            Some(word)  => parse_owner(word)?,
+            None        => (None, None),
+        };
+
+        Ok(Self {
+            min_size:     parse_value(matches, &flags::MIN_SIZE, parse_size)?,
+            max_size:     parse_value(matches, &flags::MAX_SIZE, parse_size)?,
+            time_type:    parse_value(matches, &flags::FILTER_TIME, parse_time_type)?.unwrap_or_default(),
+            newer:        parse_value(matches, &flags::NEWER, |w| parse_time(w, now))?,
+            older:        parse_value(matches, &flags::OLDER_THAN, |w| parse_time(w, now))?,
+            types:        parse_value(matches, &flags::TYPE, parse_types)?.unwrap_or_default(),
+            permissions:  parse_value(matches, &flags::PERM, parse_permissions)?,
+            owner,
+            group,
+        })
+    }
+}
+
+/// Gets the value of a flag, if it was given, and parses it with the given
+/// function, which returns `None` if it’s invalid.
+fn parse_value<T>(matches: &MatchedFlags<'_>, flag: &'static Arg, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>, OptionsError> {
+    let word = match matches.get(flag)? {
+        Some(w)  => w,
+        None     => return Ok(None),
+    };
+
+    match word.to_str().and_then(parse) {
+        Some(value)  => Ok(Some(value)),
+        None         => Err(OptionsError::BadArgument(flag, word.into())),
+    }
+}
+
+/// Parses a size, such as `500`, `10k`, `1.5MB` or `2GiB`. Units without an
+/// `i` are powers of 1000, and the ones with an `i` are powers of 1024.
+fn parse_size(word: &str) -> Option<u64> {
+    let split = word.find(|c: char| ! c.is_ascii_digit() && c != '.').unwrap_or(word.len());
+    let (number, unit) = word.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: u64 = match unit.strip_suffix('B').unwrap_or(unit) {
+        ""          => 1,
+        "k" | "K"   => 1000,
+        "Ki" | "KI" => 1 << 10,
+        "M"         => 1000 * 1000,
+        "Mi"        => 1 << 20,
+        "G"         => 1000 * 1000 * 1000,
+        "Gi"        => 1 << 30,
+        "T"         => 1000 * 1000 * 1000 * 1000,
+        "Ti"        => 1 << 40,
+        _           => return None,
+    };
+
+    Some((number * multiplier as f64).round() as u64)
+}
+
+fn parse_time_type(word: &str) -> Option<TimeType> {
+    match word {
+        "mod" | "modified"  => Some(TimeType::Modified),
+        "ch"  | "changed"   => Some(TimeType::Changed),
+        "acc" | "accessed"  => Some(TimeType::Accessed),
+        "cr"  | "created"   => Some(TimeType::Created),
+        _                   => None,
+    }
+}
+
+/// Parses either a duration before now, such as `2d` or `1h30m`, or a
+/// date in the local time zone, such as `2023-06-01` or
+/// `2023-06-01T09:30`.
+fn parse_time(word: &str, now: SystemTime) -> Option<SystemTime> {
+    match parse_duration(word) {
+        Some(duration)  => now.checked_sub(duration),
+        None            => parse_date(word),
+    }
+}
+
+/// Parses a duration written as one or more numbers with units, from
+/// seconds (`s`) up to years (`y`). A month (`mo`) is 30 days, and a year
+/// is 365.
+fn parse_duration(word: &str) -> Option<Duration> {
+    if word.is_empty() {
+        return None;
+    }
+
+    let mut seconds: u64 = 0;
+    let mut rest = word;
+    while ! rest.is_empty() {
+        let digits = rest.find(|c: char| ! c.is_ascii_digit()).unwrap_or(rest.len());
+        let units = rest[digits ..].find(|c: char| c.is_ascii_digit()).map_or(rest.len(), |i| digits + i);
+        let number: u64 = rest[.. digits].parse().ok()?;
+
+        let unit = match &rest[digits .. units] {
+            "s"          => 1,
+            "m" | "min"  => 60,
+            "h"          => 60 * 60,
+            "d"          => 60 * 60 * 24,
+            "w"          => 60 * 60 * 24 * 7,
+            "mo"         => 60 * 60 * 24 * 30,
+            "y"          => 60 * 60 * 24 * 365,
+            _            => return None,
+        };
+
+        seconds = seconds.checked_add(number.checked_mul(unit)?)?;
+        rest = &rest[units ..];
+    }
+
+    Some(Duration::from_secs(seconds))
+}
+
+/// Parses a date, with an optional time separated by a `T` or a space.
+fn parse_date(word: &str) -> Option<SystemTime> {
+    let (date, time) = match word.split_once(['T', ' ']) {
+        Some((date, time))  => (date, Some(time)),
+        None                => (word, None),
+    };
+
+    let mut numbers = date.splitn(3, '-').map(str::parse::<u32>);
+    let year = numbers.next()?.ok()?;
+    let month = numbers.next()?.ok()?;
+    let day = numbers.next()?.ok()?;
+
+    let (hour, minute, second) = match time {
+        Some(time) => {
+            let mut numbers = time.splitn(3, ':').map(str::parse::<u32>);
+            let hour = numbers.next()?.ok()?;
+            let minute = numbers.next()?.ok()?;
+            let second = numbers.next().unwrap_or(Ok(0)).ok()?;
+            (hour, minute, second)
+        }
+        None => (0, 0, 0),
+    };
+
+    if ! (1 ..= 12).contains(&month) || ! (1 ..= 31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let timestamp = local_timestamp(year as i32, month, day, hour, minute, second)?;
+    match u64::try_from(timestamp) {
+        Ok(secs)  => UNIX_EPOCH.checked_add(Duration::from_secs(secs)),
+        Err(_)    => UNIX_EPOCH.checked_sub(Duration::from_secs(timestamp.unsigned_abs())),
+    }
+}
+
+/// Converts a date and time in the local time zone to a Unix timestamp,
+/// letting the C library deal with time zones and daylight saving. The
+/// conversion at the end is only needed where `time_t` is 32 bits.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)]
+fn local_timestamp(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Option<i64> {
+    // SAFETY: a zeroed `tm` is valid, and `mktime` only reads and
+    // normalises the one it’s given
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year  = year - 1900;
+    tm.tm_mon   = month as i32 - 1;
+    tm.tm_mday  = day as i32;
+    tm.tm_hour  = hour as i32;
+    tm.tm_min   = minute as i32;
+    tm.tm_sec   = second as i32;
+    tm.tm_isdst = -1;
+
+    match unsafe { libc::mktime(&mut tm) } {
+        -1  => None,
+        t   => Some(i64::from(t)),
+    }
+}
+
+/// Converts a date and time to a Unix timestamp, treating it as UTC.
+#[cfg(windows)]
+fn local_timestamp(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Option<i64> {
+    // Howard Hinnant’s days-from-civil algorithm
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86400 + i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second))
+}
+
+/// Parses a comma-separated list of file types, using the letters that
+/// `find -type` uses.
+fn parse_types(word: &str) -> Option<Vec<f::Type>> {
+    word.split(',').map(|letter| {
+        match letter.trim() {
+            "f"  => Some(f::Type::File),
+            "d"  => Some(f::Type::Directory),
+            "l"  => Some(f::Type::Link),
+            "p"  => Some(f::Type::Pipe),
+            "s"  => Some(f::Type::Socket),
+            "b"  => Some(f::Type::BlockDevice),
+            "c"  => Some(f::Type::CharDevice),
+            _    => None,
+        }
+    }).collect()
+}
+
+/// Parses an octal permission mask, which has to match exactly unless
+/// it starts with `-`, when all of its bits have to be set, or `/`, when
+/// any of them do.
+fn parse_permissions(word: &str) -> Option<PermissionMask> {
+    let (constructor, digits): (fn(u32) -> PermissionMask, &str) = match word.as_bytes().first() {
+        Some(b'-')  => (PermissionMask::All, &word[1 ..]),
+        Some(b'/')  => (PermissionMask::Any, &word[1 ..]),
+        _           => (PermissionMask::Exact, word),
+    };
+
+    match u32::from_str_radix(digits, 8) {
+        Ok(bits) if bits <= 0o7777 && ! digits.starts_with('+')  => Some(constructor(bits)),
+        _                                                        => None,
+    }
+}
+
+/// Parses the value of `--owner`, which is a user, a group after a colon,
+/// or both, as `USER:GROUP`. Each one can be a name or an ID. (The name
+/// `--group` was already taken by the option to show the group column.)
+#[cfg(unix)]
+fn parse_owner(word: &OsStr) -> Result<(Option<u32>, Option<u32>), OptionsError> {
+    let bad = || OptionsError::BadArgument(&flags::OWNER, word.into());
+    let text = word.to_str().ok_or_else(bad)?;
+
+    let (user, group) = match text.split_once(':') {
+        Some((user, group))  => (user, group),
+        None                 => (text, ""),
+    };
+
+    if user.is_empty() && group.is_empty() {
+        return Err(bad());
+    }
+
+    let uid = match user {
+        ""    => None,
+        user  => Some(user.parse().ok().or_else(|| users::get_user_by_name(user).map(|u| u.uid())).ok_or_else(bad)?),
+    };
+
+    let gid = match group {
+        ""     => None,
+        group  => Some(group.parse().ok().or_else(|| users::get_group_by_name(group).map(|g| g.gid())).ok_or_else(bad)?),
+    };
+
+    Ok((uid, gid))
+}
+
+#[cfg(windows)]
+fn parse_owner(_word: &OsStr) -> Result<(Option<u32>, Option<u32>), OptionsError> {
+    Err(OptionsError::Unsupported(String::from("Option --owner can't be used on Windows")))
+}
+
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -231,7 +542,11 @@ mod test {
                 use crate::options::test::parse_for_test;
                 use crate::options::test::Strictnesses::*;
 
-                static TEST_ARGS: &[&Arg] = &[ &flags::SORT, &flags::ALL, &flags::TREE, &flags::IGNORE_GLOB, &flags::GIT_IGNORE ];
+                static TEST_ARGS: &[&Arg] = &[ &flags::SORT, &flags::ALL, &flags::TREE, &flags::IGNORE_GLOB, &flags::GIT_IGNORE,
+                                               &flags::TOTAL_SIZE, &flags::DISK_USAGE, &flags::MIN_SIZE, &flags::MAX_SIZE,
+                                               &flags::NEWER, &flags::OLDER_THAN, &flags::FILTER_TIME, &flags::TYPE,
+                                               &flags::OWNER, &flags::PERM, &flags::IGNORE_REGEX, &flags::MATCH,
+                                               &flags::MATCH_REGEX ];
                 for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf)) {
                     assert_eq!(result, $result);
                 }
@@ -300,17 +615,58 @@ mod test {
             glob::Pattern::new(string).unwrap()
         }
 
+        struct Ignore;
+
+        impl Ignore {
+            fn deduce(matches: &MatchedFlags<'_>) -> Result<NamePatterns, OptionsError> {
+                NamePatterns::deduce(matches, &flags::IGNORE_GLOB, &flags::IGNORE_REGEX)
+            }
+        }
+
         // Various numbers of globs
-        test!(none:   IgnorePatterns <- [];                                        Both => Ok(IgnorePatterns::empty()));
-        test!(one:    IgnorePatterns <- ["--ignore-glob", "*.ogg"];                Both => Ok(IgnorePatterns::from_iter(vec![ pat("*.ogg") ])));
-        test!(two:    IgnorePatterns <- ["--ignore-glob=*.ogg|*.MP3"];             Both => Ok(IgnorePatterns::from_iter(vec![ pat("*.ogg"), pat("*.MP3") ])));
-        test!(loads:  IgnorePatterns <- ["-I*|?|.|*"];                             Both => Ok(IgnorePatterns::from_iter(vec![ pat("*"), pat("?"), pat("."), pat("*") ])));
+        test!(none:   Ignore <- [];                                        Both => Ok(NamePatterns::empty()));
+        test!(one:    Ignore <- ["--ignore-glob", "*.ogg"];                Both => Ok(NamePatterns::from_iter(vec![ pat("*.ogg") ])));
+        test!(two:    Ignore <- ["--ignore-glob=*.ogg|*.MP3"];             Both => Ok(NamePatterns::from_iter(vec![ pat("*.ogg"), pat("*.MP3") ])));
+        test!(loads:  Ignore <- ["-I*|?|.|*"];                             Both => Ok(NamePatterns::from_iter(vec![ pat("*"), pat("?"), pat("."), pat("*") ])));
 
         // Overriding
-        test!(overridden:   IgnorePatterns <- ["-I=*.ogg",    "-I", "*.mp3"];      Last => Ok(IgnorePatterns::from_iter(vec![ pat("*.mp3") ])));
-        test!(overridden_2: IgnorePatterns <- ["-I", "*.OGG", "-I*.MP3"];          Last => Ok(IgnorePatterns::from_iter(vec![ pat("*.MP3") ])));
-        test!(overridden_3: IgnorePatterns <- ["-I=*.ogg",    "-I", "*.mp3"];  Complain => Err(OptionsError::Duplicate(Flag::Short(b'I'), Flag::Short(b'I'))));
-        test!(overridden_4: IgnorePatterns <- ["-I", "*.OGG", "-I*.MP3"];      Complain => Err(OptionsError::Duplicate(Flag::Short(b'I'), Flag::Short(b'I'))));
+        test!(overridden:   Ignore <- ["-I=*.ogg",    "-I", "*.mp3"];      Last => Ok(NamePatterns::from_iter(vec![ pat("*.mp3") ])));
+        test!(overridden_2: Ignore <- ["-I", "*.OGG", "-I*.MP3"];          Last => Ok(NamePatterns::from_iter(vec![ pat("*.MP3") ])));
+        test!(overridden_3: Ignore <- ["-I=*.ogg",    "-I", "*.mp3"];  Complain => Err(OptionsError::Duplicate(Flag::Short(b'I'), Flag::Short(b'I'))));
+        test!(overridden_4: Ignore <- ["-I", "*.OGG", "-I*.MP3"];      Complain => Err(OptionsError::Duplicate(Flag::Short(b'I'), Flag::Short(b'I'))));
+    }
+
+
+    mod match_patterns {
+        use super::*;
+        use std::iter::FromIterator;
+
+        fn pat(string: &'static str) -> glob::Pattern {
+            glob::Pattern::new(string).unwrap()
+        }
+
+        fn regex(string: &'static str) -> NamePatterns {
+            let mut patterns = NamePatterns::empty();
+            patterns.push_regex(regex::Regex::new(string).unwrap());
+            patterns
+        }
+
+        fn regex_error(string: &'static str) -> OptionsError {
+            OptionsError::FailedRegexPattern(regex::Regex::new(string).unwrap_err().to_string())
+        }
+
+        struct Match;
+
+        impl Match {
+            fn deduce(matches: &MatchedFlags<'_>) -> Result<NamePatterns, OptionsError> {
+                NamePatterns::deduce(matches, &flags::MATCH, &flags::MATCH_REGEX)
+            }
+        }
+
+        test!(none:       Match <- [];                              Both => Ok(NamePatterns::empty()));
+        test!(globs:      Match <- ["--match=*.rs|src/**/*.toml"];  Both => Ok(NamePatterns::from_iter(vec![ pat("*.rs"), pat("src/**/*.toml") ])));
+        test!(one_regex:  Match <- ["--match-regex", "^a|b$"];      Both => Ok(regex("^a|b$")));
+        test!(bad_regex:  Match <- ["--match-regex", "("];          Both => Err(regex_error("(")));
     }
 
 
@@ -320,4 +676,75 @@ mod test {
         test!(off:  GitIgnore <- [];                Both => Ok(GitIgnore::Off));
         test!(on:   GitIgnore <- ["--git-ignore"];  Both => Ok(GitIgnore::CheckAndIgnore));
     }
+
+
+    mod total_sizes {
+        use super::*;
+
+        test!(off:         TotalSize <- [];                                Both => Ok(None));
+        test!(apparent:    TotalSize <- ["--total-size"];                  Both => Ok(Some(TotalSize::Apparent)));
+        test!(disk_usage:  TotalSize <- ["--total-size", "--disk-usage"];  Both => Ok(Some(TotalSize::DiskUsage)));
+
+        test!(useless:     TotalSize <- ["--disk-usage"];                  Last => Ok(None));
+        test!(useless_2:   TotalSize <- ["--disk-usage"];                  Complain => Err(OptionsError::Useless(&flags::DISK_USAGE, false, &flags::TOTAL_SIZE)));
+    }
+
+
+    mod predicates {
+        use super::*;
+
+        /// Predicates get deduced relative to the current time, so these
+        /// tests use a fixed one instead.
+        struct Fixed;
+
+        impl Fixed {
+            fn deduce(matches: &MatchedFlags<'_>) -> Result<Predicates, OptionsError> {
+                Predicates::deduce(matches, now())
+            }
+        }
+
+        fn now() -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(1_000_000_000)
+        }
+
+        test!(empty:       Fixed <- [];                         Both => Ok(Predicates::default()));
+        test!(min_size:    Fixed <- ["--min-size=10k"];         Both => Ok(Predicates { min_size: Some(10_000), .. Predicates::default() }));
+        test!(max_size:    Fixed <- ["--max-size", "1.5MiB"];   Both => Ok(Predicates { max_size: Some(1_572_864), .. Predicates::default() }));
+        test!(bad_size:    Fixed <- ["--min-size=lots"];        Both => Err(OptionsError::BadArgument(&flags::MIN_SIZE, OsString::from("lots"))));
+
+        test!(newer:       Fixed <- ["--newer=1h30m"];          Both => Ok(Predicates { newer: Some(now() - Duration::from_secs(5400)), .. Predicates::default() }));
+        test!(filter_time: Fixed <- ["--filter-time=ch"];       Both => Ok(Predicates { time_type: TimeType::Changed, .. Predicates::default() }));
+        test!(bad_time:    Fixed <- ["--older-than=soon"];      Both => Err(OptionsError::BadArgument(&flags::OLDER_THAN, OsString::from("soon"))));
+
+        test!(types:       Fixed <- ["--type=f,l"];             Both => Ok(Predicates { types: vec![ f::Type::File, f::Type::Link ], .. Predicates::default() }));
+        test!(bad_type:    Fixed <- ["--type=f,x"];             Both => Err(OptionsError::BadArgument(&flags::TYPE, OsString::from("f,x"))));
+
+        test!(perm_exact:  Fixed <- ["--perm=644"];             Both => Ok(Predicates { permissions: Some(PermissionMask::Exact(0o644)), .. Predicates::default() }));
+        test!(perm_all:    Fixed <- ["--perm=-111"];            Both => Ok(Predicates { permissions: Some(PermissionMask::All(0o111)), .. Predicates::default() }));
+        test!(perm_any:    Fixed <- ["--perm=/6000"];           Both => Ok(Predicates { permissions: Some(PermissionMask::Any(0o6000)), .. Predicates::default() }));
+        test!(bad_perm:    Fixed <- ["--perm=rwx"];             Both => Err(OptionsError::BadArgument(&flags::PERM, OsString::from("rwx"))));
+
+        #[cfg(unix)]
+        test!(owner_ids:   Fixed <- ["--owner=1000:100"];       Both => Ok(Predicates { owner: Some(1000), group: Some(100), .. Predicates::default() }));
+        #[cfg(unix)]
+        test!(group_id:    Fixed <- ["--owner=:100"];           Both => Ok(Predicates { group: Some(100), .. Predicates::default() }));
+
+        #[test]
+        fn durations() {
+            assert_eq!(parse_duration("2d"),    Some(Duration::from_secs(2 * 86400)));
+            assert_eq!(parse_duration("1w3h"),  Some(Duration::from_secs(7 * 86400 + 3 * 3600)));
+            assert_eq!(parse_duration("6mo"),   Some(Duration::from_secs(6 * 30 * 86400)));
+            assert_eq!(parse_duration("10min"), Some(Duration::from_secs(600)));
+            assert_eq!(parse_duration("d"),     None);
+            assert_eq!(parse_duration("3"),     None);
+        }
+
+        #[test]
+        fn sizes() {
+            assert_eq!(parse_size("500"),  Some(500));
+            assert_eq!(parse_size("2KiB"), Some(2048));
+            assert_eq!(parse_size("1G"),   Some(1_000_000_000));
+            assert_eq!(parse_size("3X"),   None);
+        }
+    }
 }
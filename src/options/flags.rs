@@ -13,24 +13,57 @@ pub static ACROSS:   Arg = Arg { short: Some(b'x'), long: "across",   takes_valu
 pub static RECURSE:  Arg = Arg { short: Some(b'R'), long: "recurse",  takes_value: TakesValue::Forbidden };
 pub static TREE:     Arg = Arg { short: Some(b'T'), long: "tree",     takes_value: TakesValue::Forbidden };
 pub static CLASSIFY: Arg = Arg { short: Some(b'F'), long: "classify", takes_value: TakesValue::Forbidden };
+pub static JSON:     Arg = Arg { short: None,       long: "json",     takes_value: TakesValue::Forbidden };
+pub static PRINT0:   Arg = Arg { short: Some(b'0'), long: "print0",   takes_value: TakesValue::Forbidden };
+pub static SUMMARY:  Arg = Arg { short: None,       long: "summary",  takes_value: TakesValue::Forbidden };
+pub static HYPERLINK: Arg = Arg { short: None,      long: "hyperlink", takes_value: TakesValue::Forbidden };
+pub static QUOTING_STYLE: Arg = Arg { short: None,  long: "quoting-style", takes_value: TakesValue::Necessary(Some(QUOTING_STYLES)) };
+const QUOTING_STYLES: Values = &["literal", "shell", "shell-escape", "c", "escape"];
+pub static TREE_STYLE: Arg = Arg { short: None,     long: "tree-style", takes_value: TakesValue::Necessary(Some(TREE_STYLES)) };
+const TREE_STYLES: Values = &["unicode", "rounded", "ascii", "indent", "heavy"];
+pub static TREE_COMPACT: Arg = Arg { short: None,   long: "tree-compact", takes_value: TakesValue::Forbidden };
 
 pub static COLOR:  Arg = Arg { short: None, long: "color",  takes_value: TakesValue::Necessary(Some(COLOURS)) };
 pub static COLOUR: Arg = Arg { short: None, long: "colour", takes_value: TakesValue::Necessary(Some(COLOURS)) };
 const COLOURS: &[&str] = &["always", "auto", "never"];
 
-pub static COLOR_SCALE:  Arg = Arg { short: None, long: "color-scale",  takes_value: TakesValue::Forbidden };
-pub static COLOUR_SCALE: Arg = Arg { short: None, long: "colour-scale", takes_value: TakesValue::Forbidden };
+pub static THEME: Arg = Arg { short: None, long: "theme", takes_value: TakesValue::Necessary(None) };
+
+pub static COLOR_SCALE:  Arg = Arg { short: None, long: "color-scale",  takes_value: TakesValue::Optional(Some(COLOR_SCALES)) };
+pub static COLOUR_SCALE: Arg = Arg { short: None, long: "colour-scale", takes_value: TakesValue::Optional(Some(COLOR_SCALES)) };
+const COLOR_SCALES: Values = &["all", "size", "age"];
+
+pub static COLOR_SCALE_MODE:  Arg = Arg { short: None, long: "color-scale-mode",  takes_value: TakesValue::Necessary(Some(COLOR_SCALE_MODES)) };
+pub static COLOUR_SCALE_MODE: Arg = Arg { short: None, long: "colour-scale-mode", takes_value: TakesValue::Necessary(Some(COLOR_SCALE_MODES)) };
+const COLOR_SCALE_MODES: Values = &["fixed", "gradient"];
+
+pub static TREE_COLORS:  Arg = Arg { short: None, long: "tree-colors",  takes_value: TakesValue::Forbidden };
+pub static TREE_COLOURS: Arg = Arg { short: None, long: "tree-colours", takes_value: TakesValue::Forbidden };
 
 // filtering and sorting options
 pub static ALL:         Arg = Arg { short: Some(b'a'), long: "all",         takes_value: TakesValue::Forbidden };
 pub static LIST_DIRS:   Arg = Arg { short: Some(b'd'), long: "list-dirs",   takes_value: TakesValue::Forbidden };
 pub static LEVEL:       Arg = Arg { short: Some(b'L'), long: "level",       takes_value: TakesValue::Necessary(None) };
+pub static MAX_ENTRIES: Arg = Arg { short: None, long: "max-entries", takes_value: TakesValue::Necessary(None) };
+pub static PRUNE:       Arg = Arg { short: None,       long: "prune",       takes_value: TakesValue::Necessary(None) };
 pub static REVERSE:     Arg = Arg { short: Some(b'r'), long: "reverse",     takes_value: TakesValue::Forbidden };
 pub static SORT:        Arg = Arg { short: Some(b's'), long: "sort",        takes_value: TakesValue::Necessary(Some(SORTS)) };
 pub static IGNORE_GLOB: Arg = Arg { short: Some(b'I'), long: "ignore-glob", takes_value: TakesValue::Necessary(None) };
 pub static GIT_IGNORE:  Arg = Arg { short: None, long: "git-ignore",           takes_value: TakesValue::Forbidden };
+pub static MATCH:       Arg = Arg { short: None, long: "match",        takes_value: TakesValue::Necessary(None) };
+pub static IGNORE_REGEX: Arg = Arg { short: None, long: "ignore-regex", takes_value: TakesValue::Necessary(None) };
+pub static MATCH_REGEX: Arg = Arg { short: None, long: "match-regex",  takes_value: TakesValue::Necessary(None) };
 pub static DIRS_FIRST:  Arg = Arg { short: None, long: "group-directories-first",  takes_value: TakesValue::Forbidden };
 pub static ONLY_DIRS:   Arg = Arg { short: Some(b'D'), long: "only-dirs", takes_value: TakesValue::Forbidden };
+pub static MIN_SIZE:    Arg = Arg { short: None, long: "min-size",    takes_value: TakesValue::Necessary(None) };
+pub static MAX_SIZE:    Arg = Arg { short: None, long: "max-size",    takes_value: TakesValue::Necessary(None) };
+pub static NEWER:       Arg = Arg { short: None, long: "newer",       takes_value: TakesValue::Necessary(None) };
+pub static OLDER_THAN:  Arg = Arg { short: None, long: "older-than",  takes_value: TakesValue::Necessary(None) };
+pub static FILTER_TIME: Arg = Arg { short: None, long: "filter-time", takes_value: TakesValue::Necessary(Some(TIMES)) };
+pub static TYPE:        Arg = Arg { short: None, long: "type",        takes_value: TakesValue::Necessary(None) };
+pub static OWNER:       Arg = Arg { short: None, long: "owner",       takes_value: TakesValue::Necessary(None) };
+pub static PERM:        Arg = Arg { short: None, long: "perm",        takes_value: TakesValue::Necessary(None) };
+pub static PRUNE_EMPTY: Arg = Arg { short: None, long: "prune-empty", takes_value: TakesValue::Forbidden };
 const SORTS: Values = &[ "name", "Name", "size", "extension",
                          "Extension", "modified", "changed", "accessed",
                          "created", "inode", "type", "none" ];
@@ -51,8 +84,10 @@ pub static TIME:       Arg = Arg { short: Some(b't'), long: "time",       takes_
 pub static ACCESSED:   Arg = Arg { short: Some(b'u'), long: "accessed",   takes_value: TakesValue::Forbidden };
 pub static CREATED:    Arg = Arg { short: Some(b'U'), long: "created",    takes_value: TakesValue::Forbidden };
 pub static TIME_STYLE: Arg = Arg { short: None,       long: "time-style", takes_value: TakesValue::Necessary(Some(TIME_STYLES)) };
+pub static TOTAL_SIZE: Arg = Arg { short: None,       long: "total-size", takes_value: TakesValue::Forbidden };
+pub static DISK_USAGE: Arg = Arg { short: None,       long: "disk-usage", takes_value: TakesValue::Forbidden };
 const TIMES: Values = &["modified", "changed", "accessed", "created"];
-const TIME_STYLES: Values = &["default", "long-iso", "full-iso", "iso"];
+const TIME_STYLES: Values = &["default", "long-iso", "full-iso", "iso", "relative"];
 
 // suppressing columns
 pub static NO_PERMISSIONS: Arg = Arg { short: None, long: "no-permissions", takes_value: TakesValue::Forbidden };
@@ -61,10 +96,17 @@ pub static NO_USER: Arg = Arg { short: None, long: "no-user", takes_value: Takes
 pub static NO_TIME: Arg = Arg { short: None, long: "no-time", takes_value: TakesValue::Forbidden };
 pub static NO_ICONS: Arg = Arg { short: None, long: "no-icons", takes_value: TakesValue::Forbidden };
 
+// choosing and laying out columns
+pub static COLUMNS: Arg = Arg { short: None, long: "columns", takes_value: TakesValue::Necessary(None) };
+pub static ALIGN:   Arg = Arg { short: None, long: "align",   takes_value: TakesValue::Necessary(None) };
+pub static MAX_NAME_WIDTH: Arg = Arg { short: None, long: "max-name-width", takes_value: TakesValue::Necessary(None) };
+
 // optional feature options
 pub static GIT:       Arg = Arg { short: None,       long: "git",               takes_value: TakesValue::Forbidden };
+pub static GIT_UNTRACKED_DEPTH: Arg = Arg { short: None, long: "git-untracked-depth", takes_value: TakesValue::Necessary(None) };
 pub static EXTENDED:  Arg = Arg { short: Some(b'@'), long: "extended",          takes_value: TakesValue::Forbidden };
 pub static OCTAL:     Arg = Arg { short: None,       long: "octal-permissions", takes_value: TakesValue::Forbidden };
+pub static MAGIC:     Arg = Arg { short: None,       long: "magic",             takes_value: TakesValue::Forbidden };
 
 // my own features
 pub static PATH:  Arg = Arg { short: Some(b'p'), long: "add-path",  takes_value: TakesValue::Forbidden };
@@ -72,17 +114,18 @@ pub static PATH:  Arg = Arg { short: Some(b'p'), long: "add-path",  takes_value:
 pub static ALL_ARGS: Args = Args(&[
     &VERSION, &HELP,
 
-    &ONE_LINE, &LONG, &GRID, &ACROSS, &RECURSE, &TREE, &CLASSIFY,
-    &COLOR, &COLOUR, &COLOR_SCALE, &COLOUR_SCALE,
+    &ONE_LINE, &LONG, &GRID, &ACROSS, &RECURSE, &TREE, &CLASSIFY, &JSON, &PRINT0, &SUMMARY, &HYPERLINK, &QUOTING_STYLE, &TREE_STYLE, &TREE_COMPACT,
+    &COLOR, &COLOUR, &COLOR_SCALE, &COLOUR_SCALE, &COLOR_SCALE_MODE, &COLOUR_SCALE_MODE, &TREE_COLORS, &TREE_COLOURS, &THEME,
 
-    &ALL, &LIST_DIRS, &LEVEL, &REVERSE, &SORT, &DIRS_FIRST,
-    &IGNORE_GLOB, &GIT_IGNORE, &ONLY_DIRS,
+    &ALL, &LIST_DIRS, &LEVEL, &MAX_ENTRIES, &PRUNE, &REVERSE, &SORT, &DIRS_FIRST,
+    &IGNORE_GLOB, &GIT_IGNORE, &ONLY_DIRS, &MATCH, &IGNORE_REGEX, &MATCH_REGEX,
+    &MIN_SIZE, &MAX_SIZE, &NEWER, &OLDER_THAN, &FILTER_TIME, &TYPE, &OWNER, &PERM, &PRUNE_EMPTY,
 
     &BINARY, &BYTES, &GROUP, &NUMERIC, &HEADER, &ICONS, &INODE, &LINKS, &MODIFIED, &CHANGED,
-    &BLOCKS, &TIME, &ACCESSED, &CREATED, &TIME_STYLE,
-    &NO_PERMISSIONS, &NO_FILESIZE, &NO_USER, &NO_TIME, &NO_ICONS,
+    &BLOCKS, &TIME, &ACCESSED, &CREATED, &TIME_STYLE, &TOTAL_SIZE, &DISK_USAGE,
+    &NO_PERMISSIONS, &NO_FILESIZE, &NO_USER, &NO_TIME, &NO_ICONS, &COLUMNS, &ALIGN, &MAX_NAME_WIDTH,
 
-    &GIT, &EXTENDED, &OCTAL,
+    &GIT, &GIT_UNTRACKED_DEPTH, &EXTENDED, &OCTAL, &MAGIC,
 
     &PATH
 ]);
@@ -19,21 +19,47 @@ DISPLAY OPTIONS
   -x, --across       sort the grid across, rather than downwards
   -R, --recurse      recurse into directories
   -T, --tree         recurse into directories as a tree
+  --tree-style=STYLE  how to draw the tree (unicode, rounded, ascii, indent, heavy)
+  --tree-colo[u]rs   colour each level of the tree differently
+  --tree-compact     merge directories with only one directory inside into one row
   -F, --classify     display type indicator by file names
+  --json             display each entry's metadata as a JSON object
+  -0, --print0       display raw paths separated by NUL characters
+  --summary          count the files, directories, and sizes after each listing
   --colo[u]r=WHEN    when to use terminal colours (always, auto, never)
-  --colo[u]r-scale   highlight levels of file sizes distinctly
+  --colo[u]r-scale   colour fields on a scale (size, age, all)
+  --colo[u]r-scale-mode  how to scale sizes (fixed, gradient)
+  --theme=THEME      which colour theme to use (vscode, exa, or a file)
   --icons            display icons
   --no-icons         don't display icons (always overrides --icons)
+  --hyperlink        display entries as hyperlinks
+  --quoting-style=STYLE  how to quote file names (literal, shell, shell-escape, c, escape)
 
 FILTERING AND SORTING OPTIONS
   -a, --all                  show hidden and 'dot' files
   -d, --list-dirs            list directories as files; don't list their contents
   -L, --level DEPTH          limit the depth of recursion
+  --max-entries COUNT        list at most this many entries of each directory
+  --prune GLOBS              list these directories without recursing into them
   -r, --reverse              reverse the sort order
   -s, --sort SORT_FIELD      which field to sort by
   --group-directories-first  list directories before other files
   -D, --only-dirs            list only directories
-  -I, --ignore-glob GLOBS    glob patterns (pipe-separated) of files to ignore";
+  -I, --ignore-glob GLOBS    glob patterns (pipe-separated) of files to ignore
+  --match GLOBS              glob patterns (pipe-separated) of files to show
+  --ignore-regex REGEX       regular expression of files to ignore
+  --match-regex REGEX        regular expression of files to show
+  --min-size SIZE            list only files at least this big, such as 10k
+  --max-size SIZE            list only files at most this big, such as 2MiB
+  --newer TIME               list only files newer than a date or a duration ago
+  --older-than TIME          list only files older than a date or a duration ago
+  --filter-time WORD         which timestamp --newer and --older-than use
+  --type TYPES               list only these types of file (comma-separated)
+  --owner USER[:GROUP]       list only files with this owner and/or group
+                             (no --group filter, as -g/--group is a column)
+  --perm MODE                list only files with these permission bits
+  --prune-empty              hide directories with nothing left in them
+  --git-ignore               ignore files mentioned in '.gitignore' or '.ignore'";
 
   static USAGE_PART2: &str = "  \
   Valid sort fields:         name, Name, extension, Extension, size, type,
@@ -54,15 +80,21 @@ LONG VIEW OPTIONS
   -u, --accessed       use the accessed timestamp field
   -U, --created        use the created timestamp field
   --changed            use the changed timestamp field
-  --time-style         how to format timestamps (default, iso, long-iso, full-iso)
+  --time-style         how to format timestamps (default, iso, long-iso, full-iso, relative, +FORMAT)
+  --total-size         show the total size of each directory's contents
+  --disk-usage         add up the space used on disk for --total-size
   --no-permissions     suppress the permissions field
   --octal-permissions  list each file's permission in octal format
   --no-filesize        suppress the filesize field
   --no-user            suppress the user field
-  --no-time            suppress the time field";
-
-static GIT_FILTER_HELP: &str = "  --git-ignore               ignore files mentioned in '.gitignore'";
-static GIT_VIEW_HELP:   &str = "  --git                list each file's Git status, if tracked or ignored";
+  --no-time            suppress the time field
+  --columns SPEC       which columns to list, in order (e.g. perm,size,mtime,name)
+  --align SPEC         override column alignments (e.g. size:left,user:right)
+  --max-name-width N   shorten names (and paths) wider than N characters
+  --magic              read the start of each file to work out its type";
+
+static GIT_VIEW_HELP:   &str = "  --git                list each file's Git status, if tracked or ignored
+  --git-untracked-depth DEPTH  limit how deep to look inside untracked directories";
 static EXTENDED_HELP:   &str = "  -@, --extended       list each file's extended attributes and sizes";
 
 
@@ -98,10 +130,6 @@ impl fmt::Display for HelpString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{}", USAGE_PART1)?;
 
-        if cfg!(feature = "git") {
-            write!(f, "\n{}", GIT_FILTER_HELP)?;
-        }
-
         write!(f, "\n{}", USAGE_PART2)?;
 
         if cfg!(feature = "git") {
@@ -119,27 +147,30 @@ impl fmt::Display for HelpString {
 
 #[cfg(test)]
 mod test {
-    use crate::options::{Options, OptionsResult};
+    use crate::options::{Config, Options, OptionsResult};
     use std::ffi::OsStr;
 
     #[test]
     fn help() {
         let args = vec![ OsStr::new("--help") ];
-        let opts = Options::parse(args, &None);
+        let config = Config::default();
+        let opts = Options::parse(args, &config, &None);
         assert!(matches!(opts, OptionsResult::Help(_)));
     }
 
     #[test]
     fn help_with_file() {
         let args = vec![ OsStr::new("--help"), OsStr::new("me") ];
-        let opts = Options::parse(args, &None);
+        let config = Config::default();
+        let opts = Options::parse(args, &config, &None);
         assert!(matches!(opts, OptionsResult::Help(_)));
     }
 
     #[test]
     fn unhelpful() {
         let args = vec![];
-        let opts = Options::parse(args, &None);
+        let config = Config::default();
+        let opts = Options::parse(args, &config, &None);
         assert!(! matches!(opts, OptionsResult::Help(_)))  // no help when --help isn’t passed
     }
 }
@@ -69,13 +69,16 @@
 //! it’s clear what the user wants.
 
 
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 
 use crate::fs::dir_action::DirAction;
-use crate::fs::filter::{FileFilter, GitIgnore};
+use crate::fs::filter::FileFilter;
 use crate::output::{View, Mode, details, grid_details};
 use crate::theme::Options as ThemeOptions;
 
+mod config;
+pub use self::config::Config;
+
 mod dir_action;
 mod file_name;
 mod filter;
@@ -92,6 +95,9 @@ use self::help::HelpString;
 mod parser;
 use self::parser::MatchedFlags;
 
+mod rules;
+mod theme_file;
+
 pub mod vars;
 pub use self::vars::Vars;
 
@@ -119,15 +125,19 @@ pub struct Options {
 
     /// The options to make up the styles of the UI and file names.
     pub theme: ThemeOptions,
+
+    /// How many levels of untracked directories to look inside when getting
+    /// Git statuses, or `None` to look inside all of them.
+    pub git_untracked_depth: Option<usize>,
 }
 
 impl Options {
 
     /// Parse the given iterator of command-line strings into an Options
-    /// struct and a list of free filenames, using the environment variables
-    /// for extra options.
+    /// struct and a list of free filenames, using the configuration file
+    /// and the environment variables for extra options.
     #[allow(unused_results)]
-    pub fn parse<'args, I, V>(args: I, vars: &V) -> OptionsResult<'args>
+    pub fn parse<'args, I, V>(args: I, config: &'args Config, vars: &V) -> OptionsResult<'args>
     where I: IntoIterator<Item = &'args OsStr>,
           V: Vars,
     {
@@ -139,7 +149,15 @@ impl Options {
             Some(_)                      => Strictness::ComplainAboutRedundantArguments,
         };
 
-        let Matches { flags, frees } = match flags::ALL_ARGS.parse(args, strictness) {
+        if strictness == Strictness::ComplainAboutRedundantArguments {
+            if let Some(key) = config.unknown_keys.first() {
+                return OptionsResult::InvalidOptions(config.error(format!("unknown key {:?}", key)));
+            }
+        }
+
+        let args = config.expand_alias(args);
+
+        let Matches { flags, frees } = match flags::ALL_ARGS.parse(args.iter().copied(), strictness) {
             Ok(m)    => m,
             Err(pe)  => return OptionsResult::InvalidOptions(OptionsError::Parse(pe)),
         };
@@ -152,9 +170,33 @@ impl Options {
             return OptionsResult::Version(version);
         }
 
+        // The default flags go in front of the user’s own ones, so they get
+        // overridden the same way an earlier argument would. Strict mode
+        // only checks the arguments that were actually typed, as otherwise
+        // every flag in the file would count as given twice.
+        let flags = if config.flags.is_empty() { flags } else {
+            if flags.is_strict() {
+                if let Err(oe) = Self::deduce(&flags, vars) {
+                    return OptionsResult::InvalidOptions(oe);
+                }
+            }
+
+            let all_args = config.flags.iter().map(OsString::as_os_str).chain(args.iter().copied());
+            match flags::ALL_ARGS.parse(all_args, Strictness::UseLastArguments) {
+                Ok(m)    => m.flags,
+                Err(pe)  => return OptionsResult::InvalidOptions(OptionsError::Parse(pe)),
+            }
+        };
+
         match Self::deduce(&flags, vars) {
-            Ok(options)  => OptionsResult::Ok(options, frees),
-            Err(oe)      => OptionsResult::InvalidOptions(oe),
+            Ok(mut options) => {
+                let colours = options.theme.definitions.exa.take();
+                options.theme.definitions.exa = config.colours(colours);
+                OptionsResult::Ok(options, frees)
+            }
+            Err(oe) => {
+                OptionsResult::InvalidOptions(oe)
+            }
         }
     }
 
@@ -162,13 +204,10 @@ impl Options {
     /// status column. It’s only worth trying to discover a repository if the
     /// results will end up being displayed.
     pub fn should_scan_for_git(&self) -> bool {
-        if self.filter.git_ignore == GitIgnore::CheckAndIgnore {
-            return true;
-        }
-
         match self.view.mode {
             Mode::Details(details::Options { table: Some(ref table), .. }) |
-            Mode::GridDetails(grid_details::Options { details: details::Options { table: Some(ref table), .. }, .. }) => table.columns.git,
+            Mode::GridDetails(grid_details::Options { details: details::Options { table: Some(ref table), .. }, .. }) => table.columns.shows_git(),
+            Mode::Json(ref json) => json.git,
             _ => false,
         }
     }
@@ -176,25 +215,54 @@ impl Options {
     /// Determines the complete set of options based on the given command-line
     /// arguments, after they’ve been parsed.
     fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
-        if cfg!(not(feature = "git")) &&
-                matches.has_where_any(|f| f.matches(&flags::GIT) || f.matches(&flags::GIT_IGNORE)).is_some() {
+        if cfg!(not(feature = "git")) && matches.has_where_any(|f| f.matches(&flags::GIT)).is_some() {
             return Err(OptionsError::Unsupported(String::from(
-                "Options --git and --git-ignore can't be used because `git` feature was disabled in this build of exa"
+                "Option --git can't be used because `git` feature was disabled in this build of exa"
             )));
         }
 
         let view = View::deduce(matches, vars)?;
-        let dir_action = DirAction::deduce(matches, matches!(view.mode, Mode::Details(_)))?;
-        let filter = FileFilter::deduce(matches)?;
+        let dir_action = DirAction::deduce(matches, matches!(view.mode, Mode::Details(_) | Mode::Json(_) | Mode::Print0))?;
+        let mut filter = FileFilter::deduce(matches)?;
         let theme = ThemeOptions::deduce(matches, vars)?;
+        let git_untracked_depth = Self::deduce_untracked_depth(matches)?;
+
+        // NUL-separated output never shows sizes, so there’s no point in
+        // adding any up.
+        if view.mode == Mode::Print0 {
+            filter.total_size = None;
+        }
+
+        let options = Self { dir_action, filter, view, theme, git_untracked_depth };
+
+        // The Git column can be asked for with `--columns` as well as with
+        // `--git`, so it’s the view that gets checked.
+        if options.git_untracked_depth.is_some() && matches.is_strict() && ! options.should_scan_for_git() {
+            return Err(OptionsError::Useless(&flags::GIT_UNTRACKED_DEPTH, false, &flags::GIT));
+        }
 
-        Ok(Self { dir_action, filter, view, theme })
+        Ok(options)
+    }
+
+    /// Determines the limit on looking inside untracked directories, which
+    /// does nothing without the Git column.
+    fn deduce_untracked_depth(matches: &MatchedFlags<'_>) -> Result<Option<usize>, OptionsError> {
+        let depth = match matches.get(&flags::GIT_UNTRACKED_DEPTH)? {
+            Some(d)  => d.to_string_lossy(),
+            None     => return Ok(None),
+        };
+
+        match depth.parse() {
+            Ok(d)   => Ok(Some(d)),
+            Err(e)  => Err(OptionsError::FailedParse(depth.to_string(), NumberSource::Arg(&flags::GIT_UNTRACKED_DEPTH), e)),
+        }
     }
 }
 
 
 /// The result of the `Options::getopts` function.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum OptionsResult<'args> {
 
     /// The options were parsed successfully.
@@ -251,3 +319,60 @@ pub mod test {
         result
     }
 }
+
+
+#[cfg(test)]
+#[cfg(feature = "git")]
+mod test_untracked_depth {
+    use super::*;
+    use crate::options::test::{parse_for_test, Strictnesses};
+    use crate::options::test::Strictnesses::*;
+
+    fn depth(inputs: &[&str], strictnesses: Strictnesses) -> Vec<Result<Option<usize>, OptionsError>> {
+        parse_for_test(inputs, flags::ALL_ARGS.0, strictnesses, |mf| {
+            Options::deduce(mf, &None).map(|o| o.git_untracked_depth)
+        })
+    }
+
+    #[test]
+    fn unlimited() {
+        for result in depth(&[ "--long", "--git" ], Both) {
+            assert_eq!(result, Ok(None));
+        }
+    }
+
+    #[test]
+    fn with_git() {
+        for result in depth(&[ "--long", "--git", "--git-untracked-depth=2" ], Both) {
+            assert_eq!(result, Ok(Some(2)));
+        }
+    }
+
+    #[test]
+    fn with_the_git_column() {
+        for result in depth(&[ "--long", "--columns=name,git", "--git-untracked-depth=1" ], Both) {
+            assert_eq!(result, Ok(Some(1)));
+        }
+    }
+
+    #[test]
+    fn without_git() {
+        for result in depth(&[ "--long", "--git-untracked-depth=1" ], Last) {
+            assert_eq!(result, Ok(Some(1)));
+        }
+    }
+
+    #[test]
+    fn without_git_2() {
+        for result in depth(&[ "--long", "--git-untracked-depth=1" ], Complain) {
+            assert_eq!(result.unwrap_err(), OptionsError::Useless(&flags::GIT_UNTRACKED_DEPTH, false, &flags::GIT));
+        }
+    }
+
+    #[test]
+    fn not_a_number() {
+        for result in depth(&[ "--long", "--git", "--git-untracked-depth=deep" ], Both) {
+            assert!(matches!(result, Err(OptionsError::FailedParse(..))));
+        }
+    }
+}
@@ -102,7 +102,9 @@ pub enum TakesValue {
     /// This flag will throw an error if there’s a value after it.
     Forbidden,
 
-    /// This flag may be followed by a value to override its defaults
+    /// This flag may be given a value to override its defaults. Long flags
+    /// only take one after an equals sign, so the next argument doesn’t get
+    /// mistaken for it.
     Optional(Option<Values>),
 }
 
@@ -205,12 +207,7 @@ impl Args {
                             }
                         }
                         TakesValue::Optional(_) => {
-                            if let Some(next_arg) = inputs.next() {
-                                result_flags.push((flag, Some(next_arg)));
-                            }
-                            else {
-                                result_flags.push((flag, None));
-                            }
+                            result_flags.push((flag, None));
                         }
                     }
                 }
@@ -618,7 +615,8 @@ mod parse_test {
         &Arg { short: Some(b'l'), long: "long",     takes_value: TakesValue::Forbidden },
         &Arg { short: Some(b'v'), long: "verbose",  takes_value: TakesValue::Forbidden },
         &Arg { short: Some(b'c'), long: "count",    takes_value: TakesValue::Necessary(None) },
-        &Arg { short: Some(b't'), long: "type",     takes_value: TakesValue::Necessary(Some(SUGGESTIONS)) }
+        &Arg { short: Some(b't'), long: "type",     takes_value: TakesValue::Necessary(Some(SUGGESTIONS)) },
+        &Arg { short: None,       long: "scale",    takes_value: TakesValue::Optional(None) },
     ];
 
 
@@ -650,6 +648,11 @@ mod parse_test {
     test!(arg_equals_s:  ["--type=exa"]     => frees: [],  flags: [ (Flag::Long("type"), Some(OsStr::new("exa"))) ]);
     test!(arg_then_s:    ["--type", "exa"]  => frees: [],  flags: [ (Flag::Long("type"), Some(OsStr::new("exa"))) ]);
 
+    // Long args with optional values
+    test!(opt_none:      ["--scale"]          => frees: [],         flags: [ (Flag::Long("scale"), None) ]);
+    test!(opt_equals:    ["--scale=age"]      => frees: [],         flags: [ (Flag::Long("scale"), Some(OsStr::new("age"))) ]);
+    test!(opt_then:      ["--scale", "age"]   => frees: [ "age" ],  flags: [ (Flag::Long("scale"), None) ]);
+
 
     // Short args
     test!(short:       ["-l"]            => frees: [],       flags: [ (Flag::Short(b'l'), None) ]);
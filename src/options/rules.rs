@@ -0,0 +1,219 @@
+//! Reading the file type rules file.
+//!
+//! The rules file lets the user add their own classes of file, each with a
+//! style and an icon, without having to recompile exa. It’s written in the
+//! same subset of TOML as the configuration file, with one `[[rules]]`
+//! table per rule:
+//!
+//! ```toml
+//! [[rules]]
+//! class = "terraform"
+//! extension = ["tf", "tfvars"]
+//! style = "38;5;99"
+//! icon = "\ue69a"
+//!
+//! [[rules]]
+//! class = "proto"
+//! name-regex = '^.*\.proto$'
+//! dir = "proto protos"
+//! ```
+//!
+//! A rule matches a file if any of its patterns do. The `name`, `extension`,
+//! `dir` and `magic` keys take globs, and the `-regex` versions of them take
+//! regular expressions; `dir` patterns only apply to directories, and the
+//! others only apply to everything else. `magic` patterns get matched against
+//! the kind of file that `--magic` finds, such as `elf` or `python`. Rules are tried in the order they’re
+//! written, before the built-in ones, and the first matching rule that has
+//! a style or an icon gets to decide it.
+
+
+use std::path::Path;
+
+use crate::info::filetype::{Matcher, Rule, Target};
+use crate::options::OptionsError;
+use crate::options::config::{parse_pair, strip_comment, Value};
+use crate::theme::Pair;
+
+
+/// Parses the contents of a rules file.
+pub fn read(path: &Path, contents: &str) -> Result<Vec<Rule>, OptionsError> {
+    let error = |line: usize, message: String| {
+        OptionsError::BadConfig(path.into(), format!("line {}: {}", line, message))
+    };
+
+    let mut rules = Vec::new();
+    let mut current: Option<(usize, Rule)> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line == "[[rules]]" {
+            if let Some((start, rule)) = current.take() {
+                rules.push(check_rule(rule).map_err(|e| error(start, e))?);
+            }
+
+            current = Some((index + 1, Rule::new("")));
+            continue;
+        }
+        else if line.starts_with('[') {
+            return Err(error(index + 1, format!("unknown table {:?}", line)));
+        }
+
+        let (key, value) = parse_pair(line).map_err(|e| error(index + 1, e))?;
+        let rule = match current {
+            Some((_, ref mut rule))  => rule,
+            None                     => return Err(error(index + 1, format!("{:?} isn’t inside a [[rules]] table", key))),
+        };
+
+        match (key.as_str(), value) {
+            ("class", Value::String(class)) => {
+                rule.class = class;
+            }
+            ("style", Value::String(style)) => {
+                rule.style = Some(Pair { key: "", value: &style }.to_style());
+            }
+            ("icon", Value::String(icon)) if icon.chars().count() == 1 => {
+                rule.icon = icon.chars().next();
+            }
+            (key, value) => {
+                let matchers = parse_matchers(key, value).map_err(|e| error(index + 1, e))?;
+                rule.matchers.extend(matchers);
+            }
+        }
+    }
+
+    if let Some((start, rule)) = current {
+        rules.push(check_rule(rule).map_err(|e| error(start, e))?);
+    }
+
+    Ok(rules)
+}
+
+/// Parses the patterns for one of the matching keys, checking that they
+/// compile now, rather than when they’re first used.
+fn parse_matchers(key: &str, value: Value) -> Result<Vec<Matcher>, String> {
+    let (target, is_regex) = match key {
+        "name"             => (Target::Name,      false),
+        "extension"        => (Target::Extension, false),
+        "dir"              => (Target::Directory, false),
+        "magic"            => (Target::Magic,     false),
+        "name-regex"       => (Target::Name,      true),
+        "extension-regex"  => (Target::Extension, true),
+        "dir-regex"        => (Target::Directory, true),
+        "magic-regex"      => (Target::Magic,     true),
+        "class" | "style" | "icon" => return Err(format!("invalid value for {:?}", key)),
+        _                  => return Err(format!("unknown key {:?}", key)),
+    };
+
+    // Regexes can contain spaces, so a string is only ever one regex
+    let patterns = match value {
+        Value::String(regex) if is_regex  => vec![ regex ],
+        value                             => value.into_words().ok_or_else(|| format!("invalid value for {:?}", key))?,
+    };
+
+    patterns.into_iter().map(|pattern| {
+        if is_regex {
+            match regex::Regex::new(&pattern) {
+                Ok(_)   => Ok(Matcher::Regex(target, pattern)),
+                Err(e)  => Err(format!("invalid regex {:?}: {}", pattern, e)),
+            }
+        }
+        else {
+            match glob::Pattern::new(&pattern) {
+                Ok(_)   => Ok(Matcher::Glob(target, pattern)),
+                Err(e)  => Err(format!("invalid glob {:?}: {}", pattern, e)),
+            }
+        }
+    }).collect()
+}
+
+/// Checks that a rule has everything it needs once its table has ended.
+fn check_rule(rule: Rule) -> Result<Rule, String> {
+    if rule.class.is_empty() {
+        Err(String::from("rule has no class"))
+    }
+    else if rule.matchers.is_empty() {
+        Err(format!("rule {:?} has nothing to match", rule.class))
+    }
+    else {
+        Ok(rule)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use ansi_term::Colour::Fixed;
+
+    fn read(contents: &str) -> Result<Vec<Rule>, OptionsError> {
+        super::read(Path::new("rules.toml"), contents)
+    }
+
+    fn error(message: &str) -> Result<Vec<Rule>, OptionsError> {
+        Err(OptionsError::BadConfig(PathBuf::from("rules.toml"), message.into()))
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(read(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn one_rule() {
+        let rules = read("[[rules]]\nclass = \"terraform\"\nextension = [\"tf\", \"tfvars\"]\nstyle = \"38;5;99\"\nicon = \"\\ue69a\"\n").unwrap();
+        assert_eq!(rules, vec![ Rule {
+            class:    String::from("terraform"),
+            matchers: vec![ Matcher::Glob(Target::Extension, "tf".into()), Matcher::Glob(Target::Extension, "tfvars".into()) ],
+            style:    Some(Fixed(99).normal()),
+            icon:     Some('\u{e69a}'),
+        } ]);
+    }
+
+    #[test]
+    fn regex_with_spaces() {
+        let rules = read("[[rules]]\nclass = \"notes\"\nname-regex = '^my notes'\n").unwrap();
+        assert_eq!(rules[0].matchers, vec![ Matcher::Regex(Target::Name, "^my notes".into()) ]);
+    }
+
+    #[test]
+    fn magic() {
+        let rules = read("[[rules]]\nclass = \"tools\"\nmagic = \"python perl\"\n").unwrap();
+        assert_eq!(rules[0].matchers, vec![ Matcher::Glob(Target::Magic, "python".into()), Matcher::Glob(Target::Magic, "perl".into()) ]);
+    }
+
+    #[test]
+    fn in_order() {
+        let rules = read("[[rules]]\nclass = \"a\"\ndir = \"a\"\n[[rules]]\nclass = \"b\"\ndir = \"b\"\n").unwrap();
+        assert_eq!(rules.iter().map(|r| r.class.as_str()).collect::<Vec<_>>(), vec![ "a", "b" ]);
+    }
+
+    #[test]
+    fn no_class() {
+        assert_eq!(read("\n[[rules]]\nname = \"*.tf\"\n"), error("line 2: rule has no class"));
+    }
+
+    #[test]
+    fn nothing_to_match() {
+        assert_eq!(read("[[rules]]\nclass = \"tf\"\n"), error("line 1: rule \"tf\" has nothing to match"));
+    }
+
+    #[test]
+    fn bad_regex() {
+        assert!(read("[[rules]]\nclass = \"tf\"\nname-regex = \"(\"\n").is_err());
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert_eq!(read("[[rules]]\nclass = \"tf\"\nsuffix = \"tf\"\n"), error("line 3: unknown key \"suffix\""));
+    }
+
+    #[test]
+    fn outside_table() {
+        assert_eq!(read("class = \"tf\"\n"), error("line 1: \"class\" isn’t inside a [[rules]] table"));
+    }
+}
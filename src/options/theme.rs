@@ -1,21 +1,24 @@
-use crate::options::{flags, vars, Vars, OptionsError};
-use crate::options::parser::MatchedFlags;
-use crate::theme::{Options, UseColours, ColourScale, Definitions};
+use std::io;
+use std::path::PathBuf;
+
+use crate::options::{flags, theme_file, vars, Config, Vars, OptionsError};
+use crate::options::parser::{Flag, MatchedFlags};
+use crate::theme::{Options, UseColours, ColourScale, ColourScaleMode, Definitions, BasePalette, Palette};
 
 
 impl Options {
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let use_colours = UseColours::deduce(matches, vars)?;
-        let colour_scale = ColourScale::deduce(matches)?;
+        let colour_scale = ColourScale::deduce(matches, vars)?;
 
-        let definitions = if use_colours == UseColours::Never {
-                Definitions::default()
+        let (definitions, palette) = if use_colours == UseColours::Never {
+                (Definitions::default(), Palette::default())
             }
             else {
-                Definitions::deduce(vars)
+                (Definitions::deduce(vars), Palette::deduce(matches, vars)?)
             };
 
-        Ok(Self { use_colours, colour_scale, definitions })
+        Ok(Self { use_colours, colour_scale, definitions, palette })
     }
 }
 
@@ -49,12 +52,100 @@ impl UseColours {
 
 
 impl ColourScale {
+
+    /// Determines which columns to colour on a scale. The flag can be given
+    /// a comma-separated list of targets; on its own, it just picks sizes,
+    /// as it did before it took any.
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        let is_scale = |f: &Flag| f.matches(&flags::COLOR_SCALE) || f.matches(&flags::COLOUR_SCALE);
+
+        let mut scale = Self {
+            mode: ColourScaleMode::deduce(matches)?,
+            true_colour: vars.get(vars::COLORTERM).map_or(false, |v| v == "truecolor" || v == "24bit"),
+            .. Self::default()
+        };
+
+        if let Some(word) = matches.get_where(is_scale)? {
+            for target in word.to_string_lossy().split(',') {
+                match target {
+                    "all"   => { scale.size = true; scale.age = true; }
+                    "size"  => scale.size = true,
+                    "age"   => scale.age = true,
+                    _       => return Err(OptionsError::BadArgument(&flags::COLOR_SCALE, word.into())),
+                }
+            }
+        }
+        else if matches.has_where(is_scale)?.is_some() {
+            scale.size = true;
+        }
+
+        if matches.is_strict() && scale.mode != ColourScaleMode::default() && ! scale.size {
+            return Err(OptionsError::Useless(&flags::COLOR_SCALE_MODE, false, &flags::COLOR_SCALE));
+        }
+
+        Ok(scale)
+    }
+}
+
+
+impl ColourScaleMode {
     fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
-        if matches.has_where(|f| f.matches(&flags::COLOR_SCALE) || f.matches(&flags::COLOUR_SCALE))?.is_some() {
+        let word = match matches.get_where(|f| f.matches(&flags::COLOR_SCALE_MODE) || f.matches(&flags::COLOUR_SCALE_MODE))? {
+            Some(w)  => w,
+            None     => return Ok(Self::default()),
+        };
+
+        if word == "fixed" {
+            Ok(Self::Fixed)
+        }
+        else if word == "gradient" {
             Ok(Self::Gradient)
         }
         else {
-            Ok(Self::Fixed)
+            Err(OptionsError::BadArgument(&flags::COLOR_SCALE_MODE, word.into()))
+        }
+    }
+}
+
+
+impl Palette {
+
+    /// Picks the palette named by `--theme`. This can be one of the
+    /// built-in ones, the name of a file in the `themes` directory next to
+    /// the configuration file, or the path to a theme file.
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        let word = match matches.get(&flags::THEME)? {
+            Some(w)  => w,
+            None     => return Ok(Self::default()),
+        };
+
+        if let Some(base) = word.to_str().and_then(BasePalette::from_name) {
+            return Ok(Self { base, .. Self::default() });
+        }
+
+        let path = PathBuf::from(word);
+        let by_name = path.components().count() == 1 && path.extension().is_none();
+        let path = if by_name {
+            let dir = Config::path(vars).and_then(|p| p.parent().map(|d| d.join("themes")));
+            match dir {
+                Some(dir)  => dir.join(path).with_extension("toml"),
+                None       => return Err(OptionsError::BadArgument(&flags::THEME, word.into())),
+            }
+        }
+        else {
+            path
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                theme_file::read(&path, &contents)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && by_name => {
+                Err(OptionsError::BadArgument(&flags::THEME, word.into()))
+            }
+            Err(e) => {
+                Err(OptionsError::BadConfig(path, e.to_string()))
+            }
         }
     }
 }
@@ -74,24 +165,17 @@ mod terminal_test {
     use super::*;
     use std::ffi::OsString;
     use crate::options::flags;
-    use crate::options::parser::{Flag, Arg};
+    use crate::options::parser::Arg;
 
     use crate::options::test::parse_for_test;
     use crate::options::test::Strictnesses::*;
 
     static TEST_ARGS: &[&Arg] = &[ &flags::COLOR,       &flags::COLOUR,
-                                   &flags::COLOR_SCALE, &flags::COLOUR_SCALE, ];
+                                   &flags::COLOR_SCALE, &flags::COLOUR_SCALE,
+                                   &flags::COLOR_SCALE_MODE, &flags::COLOUR_SCALE_MODE,
+                                   &flags::THEME, ];
 
     macro_rules! test {
-        ($name:ident:  $type:ident <- $inputs:expr;  $stricts:expr => $result:expr) => {
-            #[test]
-            fn $name() {
-                for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf)) {
-                    assert_eq!(result, $result);
-                }
-            }
-        };
-
         ($name:ident:  $type:ident <- $inputs:expr, $env:expr;  $stricts:expr => $result:expr) => {
             #[test]
             fn $name() {
@@ -102,15 +186,6 @@ mod terminal_test {
             }
         };
 
-        ($name:ident:  $type:ident <- $inputs:expr;  $stricts:expr => err $result:expr) => {
-            #[test]
-            fn $name() {
-                for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf)) {
-                    assert_eq!(result.unwrap_err(), $result);
-                }
-            }
-        };
-
         ($name:ident:  $type:ident <- $inputs:expr, $env:expr;  $stricts:expr => err $result:expr) => {
             #[test]
             fn $name() {
@@ -126,6 +201,7 @@ mod terminal_test {
         ls: &'static str,
         exa: &'static str,
         no_color: &'static str,
+        colorterm: &'static str,
     }
 
     impl MockVars {
@@ -134,6 +210,7 @@ mod terminal_test {
                 ls: "",
                 exa: "",
                 no_color: "",
+                colorterm: "",
             }
         }
         fn with_no_color() -> MockVars {
@@ -141,6 +218,15 @@ mod terminal_test {
                 ls: "",
                 exa: "",
                 no_color: "true",
+                colorterm: "",
+            }
+        }
+        fn with_colorterm(colorterm: &'static str) -> MockVars {
+            MockVars {
+                ls: "",
+                exa: "",
+                no_color: "",
+                colorterm,
             }
         }
     }
@@ -157,6 +243,9 @@ mod terminal_test {
             else if name == vars::NO_COLOR && ! self.no_color.is_empty() {
                 Some(OsString::from(self.no_color.clone()))
             }
+            else if name == vars::COLORTERM && ! self.colorterm.is_empty() {
+                Some(OsString::from(self.colorterm))
+            }
             else {
                 None
             }
@@ -194,13 +283,44 @@ mod terminal_test {
     test!(overridden_7:  UseColours <- ["--colour=auto", "--color=never"], MockVars::empty();   Complain => err OptionsError::Duplicate(Flag::Long("colour"), Flag::Long("color")));
     test!(overridden_8:  UseColours <- ["--color=auto",  "--color=never"], MockVars::empty();   Complain => err OptionsError::Duplicate(Flag::Long("color"),  Flag::Long("color")));
 
-    test!(scale_1:  ColourScale <- ["--color-scale", "--colour-scale"];   Last => Ok(ColourScale::Gradient));
-    test!(scale_2:  ColourScale <- ["--color-scale",                 ];   Last => Ok(ColourScale::Gradient));
-    test!(scale_3:  ColourScale <- [                 "--colour-scale"];   Last => Ok(ColourScale::Gradient));
-    test!(scale_4:  ColourScale <- [                                 ];   Last => Ok(ColourScale::Fixed));
-
-    test!(scale_5:  ColourScale <- ["--color-scale", "--colour-scale"];   Complain => err OptionsError::Duplicate(Flag::Long("color-scale"),  Flag::Long("colour-scale")));
-    test!(scale_6:  ColourScale <- ["--color-scale",                 ];   Complain => Ok(ColourScale::Gradient));
-    test!(scale_7:  ColourScale <- [                 "--colour-scale"];   Complain => Ok(ColourScale::Gradient));
-    test!(scale_8:  ColourScale <- [                                 ];   Complain => Ok(ColourScale::Fixed));
+    const SIZE: ColourScale = ColourScale { size: true,  age: false, mode: ColourScaleMode::Fixed, true_colour: false };
+    const AGE:  ColourScale = ColourScale { size: false, age: true,  mode: ColourScaleMode::Fixed, true_colour: false };
+    const ALL:  ColourScale = ColourScale { size: true,  age: true,  mode: ColourScaleMode::Fixed, true_colour: false };
+    const NONE: ColourScale = ColourScale { size: false, age: false, mode: ColourScaleMode::Fixed, true_colour: false };
+
+    test!(scale_1:  ColourScale <- ["--color-scale", "--colour-scale"], MockVars::empty();   Last => Ok(SIZE));
+    test!(scale_2:  ColourScale <- ["--color-scale",                 ], MockVars::empty();   Last => Ok(SIZE));
+    test!(scale_3:  ColourScale <- [                 "--colour-scale"], MockVars::empty();   Last => Ok(SIZE));
+    test!(scale_4:  ColourScale <- [                                 ], MockVars::empty();   Last => Ok(NONE));
+
+    test!(scale_5:  ColourScale <- ["--color-scale", "--colour-scale"], MockVars::empty();   Complain => err OptionsError::Duplicate(Flag::Long("color-scale"),  Flag::Long("colour-scale")));
+    test!(scale_6:  ColourScale <- ["--color-scale",                 ], MockVars::empty();   Complain => Ok(SIZE));
+    test!(scale_7:  ColourScale <- [                 "--colour-scale"], MockVars::empty();   Complain => Ok(SIZE));
+    test!(scale_8:  ColourScale <- [                                 ], MockVars::empty();   Complain => Ok(NONE));
+
+    // Targets
+    test!(scale_size:  ColourScale <- ["--color-scale=size"], MockVars::empty();       Both => Ok(SIZE));
+    test!(scale_age:   ColourScale <- ["--colour-scale=age"], MockVars::empty();       Both => Ok(AGE));
+    test!(scale_all:   ColourScale <- ["--color-scale=all"], MockVars::empty();        Both => Ok(ALL));
+    test!(scale_list:  ColourScale <- ["--color-scale=age,size"], MockVars::empty();   Both => Ok(ALL));
+    test!(scale_bad:   ColourScale <- ["--color-scale=date"], MockVars::empty();       Both => err OptionsError::BadArgument(&flags::COLOR_SCALE, OsString::from("date")));
+
+    // Modes
+    test!(mode_fixed:     ColourScale <- ["--color-scale", "--color-scale-mode=fixed"], MockVars::empty();        Both => Ok(SIZE));
+    test!(mode_gradient:  ColourScale <- ["--color-scale", "--colour-scale-mode=gradient"], MockVars::empty();    Both => Ok(ColourScale { mode: ColourScaleMode::Gradient, .. SIZE }));
+    test!(mode_bad:       ColourScale <- ["--color-scale", "--color-scale-mode=log"], MockVars::empty();          Both => err OptionsError::BadArgument(&flags::COLOR_SCALE_MODE, OsString::from("log")));
+    test!(mode_alone:     ColourScale <- ["--color-scale-mode=gradient"], MockVars::empty();                      Last => Ok(ColourScale { mode: ColourScaleMode::Gradient, .. NONE }));
+    test!(mode_useless:   ColourScale <- ["--color-scale-mode=gradient"], MockVars::empty();                      Complain => err OptionsError::Useless(&flags::COLOR_SCALE_MODE, false, &flags::COLOR_SCALE));
+
+    // 24-bit colour
+    test!(true_colour:    ColourScale <- ["--color-scale"], MockVars::with_colorterm("truecolor");  Both => Ok(ColourScale { true_colour: true, .. SIZE }));
+    test!(bit_colour:     ColourScale <- ["--color-scale"], MockVars::with_colorterm("24bit");      Both => Ok(ColourScale { true_colour: true, .. SIZE }));
+    test!(not_colour:     ColourScale <- ["--color-scale"], MockVars::with_colorterm("yes");        Both => Ok(SIZE));
+
+    // --theme
+    test!(theme_none:     Palette <- [], MockVars::empty();                    Both => Ok(Palette::default()));
+    test!(theme_exa:      Palette <- ["--theme=exa"], MockVars::empty();       Both => Ok(Palette { base: BasePalette::Exa, .. Palette::default() }));
+    test!(theme_vscode:   Palette <- ["--theme", "vscode"], MockVars::empty(); Both => Ok(Palette::default()));
+    test!(theme_missing:  Palette <- ["--theme=nope"], MockVars::empty();      Both => err OptionsError::BadArgument(&flags::THEME, OsString::from("nope")));
+    test!(theme_twice:    Palette <- ["--theme=exa", "--theme=vscode"], MockVars::empty();  Last => Ok(Palette::default()));
 }
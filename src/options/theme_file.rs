@@ -0,0 +1,154 @@
+//! Reading theme files, which get picked with `--theme`.
+//!
+//! A theme file starts from one of the built-in palettes, then overrides
+//! the styles it wants to change. It’s written in the same subset of TOML
+//! as the configuration file:
+//!
+//! ```toml
+//! base = "exa"
+//!
+//! [filekinds]
+//! directory = "bold #005fd7"
+//!
+//! [ui]
+//! punctuation = "#808080"
+//!
+//! [files]
+//! image = "#00afaf"
+//! language = "none"
+//!
+//! [dirs]
+//! folder = "bold #808080"
+//! ```
+//!
+//! The `filekinds`, `perms`, `size`, `users`, `links` and `git` tables use
+//! the names of the fields in `UiStyles`, with `ui` holding the ones that
+//! aren’t in a group. The `files` and `dirs` tables give styles to classes
+//! of files and directories, using the class names from the file type
+//! rules. Styles are written as described in `theme::parse_style`.
+
+
+use std::path::Path;
+
+use crate::options::OptionsError;
+use crate::options::config::{parse_pair, strip_comment, Value};
+use crate::theme::{BasePalette, Palette, UiStyles, parse_style};
+
+
+/// Parses the contents of a theme file.
+pub fn read(path: &Path, contents: &str) -> Result<Palette, OptionsError> {
+    let error = |line: usize, message: String| {
+        OptionsError::BadConfig(path.into(), format!("line {}: {}", line, message))
+    };
+
+    let mut palette = Palette::default();
+    let mut section = String::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+            if ! is_section(&section) {
+                return Err(error(index + 1, format!("unknown table {:?}", section)));
+            }
+
+            continue;
+        }
+
+        let (key, value) = parse_pair(line).map_err(|e| error(index + 1, e))?;
+        let value = match value {
+            Value::String(s)  => s,
+            _                 => return Err(error(index + 1, format!("invalid value for {:?}", key))),
+        };
+
+        match section.as_str() {
+            "" if key == "base" => {
+                palette.base = BasePalette::from_name(&value)
+                    .ok_or_else(|| error(index + 1, format!("unknown theme {:?}", value)))?;
+            }
+            "" => {
+                return Err(error(index + 1, format!("unknown key {:?}", key)));
+            }
+            "files" | "dirs" => {
+                let style = parse_style(&value).map_err(|e| error(index + 1, e))?;
+                let classes = if section == "files" { &mut palette.files } else { &mut palette.dirs };
+                classes.push((key, style));
+            }
+            _ => {
+                let style = parse_style(&value).map_err(|e| error(index + 1, e))?.unwrap_or_default();
+                if ! UiStyles::default().set_field(&section, &key, style) {
+                    return Err(error(index + 1, format!("unknown key {:?} in [{}]", key, section)));
+                }
+
+                palette.ui.push((section.clone(), key, style));
+            }
+        }
+    }
+
+    Ok(palette)
+}
+
+fn is_section(name: &str) -> bool {
+    matches!(name, "filekinds" | "perms" | "size" | "users" | "links" | "git" | "ages" | "tree" | "ui" | "files" | "dirs")
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use ansi_term::Colour::*;
+
+    fn read(contents: &str) -> Result<Palette, OptionsError> {
+        super::read(Path::new("light.toml"), contents)
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(read(""), Ok(Palette::default()));
+    }
+
+    #[test]
+    fn base() {
+        assert_eq!(read("base = \"exa\"").unwrap().base, BasePalette::Exa);
+    }
+
+    #[test]
+    fn ui_field() {
+        let palette = read("[filekinds]\ndirectory = \"bold #005fd7\"\n").unwrap();
+        assert_eq!(palette.ui, vec![ ("filekinds".into(), "directory".into(), RGB(0, 0x5f, 0xd7).bold()) ]);
+    }
+
+    #[test]
+    fn tree_depth() {
+        let palette = read("[tree]\ndepth_2 = \"cyan\"\n").unwrap();
+        assert_eq!(palette.ui, vec![ ("tree".into(), "depth_2".into(), Cyan.normal()) ]);
+    }
+
+    #[test]
+    fn classes() {
+        let palette = read("[files]\nimage = \"#00afaf\"\nlanguage = \"none\"\n[dirs]\nfolder = \"244\"\n").unwrap();
+        assert_eq!(palette.files, vec![ ("image".into(), Some(RGB(0, 0xaf, 0xaf).normal())), ("language".into(), None) ]);
+        assert_eq!(palette.dirs, vec![ ("folder".into(), Some(Fixed(244).normal())) ]);
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(read("[git]\nstaged = \"red\"\n"),
+                   Err(OptionsError::BadConfig(PathBuf::from("light.toml"), "line 2: unknown key \"staged\" in [git]".into())));
+    }
+
+    #[test]
+    fn bad_style() {
+        assert!(read("[ui]\ndate = \"#12345\"\n").is_err());
+    }
+
+    #[test]
+    fn unknown_base() {
+        assert!(read("base = \"solarized\"\n").is_err());
+    }
+}
@@ -15,10 +15,25 @@ pub static COLUMNS: &str = "COLUMNS";
 /// Environment variable used to datetime format.
 pub static TIME_STYLE: &str = "TIME_STYLE";
 
+/// Environment variable used to pick how to quote file names.
+pub static QUOTING_STYLE: &str = "QUOTING_STYLE";
+
 /// Environment variable used to disable colors.
 /// See: <https://no-color.org/>
 pub static NO_COLOR: &str = "NO_COLOR";
 
+/// Environment variable used to tell whether the terminal supports 24-bit
+/// colours, when it’s set to `truecolor` or `24bit`.
+pub static COLORTERM: &str = "COLORTERM";
+
+/// Environment variable used to find the user’s configuration directory.
+/// See: <https://specifications.freedesktop.org/basedir-spec/latest/>
+pub static XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
+
+/// Environment variable used to find the configuration directory when
+/// `XDG_CONFIG_HOME` isn’t set.
+pub static HOME: &str = "HOME";
+
 // exa-specific variables
 
 /// Environment variable used to colour exa’s interface when colours are
@@ -48,6 +63,15 @@ pub static EXA_GRID_ROWS: &str = "EXA_GRID_ROWS";
 /// far apart, so this may be necessary depending on how they are shown.
 pub static EXA_ICON_SPACING: &str = "EXA_ICON_SPACING";
 
+/// Environment variable used to read the configuration file from somewhere
+/// other than `$XDG_CONFIG_HOME/exa/config.toml`. Setting it to the empty
+/// string stops exa from reading a configuration file at all.
+pub static EXA_CONFIG: &str = "EXA_CONFIG";
+
+/// Environment variable used to pick which columns the long view shows and
+/// in which order, the same way as the `--columns` option.
+pub static EXA_COLUMNS: &str = "EXA_COLUMNS";
+
 
 /// Mockable wrapper for `std::env::var_os`.
 pub trait Vars {
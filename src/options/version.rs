@@ -38,20 +38,22 @@ impl fmt::Display for VersionString {
 
 #[cfg(test)]
 mod test {
-    use crate::options::{Options, OptionsResult};
+    use crate::options::{Config, Options, OptionsResult};
     use std::ffi::OsStr;
 
     #[test]
     fn version() {
         let args = vec![ OsStr::new("--version") ];
-        let opts = Options::parse(args, &None);
+        let config = Config::default();
+        let opts = Options::parse(args, &config, &None);
         assert!(matches!(opts, OptionsResult::Version(_)));
     }
 
     #[test]
     fn version_with_file() {
         let args = vec![ OsStr::new("--version"), OsStr::new("me") ];
-        let opts = Options::parse(args, &None);
+        let config = Config::default();
+        let opts = Options::parse(args, &config, &None);
         assert!(matches!(opts, OptionsResult::Version(_)));
     }
 }
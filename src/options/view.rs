@@ -1,10 +1,11 @@
 use crate::fs::feature::xattr;
 use crate::options::{flags, OptionsError, NumberSource, Vars};
 use crate::options::parser::MatchedFlags;
-use crate::output::{View, Mode, TerminalWidth, grid, details};
+use crate::options::file_name::deduce_max_width;
+use crate::output::{View, Mode, TerminalWidth, TreeStyle, grid, details, json};
 use crate::output::grid_details::{self, RowThreshold};
 use crate::output::file_name::Options as FileStyle;
-use crate::output::table::{TimeTypes, SizeFormat, UserFormat, Columns, Options as TableOptions};
+use crate::output::table::{TimeTypes, SizeFormat, UserFormat, Columns, Column, ColumnName, Alignment, Options as TableOptions};
 use crate::output::time::TimeFormat;
 
 
@@ -13,7 +14,8 @@ impl View {
         let mode = Mode::deduce(matches, vars)?;
         let width = TerminalWidth::deduce(vars)?;
         let file_style = FileStyle::deduce(matches, vars)?;
-        Ok(Self { mode, width, file_style })
+        let summary = matches.has(&flags::SUMMARY)?;
+        Ok(Self { mode, width, file_style, summary })
     }
 }
 
@@ -29,6 +31,22 @@ impl Mode {
     /// This is complicated a little by the fact that `--grid` and `--tree`
     /// can also combine with `--long`, so care has to be taken to use the
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+
+        // JSON output has no layout of its own, so it takes priority over
+        // the other view flags: `--tree` and `--recurse` only decide how
+        // deeply its objects get nested.
+        if matches.has(&flags::JSON)? {
+            let json = json::Options::deduce(matches)?;
+            return Ok(Self::Json(json));
+        }
+
+        // The same goes for NUL-separated output, which lists every file
+        // under a directory when recursing, whether as a tree or not.
+        if matches.has(&flags::PRINT0)? {
+            Self::strict_check_print0(matches)?;
+            return Ok(Self::Print0);
+        }
+
         let flag = matches.has_where_any(|f| f.matches(&flags::LONG) || f.matches(&flags::ONE_LINE)
                                           || f.matches(&flags::GRID) || f.matches(&flags::TREE));
 
@@ -76,6 +94,24 @@ impl Mode {
         Ok(Self::Grid(grid))
     }
 
+    fn strict_check_print0(matches: &MatchedFlags<'_>) -> Result<(), OptionsError> {
+        // NUL-separated output has no layout for the other view flags to
+        // change, and never shows sizes, so it doesn’t add any up.
+        if matches.is_strict() {
+            for option in &[ &flags::LONG, &flags::GRID, &flags::ONE_LINE, &flags::ACROSS ] {
+                if matches.has(option)? {
+                    return Err(OptionsError::Conflict(*option, &flags::PRINT0));
+                }
+            }
+
+            if matches.has(&flags::TOTAL_SIZE)? {
+                return Err(OptionsError::Useless(&flags::TOTAL_SIZE, true, &flags::PRINT0));
+            }
+        }
+
+        Ok(())
+    }
+
     fn strict_check_long_flags(matches: &MatchedFlags<'_>) -> Result<(), OptionsError> {
         // If --long hasn’t been passed, then check if we need to warn the
         // user about flags that won’t have any effect.
@@ -90,6 +126,9 @@ impl Mode {
             if matches.has(&flags::GIT)? {
                 return Err(OptionsError::Useless(&flags::GIT, false, &flags::LONG));
             }
+            else if matches.has(&flags::MAGIC)? && ! matches.has(&flags::TREE)? {
+                return Err(OptionsError::Useless(&flags::MAGIC, false, &flags::LONG));
+            }
             else if matches.has(&flags::LEVEL)? && ! matches.has(&flags::RECURSE)? && ! matches.has(&flags::TREE)? {
                 return Err(OptionsError::Useless2(&flags::LEVEL, &flags::RECURSE, &flags::TREE));
             }
@@ -111,12 +150,24 @@ impl grid::Options {
 }
 
 
+impl json::Options {
+    fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+        let git = matches.has(&flags::GIT)?;
+        Ok(Self { git })
+    }
+}
+
+
 impl details::Options {
     fn deduce_tree(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
         let details = details::Options {
             table: None,
             header: false,
             xattr: xattr::ENABLED && matches.has(&flags::EXTENDED)?,
+            magic: matches.has(&flags::MAGIC)?,
+            tree_style: TreeStyle::deduce(matches)?,
+            tree_colours: deduce_tree_colours(matches)?,
+            tree_compact: matches.has(&flags::TREE_COMPACT)?,
         };
 
         Ok(details)
@@ -136,11 +187,49 @@ impl details::Options {
             table: Some(TableOptions::deduce(matches, vars)?),
             header: matches.has(&flags::HEADER)?,
             xattr: xattr::ENABLED && matches.has(&flags::EXTENDED)?,
+            magic: matches.has(&flags::MAGIC)?,
+            tree_style: TreeStyle::deduce(matches)?,
+            tree_colours: deduce_tree_colours(matches)?,
+            tree_compact: matches.has(&flags::TREE_COMPACT)?,
         })
     }
 }
 
 
+impl TreeStyle {
+    fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+        let word = match matches.get(&flags::TREE_STYLE)? {
+            Some(w)  => w,
+            None     => return Ok(Self::default()),
+        };
+
+        if word == "unicode" {
+            Ok(Self::Unicode)
+        }
+        else if word == "rounded" {
+            Ok(Self::Rounded)
+        }
+        else if word == "ascii" {
+            Ok(Self::Ascii)
+        }
+        else if word == "indent" {
+            Ok(Self::Indent)
+        }
+        else if word == "heavy" {
+            Ok(Self::Heavy)
+        }
+        else {
+            Err(OptionsError::BadArgument(&flags::TREE_STYLE, word.into()))
+        }
+    }
+}
+
+fn deduce_tree_colours(matches: &MatchedFlags<'_>) -> Result<bool, OptionsError> {
+    let flag = matches.has_where(|f| f.matches(&flags::TREE_COLORS) || f.matches(&flags::TREE_COLOURS))?;
+    Ok(flag.is_some())
+}
+
+
 impl TerminalWidth {
     fn deduce<V: Vars>(vars: &V) -> Result<Self, OptionsError> {
         use crate::options::vars;
@@ -190,14 +279,56 @@ impl TableOptions {
         let time_format = TimeFormat::deduce(matches, vars)?;
         let size_format = SizeFormat::deduce(matches)?;
         let user_format = UserFormat::deduce(matches)?;
-        let columns = Columns::deduce(matches)?;
-        Ok(Self { size_format, time_format, user_format, columns })
+        let columns = Columns::deduce(matches, vars)?;
+        let alignments = Self::deduce_alignments(matches)?;
+        let max_path_width = deduce_max_width(matches)?;
+        Ok(Self { size_format, time_format, user_format, columns, alignments, max_path_width })
+    }
+
+    /// Determine which columns should be aligned differently from usual,
+    /// from a comma-separated list of column names and alignments, such as
+    /// `size:left,user:right`.
+    fn deduce_alignments(matches: &MatchedFlags<'_>) -> Result<Vec<(Column, Alignment)>, OptionsError> {
+        let spec = match matches.get(&flags::ALIGN)? {
+            Some(spec)  => spec.to_string_lossy(),
+            None        => return Ok(Vec::new()),
+        };
+
+        let mut alignments = Vec::new();
+        for entry in spec.split(',').map(str::trim) {
+            let bad_argument = || OptionsError::BadArgument(&flags::ALIGN, entry.into());
+
+            let (name, alignment) = entry.split_once(':').ok_or_else(bad_argument)?;
+            let alignment = match alignment {
+                "left"   => Alignment::Left,
+                "right"  => Alignment::Right,
+                _        => return Err(bad_argument()),
+            };
+
+            match ColumnName::from_name(name) {
+                Some(ColumnName::Name) => {
+                    return Err(bad_argument());
+                }
+                Some(column) => {
+                    // Columns that don’t exist on this platform can’t be
+                    // aligned either way.
+                    if let Some(column) = column.column() {
+                        alignments.push((column, alignment));
+                    }
+                }
+                None => {
+                    return Err(OptionsError::UnknownColumn(name.into(), NumberSource::Arg(&flags::ALIGN)));
+                }
+            }
+        }
+
+        Ok(alignments)
     }
 }
 
 
 impl Columns {
-    fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let time_types = TimeTypes::deduce(matches)?;
         let git = matches.has(&flags::GIT)?;
 
@@ -213,7 +344,38 @@ impl Columns {
 
         let path =          matches.has(&flags::PATH)?;
 
-        Ok(Self { time_types, inode, links, blocks, group, git, octal, permissions, filesize, user, path })
+        let order = Self::deduce_order(matches, vars)?;
+
+        Ok(Self { time_types, inode, links, blocks, group, git, octal, permissions, filesize, user, path, order })
+    }
+
+    /// Determine which columns to show and in which order, if they’ve been
+    /// picked with a comma-separated list of names instead of the flags for
+    /// each column. The `--columns` option takes precedence over the
+    /// environment variable.
+    fn deduce_order<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Option<Vec<ColumnName>>, OptionsError> {
+        use crate::options::vars;
+
+        let (spec, source) =
+            if let Some(spec) = matches.get(&flags::COLUMNS)? {
+                (spec.to_string_lossy().into_owned(), NumberSource::Arg(&flags::COLUMNS))
+            }
+            else {
+                match vars.get(vars::EXA_COLUMNS) {
+                    Some(ref s) if ! s.is_empty()  => (s.to_string_lossy().into_owned(), NumberSource::Env(vars::EXA_COLUMNS)),
+                    _                              => return Ok(None),
+                }
+            };
+
+        let mut order = Vec::new();
+        for name in spec.split(',').map(str::trim) {
+            match ColumnName::from_name(name) {
+                Some(column)  => order.push(column),
+                None          => return Err(OptionsError::UnknownColumn(name.into(), source)),
+            }
+        }
+
+        Ok(Some(order))
     }
 }
 
@@ -256,7 +418,10 @@ impl TimeFormat {
                 }
             };
 
-        if &word == "default" {
+        if let Some(format) = word.to_str().and_then(|w| w.strip_prefix('+')) {
+            Ok(Self::custom(format))
+        }
+        else if &word == "default" {
             Ok(Self::DefaultFormat)
         }
         else if &word == "iso" {
@@ -268,6 +433,9 @@ impl TimeFormat {
         else if &word == "full-iso" {
             Ok(Self::FullISO)
         }
+        else if &word == "relative" {
+            Ok(Self::Relative)
+        }
         else {
             Err(OptionsError::BadArgument(&flags::TIME_STYLE, word))
         }
@@ -363,7 +531,10 @@ mod test {
                                    &flags::HEADER, &flags::GROUP,  &flags::INODE, &flags::GIT,
                                    &flags::LINKS,  &flags::BLOCKS, &flags::LONG,  &flags::LEVEL,
                                    &flags::GRID,   &flags::ACROSS, &flags::ONE_LINE, &flags::TREE,
-                                   &flags::NUMERIC ];
+                                   &flags::NUMERIC, &flags::JSON, &flags::COLUMNS,
+                                   &flags::ALIGN, &flags::PRINT0, &flags::TREE_STYLE,
+                                   &flags::TREE_COLORS, &flags::TREE_COLOURS, &flags::TREE_COMPACT,
+                                   &flags::TOTAL_SIZE, &flags::MAGIC ];
 
     macro_rules! test {
 
@@ -428,6 +599,16 @@ mod test {
                 }
             }
         };
+
+        ($name:ident: $type:ident <- $inputs:expr, $vars:expr; $stricts:expr => $result:expr) => {
+            /// Like the first one, but with $vars.
+            #[test]
+            fn $name() {
+                for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| $type::deduce(mf, &$vars)) {
+                    assert_eq!(result, $result);
+                }
+            }
+        };
     }
 
 
@@ -468,6 +649,7 @@ mod test {
         test!(iso:       TimeFormat <- ["--time-style", "iso"], None;       Both => like Ok(TimeFormat::ISOFormat));
         test!(long_iso:  TimeFormat <- ["--time-style=long-iso"], None;     Both => like Ok(TimeFormat::LongISO));
         test!(full_iso:  TimeFormat <- ["--time-style", "full-iso"], None;  Both => like Ok(TimeFormat::FullISO));
+        test!(relative:  TimeFormat <- ["--time-style=relative"], None;     Both => like Ok(TimeFormat::Relative));
 
         // Overriding
         test!(actually:  TimeFormat <- ["--time-style=default", "--time-style", "iso"], None;  Last => like Ok(TimeFormat::ISOFormat));
@@ -485,6 +667,13 @@ mod test {
 
         // If the time-style argument is given, `TIME_STYLE` is overriding.
         test!(override_env:     TimeFormat <- ["--time-style=full-iso"], Some("long-iso".into());  Both => like Ok(TimeFormat::FullISO));
+
+        // Custom formats can be given in either place, with an optional
+        // separate format for older times.
+        test!(custom:      TimeFormat <- ["--time-style=+%H:%M:%S"], None;  Both => Ok(TimeFormat::custom("%H:%M:%S")));
+        test!(custom_old:  TimeFormat <- ["--time-style=+%b %d %H:%M\n%b %d  %Y"], None;  Both => Ok(TimeFormat::Custom { recent: "%b %d %H:%M".into(), old: Some("%b %d  %Y".into()) }));
+        test!(custom_env:  TimeFormat <- [], Some("+%F %T %Z".into());  Both => Ok(TimeFormat::custom("%F %T %Z")));
+        test!(no_plus:     TimeFormat <- ["--time-style=%F"], None;  Both => err OptionsError::BadArgument(&flags::TIME_STYLE, OsString::from("%F")));
     }
 
 
@@ -534,6 +723,86 @@ mod test {
     }
 
 
+    mod columns {
+        use super::*;
+        use crate::options::vars;
+        use crate::output::table::TimeType;
+
+        /// The columns that get picked when none of the flags for each
+        /// column are given, with the given spec.
+        fn spec(order: Option<Vec<ColumnName>>) -> Columns {
+            Columns {
+                time_types: TimeTypes::default(),
+                inode: false, links: false, blocks: false, group: false, git: false, octal: false,
+                permissions: true, filesize: true, user: true,
+                path: false,
+                order,
+            }
+        }
+
+        // Default behaviour
+        test!(empty:        Columns <- [], None;                             Both => Ok(spec(None)));
+        test!(empty_env:    Columns <- [], Some("".into());                  Both => Ok(spec(None)));
+
+        // Specs
+        test!(some:         Columns <- ["--columns=perm,size,name"], None;   Both => Ok(spec(Some(vec![ ColumnName::Permissions, ColumnName::FileSize, ColumnName::Name ]))));
+        test!(reordered:    Columns <- ["--columns", "name,mtime,git"], None;  Both => Ok(spec(Some(vec![ ColumnName::Name, ColumnName::Timestamp(TimeType::Modified), ColumnName::GitStatus ]))));
+        test!(long_names:   Columns <- ["--columns=accessed, user"], None;   Both => Ok(spec(Some(vec![ ColumnName::Timestamp(TimeType::Accessed), ColumnName::User ]))));
+        test!(from_env:     Columns <- [], Some("inode,name".into());        Both => Ok(spec(Some(vec![ ColumnName::Inode, ColumnName::Name ]))));
+        test!(override_env: Columns <- ["--columns=links"], Some("inode".into());  Both => Ok(spec(Some(vec![ ColumnName::HardLinks ]))));
+
+        // Errors
+        test!(typo:         Columns <- ["--columns=perm,szie"], None;        Both => err OptionsError::UnknownColumn("szie".into(), NumberSource::Arg(&flags::COLUMNS)));
+        test!(typo_env:     Columns <- [], Some("prem".into());              Both => err OptionsError::UnknownColumn("prem".into(), NumberSource::Env(vars::EXA_COLUMNS)));
+        test!(trailing:     Columns <- ["--columns=user,"], None;            Both => err OptionsError::UnknownColumn("".into(), NumberSource::Arg(&flags::COLUMNS)));
+    }
+
+
+    mod alignments {
+        use super::*;
+        use crate::output::table::Column;
+
+        fn deduce(inputs: &[&str]) -> Vec<Result<Vec<(Column, Alignment)>, OptionsError>> {
+            parse_for_test(inputs, TEST_ARGS, Both, TableOptions::deduce_alignments)
+        }
+
+        #[test]
+        fn none() {
+            for result in deduce(&[]) {
+                assert_eq!(result, Ok(Vec::new()));
+            }
+        }
+
+        #[test]
+        fn overrides() {
+            for result in deduce(&["--align=size:left,perm:right"]) {
+                assert_eq!(result, Ok(vec![ (Column::FileSize, Alignment::Left), (Column::Permissions, Alignment::Right) ]));
+            }
+        }
+
+        #[test]
+        fn bad_alignment() {
+            for result in deduce(&["--align", "size:middle"]) {
+                assert_eq!(result, Err(OptionsError::BadArgument(&flags::ALIGN, OsString::from("size:middle"))));
+            }
+        }
+
+        #[test]
+        fn name() {
+            for result in deduce(&["--align=name:right"]) {
+                assert_eq!(result, Err(OptionsError::BadArgument(&flags::ALIGN, OsString::from("name:right"))));
+            }
+        }
+
+        #[test]
+        fn unknown_column() {
+            for result in deduce(&["--align=szie:left"]) {
+                assert_eq!(result, Err(OptionsError::UnknownColumn("szie".into(), NumberSource::Arg(&flags::ALIGN))));
+            }
+        }
+    }
+
+
     mod views {
         use super::*;
 
@@ -561,6 +830,11 @@ mod test {
         test!(lid:           Mode <- ["--long", "--grid"], None;  Both => like Ok(Mode::GridDetails(_)));
         test!(leg:           Mode <- ["-lG"], None;               Both => like Ok(Mode::GridDetails(_)));
 
+        // JSON views
+        test!(json:          Mode <- ["--json"], None;            Both => like Ok(Mode::Json(_)));
+        test!(json_long:     Mode <- ["--json", "--long"], None;  Both => like Ok(Mode::Json(_)));
+        test!(json_tree:     Mode <- ["--tree", "--json"], None;  Both => like Ok(Mode::Json(_)));
+
         // Options that do nothing with --long
         test!(long_across:   Mode <- ["--long", "--across"],   None;  Last => like Ok(Mode::Details(_)));
 
@@ -577,6 +851,16 @@ mod test {
         #[cfg(feature = "git")]
         test!(just_git:      Mode <- ["--git"],    None;  Last => like Ok(Mode::Grid(_)));
 
+        test!(print0:        Mode <- ["-0"], None;                  Both => like Ok(Mode::Print0));
+        test!(print0_long:   Mode <- ["--print0", "--long"], None;  Last => like Ok(Mode::Print0));
+        test!(print0_tree:   Mode <- ["--tree", "-0"], None;        Both => like Ok(Mode::Print0));
+        test!(print0_long_2: Mode <- ["--print0", "--long"], None;  Complain => err OptionsError::Conflict(&flags::LONG, &flags::PRINT0));
+        test!(print0_grid:   Mode <- ["-0", "--grid"], None;        Complain => err OptionsError::Conflict(&flags::GRID, &flags::PRINT0));
+        test!(print0_total:  Mode <- ["-0", "--total-size"], None;  Complain => err OptionsError::Useless(&flags::TOTAL_SIZE, true, &flags::PRINT0));
+
+        #[cfg(feature = "git")]
+        test!(json_git:      Mode <- ["--json", "--git"],  None;  Both => like Ok(Mode::Json(json::Options { git: true })));
+
         test!(just_header_2: Mode <- ["--header"],   None;  Complain => err OptionsError::Useless(&flags::HEADER,  false, &flags::LONG));
         test!(just_group_2:  Mode <- ["--group"],    None;  Complain => err OptionsError::Useless(&flags::GROUP,   false, &flags::LONG));
         test!(just_inode_2:  Mode <- ["--inode"],    None;  Complain => err OptionsError::Useless(&flags::INODE,   false, &flags::LONG));
@@ -585,6 +869,10 @@ mod test {
         test!(just_binary_2: Mode <- ["--binary"],   None;  Complain => err OptionsError::Useless(&flags::BINARY,  false, &flags::LONG));
         test!(just_bytes_2:  Mode <- ["--bytes"],    None;  Complain => err OptionsError::Useless(&flags::BYTES,   false, &flags::LONG));
         test!(just_numeric2: Mode <- ["--numeric"],  None;  Complain => err OptionsError::Useless(&flags::NUMERIC, false, &flags::LONG));
+        test!(just_magic:    Mode <- ["--magic"],    None;  Last => like Ok(Mode::Grid(_)));
+        test!(just_magic_2:  Mode <- ["--magic"],    None;  Complain => err OptionsError::Useless(&flags::MAGIC,   false, &flags::LONG));
+        test!(tree_magic:    Mode <- ["--tree", "--magic"],  None;  Both => like Ok(Mode::Details(details::Options { magic: true, .. })));
+        test!(long_magic:    Mode <- ["--long", "--magic"],  None;  Both => like Ok(Mode::Details(details::Options { magic: true, .. })));
 
         #[cfg(feature = "git")]
         test!(just_git_2:    Mode <- ["--git"],    None;  Complain => err OptionsError::Useless(&flags::GIT,    false, &flags::LONG));
@@ -598,4 +886,21 @@ mod test {
         test!(og:            Mode <- ["--oneline", "--grid"],           None;  Both => like Ok(Mode::Grid(_)));
         test!(tg:            Mode <- ["--tree", "--grid"],              None;  Both => like Ok(Mode::Grid(_)));
     }
+
+
+    mod tree_guides {
+        use super::*;
+
+        test!(default:       Mode <- ["--tree"], None;                         Both => like Ok(Mode::Details(details::Options { tree_style: TreeStyle::Unicode, tree_colours: false, .. })));
+        test!(rounded:       Mode <- ["--tree", "--tree-style=rounded"], None;  Both => like Ok(Mode::Details(details::Options { tree_style: TreeStyle::Rounded, .. })));
+        test!(ascii_long:    Mode <- ["-lT", "--tree-style", "ascii"], None;    Both => like Ok(Mode::Details(details::Options { tree_style: TreeStyle::Ascii, .. })));
+        test!(overridden:    Mode <- ["-T", "--tree-style=heavy", "--tree-style=indent"], None;  Last => like Ok(Mode::Details(details::Options { tree_style: TreeStyle::Indent, .. })));
+        test!(colours:       Mode <- ["-T", "--tree-colours"], None;            Both => like Ok(Mode::Details(details::Options { tree_colours: true, .. })));
+        test!(colors:        Mode <- ["-T", "--tree-colors"], None;             Both => like Ok(Mode::Details(details::Options { tree_colours: true, .. })));
+        test!(compact:       Mode <- ["-T", "--tree-compact"], None;            Both => like Ok(Mode::Details(details::Options { tree_compact: true, .. })));
+        test!(compact_long:  Mode <- ["-lT", "--tree-compact"], None;           Both => like Ok(Mode::Details(details::Options { tree_compact: true, .. })));
+        test!(not_compact:   Mode <- ["-T"], None;                              Both => like Ok(Mode::Details(details::Options { tree_compact: false, .. })));
+
+        test!(bad_style:     Mode <- ["-T", "--tree-style=dotted"], None;       Both => err OptionsError::BadArgument(&flags::TREE_STYLE, OsString::from("dotted")));
+    }
 }
@@ -1,10 +1,11 @@
 //! The `TextCell` type for the details and lines views.
 
+use std::borrow::Cow;
 use std::iter::Sum;
 use std::ops::{Add, Deref, DerefMut};
 
 use ansi_term::{Style, ANSIString, ANSIStrings};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 
 /// An individual cell that holds text in a table, used in the details and
@@ -89,6 +90,14 @@ impl TextCell {
         (*self.width) += extra_width;
     }
 
+    /// Paints every string in this cell in the given style, keeping the
+    /// text as it is.
+    pub fn restyle(&mut self, style: Style) {
+        for string in &mut self.contents.0 {
+            *string.style_ref_mut() = style;
+        }
+    }
+
     /// Adds all the contents of another `TextCell` to the end of this cell.
     pub fn append(&mut self, other: Self) {
         (*self.width) += *other.width;
@@ -161,8 +170,11 @@ impl TextCellContents {
 
     /// Calculates the width that a cell with these contents would take up, by
     /// counting the number of characters in each unformatted ANSI string.
+    /// Strings that are just escape sequences, such as the ones around
+    /// hyperlinks, don’t take up any room.
     pub fn width(&self) -> DisplayWidth {
         self.0.iter()
+            .filter(|anstr| ! anstr.starts_with('\x1B'))
             .map(|anstr| DisplayWidth::from(&**anstr))
             .sum()
     }
@@ -245,6 +257,62 @@ impl Sum for DisplayWidth {
 }
 
 
+
+/// Shortens some text so it fits in the given display width by replacing
+/// the middle of it with an ellipsis, keeping the start and end — usually
+/// the most telling parts of a file name — intact. Text that already fits
+/// gets returned as it is.
+pub fn truncate_middle(text: &str, max_width: usize) -> Cow<'_, str> {
+    if *DisplayWidth::from(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    let budget = max_width.saturating_sub(ELLIPSIS_WIDTH);
+    let head = take_width(text.chars(), (budget + 1) / 2);
+    let tail = take_width(text.chars().rev(), budget / 2);
+
+    let mut truncated = String::with_capacity(max_width + ELLIPSIS.len());
+    truncated.push_str(&text[.. head]);
+    truncated.push_str(ELLIPSIS);
+    truncated.push_str(&text[text.len() - tail ..]);
+    Cow::Owned(truncated)
+}
+
+/// Shortens some text so it fits in the given display width by replacing
+/// the start of it with an ellipsis, which keeps the end of a path intact.
+/// Text that already fits gets returned as it is.
+pub fn truncate_left(text: &str, max_width: usize) -> Cow<'_, str> {
+    if *DisplayWidth::from(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    let budget = max_width.saturating_sub(ELLIPSIS_WIDTH);
+    let tail = take_width(text.chars().rev(), budget);
+    Cow::Owned(format!("{}{}", ELLIPSIS, &text[text.len() - tail ..]))
+}
+
+/// The text put in place of the characters that get truncated.
+const ELLIPSIS: &str = "…";
+const ELLIPSIS_WIDTH: usize = 1;
+
+/// Returns how many bytes of the given characters fit in the given display
+/// width, stopping before a character that would overflow it.
+fn take_width(chars: impl Iterator<Item = char>, max_width: usize) -> usize {
+    let mut width = 0;
+    let mut bytes = 0;
+
+    for c in chars {
+        width += c.width().unwrap_or(0);
+        if width > max_width {
+            break;
+        }
+
+        bytes += c.len_utf8();
+    }
+
+    bytes
+}
+
 #[cfg(test)]
 mod width_unit_test {
     use super::DisplayWidth;
@@ -274,3 +342,37 @@ mod width_unit_test {
         assert_eq!(*(cell + 8), 17);
     }
 }
+
+
+#[cfg(test)]
+mod truncate_test {
+    use super::*;
+
+    #[test]
+    fn fits() {
+        assert_eq!(truncate_middle("short.rs", 8), "short.rs");
+        assert_eq!(truncate_left("short.rs", 20), "short.rs");
+    }
+
+    #[test]
+    fn middle() {
+        assert_eq!(truncate_middle("very_long_generated_name.rs", 17), "very_lon…_name.rs");
+    }
+
+    #[test]
+    fn middle_wide() {
+        // Each of these characters takes up two columns.
+        assert_eq!(truncate_middle("日本語のファイル名", 8), "日本…名");
+    }
+
+    #[test]
+    fn left() {
+        assert_eq!(truncate_left("target/debug/build/exa.d", 10), "…ild/exa.d");
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(truncate_middle("anything", 0), "…");
+        assert_eq!(truncate_left("anything", 1), "…");
+    }
+}
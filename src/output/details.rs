@@ -62,7 +62,8 @@
 
 use std::io::{self, Write};
 use std::mem::MaybeUninit;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::vec::IntoIter as VecIntoIter;
 
 use ansi_term::Style;
@@ -71,12 +72,18 @@ use scoped_threadpool::Pool;
 use crate::fs::{Dir, File};
 use crate::fs::dir_action::RecurseOptions;
 use crate::fs::feature::git::GitCache;
+use crate::fs::ignore::IgnoreCache;
+use crate::fs::read_ahead::ReadAhead;
+use crate::fs::feature::magic::Magic;
 use crate::fs::feature::xattr::{Attribute, FileAttributes};
-use crate::fs::filter::FileFilter;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::total_size::fill_total_sizes;
 use crate::output::cell::TextCell;
-use crate::output::file_name::Options as FileStyle;
-use crate::output::table::{Table, Options as TableOptions, Row as TableRow};
-use crate::output::tree::{TreeTrunk, TreeParams, TreeDepth};
+use crate::output::thread_pool;
+use crate::output::file_name::{Classify, ShowIcons, Options as FileStyle};
+use crate::output::summary::{Summary, Remainder};
+use crate::output::table::{Table, Options as TableOptions, Row as TableRow, ENVIRONMENT};
+use crate::output::tree::{TreeTrunk, TreeParams, TreeDepth, TreePart, TreeStyle};
 use crate::theme::Theme;
 
 
@@ -92,6 +99,7 @@ use crate::theme::Theme;
 /// Almost all the heavy lifting is done in a Table object, which handles the
 /// columns for each row.
 #[derive(PartialEq, Eq, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Options {
 
     /// Options specific to drawing a table.
@@ -105,6 +113,21 @@ pub struct Options {
 
     /// Whether to show each file’s extended attributes.
     pub xattr: bool,
+
+    /// Whether to read the start of each regular file to work out what
+    /// kind of file it is, for colouring and icons.
+    pub magic: bool,
+
+    /// Which characters to draw the tree’s guides with.
+    pub tree_style: TreeStyle,
+
+    /// Whether to colour each level of the tree’s guides differently,
+    /// rather than using the punctuation colour for all of them.
+    pub tree_colours: bool,
+
+    /// Whether to merge chains of directories that each contain only one
+    /// other directory into a single row of the tree.
+    pub tree_compact: bool,
 }
 
 
@@ -118,15 +141,28 @@ pub struct Render<'a> {
     /// Whether to recurse through directories with a tree view, and if so,
     /// which options to use. This field is only relevant here if the `tree`
     /// field of the RecurseOptions is `true`.
-    pub recurse: Option<RecurseOptions>,
+    pub recurse: Option<&'a RecurseOptions>,
 
     /// How to sort and filter the files after getting their details.
     pub filter: &'a FileFilter,
 
-    /// Whether we are skipping Git-ignored files.
-    pub git_ignoring: bool,
+    /// The ignore rules to skip files with, if we are skipping ignored
+    /// files.
+    pub ignore: Option<&'a IgnoreCache>,
+
+    /// What’s been found out about the directories in this listing so far.
+    pub cache: &'a FilterCache,
+
+    /// The threads that read the directories in a tree before their rows
+    /// get made.
+    pub read_ahead: &'a ReadAhead,
 
     pub git: Option<&'a GitCache>,
+
+    /// Where to count the files inside the directories in a tree as they
+    /// get listed, for `--summary`. The files at the top of the listing
+    /// get counted before they get here.
+    pub summary: Option<&'a Mutex<Summary>>,
 }
 
 
@@ -146,18 +182,33 @@ impl<'a> AsRef<File<'a>> for Egg<'a> {
 
 
 impl<'a> Render<'a> {
+
+    /// The directory being listed, which path-aware patterns are relative
+    /// to, or an empty path when listing the files on the command-line.
+    fn root(&self) -> &'a Path {
+        self.dir.map_or(Path::new(""), |d| d.path.as_path())
+    }
+
     pub fn render<W: Write>(mut self, w: &mut W) -> io::Result<()> {
-        let n_cpus = match num_cpus::get() as u32 {
-            0 => 1,
-            n => n,
-        };
-        let mut pool = Pool::new(n_cpus);
+        let mut pool = thread_pool();
         let mut rows = Vec::new();
 
+        // The files get taken out so their contents can be read on the
+        // thread pool with `--magic`, while `self` is borrowed.
+        let mut files = std::mem::take(&mut self.files);
+
+        // The directories in the tree get read on other threads while the
+        // rows for the ones before them are being made.
+        if let Some(r) = self.recurse {
+            if r.tree {
+                self.read_ahead.start(&files, 0, r, self.filter, self.ignore, self.root(), self.cache);
+            }
+        }
+
         if let Some(ref table) = self.opts.table {
             match (self.git, self.dir) {
                 (Some(g), Some(d))  => if ! g.has_anything_for(&d.path) { self.git = None },
-                (Some(g), None)     => if ! self.files.iter().any(|f| g.has_anything_for(&f.path)) { self.git = None },
+                (Some(g), None)     => if ! files.iter().any(|f| g.has_anything_for(&f.path)) { self.git = None },
                 (None,    _)        => {/* Keep Git how it is */},
             }
 
@@ -172,14 +223,14 @@ impl<'a> Render<'a> {
             // This is weird, but I can’t find a way around it:
             // https://internals.rust-lang.org/t/should-option-mut-t-implement-copy/3715/6
             let mut table = Some(table);
-            self.add_files_to_table(&mut pool, &mut table, &mut rows, &self.files, TreeDepth::root());
+            self.add_files_to_table(&mut pool, &mut table, &mut rows, &mut files, TreeDepth::root(), 0);
 
             for row in self.iterate_with_table(table.unwrap(), rows) {
                 writeln!(w, "{}", row.strings())?
             }
         }
         else {
-            self.add_files_to_table(&mut pool, &mut None, &mut rows, &self.files, TreeDepth::root());
+            self.add_files_to_table(&mut pool, &mut None, &mut rows, &mut files, TreeDepth::root(), 0);
 
             for row in self.iterate(rows) {
                 writeln!(w, "{}", row.strings())?
@@ -191,21 +242,61 @@ impl<'a> Render<'a> {
 
     /// Adds files to the table, possibly recursively. This is easily
     /// parallelisable, and uses a pool of threads.
-    fn add_files_to_table<'dir>(&self, pool: &mut Pool, table: &mut Option<Table<'a>>, rows: &mut Vec<Row>, src: &[File<'dir>], depth: TreeDepth) {
-        use std::sync::{Arc, Mutex};
+    ///
+    /// The depth is how far into the tree the rows get drawn, and `chained`
+    /// is how many directories above have had their rows merged into
+    /// others by `--tree-compact`, which `--level` still counts.
+    fn add_files_to_table<'dir>(&self, pool: &mut Pool, table: &mut Option<Table<'a>>, rows: &mut Vec<Row>, src: &mut [File<'dir>], depth: TreeDepth, chained: usize) {
+        use std::sync::Arc;
         use log::*;
         use crate::fs::feature::xattr;
 
+        // Each directory in a tree has its sizes compared with one another.
+        if let Some(t) = table.as_mut() {
+            t.start_size_group();
+        }
+
+        // Directories get their totals added up first, on the whole pool.
+        // Files listed at the top level have had theirs added up already,
+        // so they could be sorted.
+        fill_total_sizes(pool, src, self.root(), self.filter, self.ignore, self.cache);
+
+        // With `--max-entries`, only the first few entries of each directory
+        // get listed, so they have to be sorted now to know which ones those
+        // are, rather than after their details have been looked up.
+        let mut remainder = None;
+        let listed = match self.recurse {
+            Some(r) if r.tree && r.entries_to_list(src.len()) < src.len() => {
+                self.filter.sort_files(src);
+
+                let count = r.entries_to_list(src.len());
+                remainder = Some(Remainder::of_files(&src[count ..]));
+                count
+            }
+            _ => src.len(),
+        };
+        let src = &mut src[.. listed];
+
         let mut file_eggs = (0..src.len()).map(|_| MaybeUninit::uninit()).collect::<Vec<_>>();
 
         pool.scoped(|scoped| {
             let file_eggs = Arc::new(Mutex::new(&mut file_eggs));
             let table = table.as_ref();
 
-            for (idx, file) in src.iter().enumerate() {
+            for (idx, file) in src.iter_mut().enumerate() {
                 let file_eggs = Arc::clone(&file_eggs);
 
                 scoped.execute(move || {
+                    // Reading the file is the slowest part of getting its
+                    // details, so it’s done here rather than when its name
+                    // gets painted. Anything other than a regular file gets
+                    // skipped without being opened, as is anything that the
+                    // grid-details view looked at before giving up.
+                    if self.opts.magic && file.magic.is_none() {
+                        file.magic = Magic::detect(file);
+                    }
+
+                    let file: &File<'_> = file;
                     let mut errors = Vec::new();
                     let mut xattrs = Vec::new();
 
@@ -256,8 +347,8 @@ impl<'a> Render<'a> {
 
                     let mut dir = None;
                     if let Some(r) = self.recurse {
-                        if file.is_directory() && r.tree && ! r.is_too_deep(depth.0) {
-                            match file.to_dir() {
+                        if file.is_directory() && r.tree && ! r.is_too_deep(depth.0 + chained) && ! r.is_pruned(file) {
+                            match self.read_ahead.read_dir(&file.path) {
                                 Ok(d) => {
                                     dir = Some(d);
                                 }
@@ -279,13 +370,24 @@ impl<'a> Render<'a> {
         self.filter.sort_files(&mut file_eggs);
 
         for (tree_params, egg) in depth.iterate_over(file_eggs.into_iter()) {
-            let mut files = Vec::new();
+            // The placeholder for the entries that were left out comes
+            // last, rather than the last entry that was listed.
+            let tree_params = if remainder.is_some() { TreeParams::new(depth, false) }
+                                                else { tree_params };
+
+            let mut xattrs = egg.xattrs;
             let mut errors = egg.errors;
 
             if let (Some(ref mut t), Some(row)) = (table.as_mut(), egg.table_row.as_ref()) {
                 t.add_widths(row);
             }
 
+            if let Some(summary) = self.summary {
+                if depth.0 > 0 {
+                    summary.lock().unwrap().count(egg.file, self.git);
+                }
+            }
+
             let file_name = self.file_style.for_file(egg.file, self.theme)
                                 .with_link_paths()
                                 .paint()
@@ -300,35 +402,16 @@ impl<'a> Render<'a> {
             rows.push(row);
 
             if let Some(ref dir) = egg.dir {
-                for file_to_add in dir.files(self.filter.dot_filter, self.git, self.git_ignoring) {
-                    match file_to_add {
-                        Ok(f) => {
-                            files.push(f);
-                        }
-                        Err((path, e)) => {
-                            errors.push((e, Some(path)));
-                        }
-                    }
-                }
-
-                self.filter.filter_child_files(&mut files);
+                let has_anything = self.add_dir_to_table(pool, table, rows, dir, &mut xattrs, &mut errors, depth, chained);
+                self.read_ahead.done_with(&dir.path);
 
-                if ! files.is_empty() {
-                    for xattr in egg.xattrs {
-                        rows.push(self.render_xattr(&xattr, TreeParams::new(depth.deeper(), false)));
-                    }
-
-                    for (error, path) in errors {
-                        rows.push(self.render_error(&error, TreeParams::new(depth.deeper(), false), path));
-                    }
-
-                    self.add_files_to_table(pool, table, rows, &files, depth.deeper());
+                if has_anything {
                     continue;
                 }
             }
 
-            let count = egg.xattrs.len();
-            for (index, xattr) in egg.xattrs.into_iter().enumerate() {
+            let count = xattrs.len();
+            for (index, xattr) in xattrs.into_iter().enumerate() {
                 let params = TreeParams::new(depth.deeper(), errors.is_empty() && index == count - 1);
                 let r = self.render_xattr(&xattr, params);
                 rows.push(r);
@@ -341,6 +424,89 @@ impl<'a> Render<'a> {
                 rows.push(r);
             }
         }
+
+        if let Some(remainder) = remainder {
+            rows.push(self.render_remainder(&remainder, TreeParams::new(depth, true)));
+        }
+    }
+
+    /// Adds the contents of a directory to the table, after the row for the
+    /// directory itself, returning whether it had anything in it. When it’s
+    /// empty, its attributes and errors get left for the caller to add.
+    ///
+    /// With `--tree-compact`, a directory whose only child is another
+    /// directory shares that directory’s row, so chains such as
+    /// `src/main/java` take up one row rather than three. The row keeps
+    /// the details of the first directory in the chain, as its Git status
+    /// and total size already cover everything inside it.
+    #[allow(clippy::too_many_arguments)]
+    fn add_dir_to_table(&self, pool: &mut Pool, table: &mut Option<Table<'a>>, rows: &mut Vec<Row>, dir: &Dir,
+                        xattrs: &mut Vec<Attribute>, errors: &mut Vec<(io::Error, Option<PathBuf>)>, depth: TreeDepth, chained: usize) -> bool {
+        let recurse = match self.recurse {
+            Some(r)  => r,
+            None     => return false,
+        };
+
+        let mut files = Vec::new();
+
+        for file_to_add in dir.files(self.filter.dot_filter, self.ignore) {
+            match file_to_add {
+                Ok(f) => {
+                    files.push(f);
+                }
+                Err((path, e)) => {
+                    errors.push((e, Some(path)));
+                }
+            }
+        }
+
+        self.filter.filter_recursed_files(&mut files, self.root(), recurse, depth.deeper().0 + chained, self.ignore, self.cache);
+
+        if files.is_empty() {
+            return false;
+        }
+
+        // A chain stops at the level limit, as merging the next directory
+        // would list its contents even though they’re too deep.
+        if self.opts.tree_compact && xattrs.is_empty() && errors.is_empty() && files.len() == 1
+        && recurse.descends_into(&files[0], depth.deeper().0 + chained) {
+            if let Ok(child_dir) = self.read_ahead.read_dir(&files[0].path) {
+                if let Some(row) = rows.last_mut() {
+                    self.add_to_chain(&mut row.name, &files[0]);
+                }
+
+                if let Some(summary) = self.summary {
+                    summary.lock().unwrap().count(&files[0], self.git);
+                }
+
+                return self.add_dir_to_table(pool, table, rows, &child_dir, xattrs, errors, depth, chained + 1);
+            }
+        }
+
+        for xattr in xattrs.drain(..) {
+            rows.push(self.render_xattr(&xattr, TreeParams::new(depth.deeper(), false)));
+        }
+
+        for (error, path) in errors.drain(..) {
+            rows.push(self.render_error(&error, TreeParams::new(depth.deeper(), false), path));
+        }
+
+        self.add_files_to_table(pool, table, rows, &mut files, depth.deeper(), chained);
+        true
+    }
+
+    /// Adds the name of the next directory in a compacted chain to the
+    /// name of the row, separated by a slash. Only the first name in the
+    /// chain gets an icon, and when names are being classified, the slash
+    /// after each directory already separates them.
+    fn add_to_chain(&self, name: &mut TextCell, dir: &File<'_>) {
+        let style = FileStyle { show_icons: ShowIcons::Off, .. *self.file_style };
+
+        if style.classify == Classify::JustFilenames {
+            name.push(self.theme.ui.punctuation.paint("/"), 1);
+        }
+
+        name.append(style.for_file(dir, self.theme).paint().promote());
     }
 
     pub fn render_header(&self, header: TableRow) -> Row {
@@ -366,6 +532,11 @@ impl<'a> Render<'a> {
         Row { cells: None, name, tree }
     }
 
+    fn render_remainder(&self, remainder: &Remainder, tree: TreeParams) -> Row {
+        let name = TextCell::paint(self.theme.ui.punctuation, remainder.describe(ENVIRONMENT.numeric()));
+        Row { cells: None, name, tree }
+    }
+
     fn render_xattr(&self, xattr: &Attribute, tree: TreeParams) -> Row {
         let name = TextCell::paint(self.theme.ui.perms.attribute, format!("{} (len {})", xattr.name, xattr.size));
         Row { cells: None, name, tree }
@@ -376,12 +547,22 @@ impl<'a> Render<'a> {
     }
 
     pub fn iterate_with_table(&'a self, table: Table<'a>, rows: Vec<Row>) -> TableIter<'a> {
+
+        // Names only need padding when there are columns after them.
+        let name_width = if table.has_trailing_columns() {
+            rows.iter().map(|r| r.tree.width() + *r.name.width).max().unwrap_or(0)
+        }
+        else {
+            0
+        };
+
         TableIter {
             tree_trunk: TreeTrunk::default(),
-            total_width: table.widths().total(),
+            leading_width: table.leading_width(),
+            name_width,
             table,
             inner: rows.into_iter(),
-            tree_style: self.theme.ui.punctuation,
+            tree_guides: self.tree_guides(),
         }
     }
 
@@ -389,7 +570,33 @@ impl<'a> Render<'a> {
         Iter {
             tree_trunk: TreeTrunk::default(),
             inner: rows.into_iter(),
-            tree_style: self.theme.ui.punctuation,
+            tree_guides: self.tree_guides(),
+        }
+    }
+
+    fn tree_guides(&self) -> TreeGuides {
+        let styles = if self.opts.tree_colours { self.theme.ui.tree_depths.to_vec() }
+                                          else { vec![ self.theme.ui.punctuation ] };
+
+        TreeGuides { glyphs: self.opts.tree_style, styles }
+    }
+}
+
+
+/// The characters and colours used to draw the guides of a tree.
+struct TreeGuides {
+    glyphs: TreeStyle,
+
+    /// The styles to paint each level of the tree with, which get cycled
+    /// through when the tree is deeper than there are styles.
+    styles: Vec<Style>,
+}
+
+impl TreeGuides {
+    fn paint(&self, cell: &mut TextCell, parts: &[TreePart]) {
+        for (depth, part) in parts.iter().enumerate() {
+            let style = self.styles[depth % self.styles.len()];
+            cell.push(style.paint(part.ascii_art(self.glyphs)), 4);
         }
     }
 }
@@ -406,7 +613,8 @@ pub struct Row {
     pub cells: Option<TableRow>,
 
     /// This file’s name, in coloured output. The name is treated separately
-    /// from the other cells, as it never requires padding.
+    /// from the other cells, as it only requires padding when there are
+    /// columns after it.
     pub name: TextCell,
 
     /// Information used to determine which symbols to display in a tree.
@@ -418,9 +626,10 @@ pub struct TableIter<'a> {
     inner: VecIntoIter<Row>,
     table: Table<'a>,
 
-    total_width: usize,
-    tree_style:  Style,
-    tree_trunk:  TreeTrunk,
+    leading_width: usize,
+    name_width:    usize,
+    tree_guides:   TreeGuides,
+    tree_trunk:    TreeTrunk,
 }
 
 impl<'a> Iterator for TableIter<'a> {
@@ -428,19 +637,19 @@ impl<'a> Iterator for TableIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next().map(|row| {
-            let mut cell =
+            let (mut cell, after) =
                 if let Some(cells) = row.cells {
                     self.table.render(cells)
                 }
                 else {
                     let mut cell = TextCell::default();
-                    cell.add_spaces(self.total_width);
-                    cell
+                    cell.add_spaces(self.leading_width);
+                    (cell, TextCell::default())
                 };
 
-            for tree_part in self.tree_trunk.new_row(row.tree) {
-                cell.push(self.tree_style.paint(tree_part.ascii_art()), 4);
-            }
+            let name_width = row.tree.width() + *row.name.width;
+
+            self.tree_guides.paint(&mut cell, self.tree_trunk.new_row(row.tree));
 
             // If any tree characters have been printed, then add an extra
             // space, which makes the output look much better.
@@ -449,6 +658,12 @@ impl<'a> Iterator for TableIter<'a> {
             }
 
             cell.append(row.name);
+
+            if *after.width > 0 {
+                cell.add_spaces(self.name_width - name_width);
+                cell.append(after);
+            }
+
             cell
         })
     }
@@ -457,7 +672,7 @@ impl<'a> Iterator for TableIter<'a> {
 
 pub struct Iter {
     tree_trunk: TreeTrunk,
-    tree_style: Style,
+    tree_guides: TreeGuides,
     inner: VecIntoIter<Row>,
 }
 
@@ -468,9 +683,7 @@ impl Iterator for Iter {
         self.inner.next().map(|row| {
             let mut cell = TextCell::default();
 
-            for tree_part in self.tree_trunk.new_row(row.tree) {
-                cell.push(self.tree_style.paint(tree_part.ascii_art()), 4);
-            }
+            self.tree_guides.paint(&mut cell, self.tree_trunk.new_row(row.tree));
 
             // If any tree characters have been printed, then add an extra
             // space, which makes the output look much better.
@@ -483,3 +696,114 @@ impl Iterator for Iter {
         })
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::temp_tree::TempTree;
+    use crate::output::QuotingStyle;
+    use crate::output::file_name::EmbedHyperlinks;
+
+    /// Lists a tree, returning the name on each row after the first, which
+    /// is the directory at the top.
+    fn rows(tree: &TempTree, recurse: &RecurseOptions, tree_compact: bool) -> Vec<String> {
+        let theme = Theme::plain();
+        let file_style = FileStyle {
+            classify: Classify::JustFilenames,
+            show_icons: ShowIcons::Off,
+            max_width: None,
+            embed_hyperlinks: EmbedHyperlinks::Off,
+            quoting_style: QuotingStyle::Literal,
+        };
+        let opts = Options {
+            table: None,
+            header: false,
+            xattr: false,
+            magic: false,
+            tree_style: TreeStyle::Indent,
+            tree_colours: false,
+            tree_compact,
+        };
+        let filter = FileFilter::default();
+        let cache = FilterCache::default();
+        let read_ahead = ReadAhead::spawn();
+
+        let files = vec![ File::from_args(tree.path.clone(), None, None).unwrap() ];
+        let render = Render {
+            dir: None, files, theme: &theme, file_style: &file_style, opts: &opts,
+            recurse: Some(recurse), filter: &filter, ignore: None, cache: &cache, read_ahead: &read_ahead, git: None, summary: None,
+        };
+
+        let mut out = Vec::new();
+        render.render(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+            .lines().skip(1)
+            .map(|line| unpainted(line).trim().to_string())
+            .collect()
+    }
+
+    /// Removes the colour codes from a line, as file names get painted even
+    /// with the plain theme.
+    fn unpainted(line: &str) -> String {
+        let mut text = String::new();
+        let mut chars = line.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            }
+            else {
+                text.push(c);
+            }
+        }
+
+        text
+    }
+
+    fn recurse(max_depth: Option<usize>, prune: &[&str]) -> RecurseOptions {
+        let prune = prune.iter().map(|p| glob::Pattern::new(p).unwrap()).collect();
+        RecurseOptions { tree: true, max_depth, max_entries: None, prune }
+    }
+
+    #[test]
+    fn pruned_directories_are_listed_but_not_their_contents() {
+        let tree = TempTree::new("tree-pruned");
+        tree.file("node_modules/a", 1).file("src/b", 1);
+
+        assert_eq!(rows(&tree, &recurse(None, &[ "node_modules" ]), false), vec![ "node_modules", "src", "b" ]);
+    }
+
+    #[test]
+    fn chain_stops_at_two_entries() {
+        let tree = TempTree::new("compact-two");
+        tree.file("one/two/x", 1).file("one/two/y", 1).file("z", 1);
+
+        assert_eq!(rows(&tree, &recurse(None, &[]), true), vec![ "one/two", "x", "y", "z" ]);
+    }
+
+    #[test]
+    fn chain_stops_at_a_file() {
+        let tree = TempTree::new("compact-file");
+        tree.file("one/two/only", 1).file("z", 1);
+
+        assert_eq!(rows(&tree, &recurse(None, &[]), true), vec![ "one/two", "only", "z" ]);
+    }
+
+    #[test]
+    fn chain_stops_at_a_pruned_directory() {
+        let tree = TempTree::new("compact-pruned");
+        tree.file("one/two/three/x", 1).file("z", 1);
+
+        assert_eq!(rows(&tree, &recurse(None, &[ "two" ]), true), vec![ "one", "two", "z" ]);
+    }
+
+    #[test]
+    fn chain_stops_at_the_level_limit() {
+        let tree = TempTree::new("compact-level");
+        tree.file("one/two/three/x", 1).file("z", 1);
+
+        assert_eq!(rows(&tree, &recurse(None, &[]), true), vec![ "one/two/three", "x", "z" ]);
+        assert_eq!(rows(&tree, &recurse(Some(2), &[]), true), vec![ "one", "two", "z" ]);
+    }
+}
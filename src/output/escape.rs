@@ -1,8 +1,62 @@
 use ansi_term::{ANSIString, Style};
 
 
-pub fn escape(string: String, bits: &mut Vec<ANSIString<'_>>, good: Style, bad: Style) {
-    if string.chars().all(|c| c >= 0x20 as char && c != 0x7f as char) {
+/// How to quote and escape file names, so that names with spaces, quotes,
+/// or control characters in them can be told apart, or pasted back into a
+/// shell. These are the same styles that GNU `ls` has.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum QuotingStyle {
+
+    /// Shell-escape names when printing to a terminal, and leave them
+    /// alone when the output is being piped somewhere else. This gets
+    /// resolved before any names are printed.
+    Automatic,
+
+    /// Print names as they are, apart from escaping control characters.
+    Literal,
+
+    /// Put names in quotes if a shell would need them to be, and show
+    /// control characters as question marks.
+    Shell,
+
+    /// Put names in quotes if a shell would need them to be, and write
+    /// control characters using `$'…'` strings.
+    ShellEscape,
+
+    /// Put names in double quotes, escaping characters the way C would.
+    C,
+
+    /// Escape characters the way C would, without the quotes, escaping
+    /// spaces too.
+    Escape,
+}
+
+impl QuotingStyle {
+
+    /// Resolves the automatic quoting style, depending on whether the
+    /// output is going to a terminal.
+    pub fn for_output(self, isatty: bool) -> Self {
+        match self {
+            Self::Automatic if isatty  => Self::ShellEscape,
+            Self::Automatic            => Self::Literal,
+            other                      => other,
+        }
+    }
+}
+
+
+pub fn escape(string: String, bits: &mut Vec<ANSIString<'_>>, good: Style, bad: Style, quoting: QuotingStyle) {
+    match quoting {
+        QuotingStyle::Automatic | QuotingStyle::Literal  => escape_literal(string, bits, good, bad),
+        QuotingStyle::Shell                              => quote_shell(&string, bits, good, bad, false),
+        QuotingStyle::ShellEscape                        => quote_shell(&string, bits, good, bad, true),
+        QuotingStyle::C                                  => escape_c(&string, bits, good, bad, true),
+        QuotingStyle::Escape                             => escape_c(&string, bits, good, bad, false),
+    }
+}
+
+fn escape_literal(string: String, bits: &mut Vec<ANSIString<'_>>, good: Style, bad: Style) {
+    if string.chars().all(is_printable) {
         bits.push(good.paint(string));
         return;
     }
@@ -11,7 +65,7 @@ pub fn escape(string: String, bits: &mut Vec<ANSIString<'_>>, good: Style, bad:
         // The `escape_default` method on `char` is *almost* what we want here, but
         // it still escapes non-ASCII UTF-8 characters, which are still printable.
 
-        if c >= 0x20 as char && c != 0x7f as char {
+        if is_printable(c) {
             // TODO: This allocates way too much,
             // hence the `all` check above.
             let mut s = String::new();
@@ -24,3 +78,215 @@ pub fn escape(string: String, bits: &mut Vec<ANSIString<'_>>, good: Style, bad:
         }
     }
 }
+
+/// Quotes a string so it can be pasted into a shell. Single quotes are
+/// used unless the string contains one, in which case double quotes get
+/// used if nothing else in it would need escaping inside them.
+///
+/// When `dollar_strings` is set, control characters are written out as
+/// `$'\n'` strings between the quoted parts, which keeps them pasteable;
+/// otherwise, they get replaced by question marks.
+fn quote_shell(string: &str, bits: &mut Vec<ANSIString<'_>>, good: Style, bad: Style, dollar_strings: bool) {
+    if ! string.is_empty() && ! string.chars().any(needs_shell_quotes) {
+        bits.push(good.paint(string.to_owned()));
+        return;
+    }
+
+    let has_controls = ! string.chars().all(is_printable);
+
+    if string.contains('\'') && ! (has_controls && dollar_strings)
+                             && ! string.chars().any(|c| matches!(c, '"' | '$' | '`' | '\\' | '!')) {
+        bits.push(good.paint("\""));
+        for c in string.chars() {
+            if is_printable(c) { bits.push(good.paint(c.to_string())) }
+                          else { bits.push(bad.paint("?")) }
+        }
+        bits.push(good.paint("\""));
+        return;
+    }
+
+    let mut quoted = String::new();
+    let mut in_quotes = false;
+    let mut controls = String::new();
+
+    for c in string.chars() {
+        if is_printable(c) || ! dollar_strings {
+            if ! controls.is_empty() {
+                bits.push(bad.paint(format!("$'{}'", controls)));
+                controls.clear();
+            }
+
+            if ! in_quotes {
+                quoted.push('\'');
+                in_quotes = true;
+            }
+
+            if c == '\'' {
+                quoted.push_str("'\\''");
+            }
+            else if is_printable(c) {
+                quoted.push(c);
+            }
+            else {
+                bits.push(good.paint(std::mem::take(&mut quoted)));
+                bits.push(bad.paint("?"));
+            }
+        }
+        else {
+            if in_quotes {
+                quoted.push('\'');
+                bits.push(good.paint(std::mem::take(&mut quoted)));
+                in_quotes = false;
+            }
+
+            controls.push_str(&c_escape(c));
+        }
+    }
+
+    if in_quotes {
+        quoted.push('\'');
+        bits.push(good.paint(quoted));
+    }
+    else if ! controls.is_empty() {
+        bits.push(bad.paint(format!("$'{}'", controls)));
+    }
+    else if string.is_empty() {
+        bits.push(good.paint("''"));
+    }
+}
+
+/// Escapes a string the way a C string literal would be, surrounding it
+/// with double quotes when `quotes` is set, or escaping spaces otherwise.
+fn escape_c(string: &str, bits: &mut Vec<ANSIString<'_>>, good: Style, bad: Style, quotes: bool) {
+    let mut plain = String::new();
+
+    if quotes {
+        plain.push('"');
+    }
+
+    for c in string.chars() {
+        let escaped = match c {
+            '"' if quotes         => "\\\"".into(),
+            ' ' if ! quotes       => "\\ ".into(),
+            '\\'                  => "\\\\".into(),
+            c if is_printable(c)  => {
+                plain.push(c);
+                continue;
+            }
+            c                     => c_escape(c),
+        };
+
+        if ! plain.is_empty() {
+            bits.push(good.paint(std::mem::take(&mut plain)));
+        }
+
+        bits.push(bad.paint(escaped));
+    }
+
+    if quotes {
+        plain.push('"');
+    }
+
+    if ! plain.is_empty() {
+        bits.push(good.paint(plain));
+    }
+}
+
+/// Writes a control character the way a C string literal would.
+fn c_escape(c: char) -> String {
+    match c {
+        '\n'    => "\\n".into(),
+        '\t'    => "\\t".into(),
+        '\r'    => "\\r".into(),
+        '\x07'  => "\\a".into(),
+        '\x08'  => "\\b".into(),
+        '\x0B'  => "\\v".into(),
+        '\x0C'  => "\\f".into(),
+        c       => format!("\\{:03o}", u32::from(c)),
+    }
+}
+
+fn is_printable(c: char) -> bool {
+    c >= 0x20 as char && c != 0x7f as char
+}
+
+/// Whether a shell would treat this character specially, meaning a name
+/// with it in has to be quoted.
+fn needs_shell_quotes(c: char) -> bool {
+    ! is_printable(c) || " !\"#$&'()*;<=>?[\\]^`{|}~".contains(c)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ansi_term::ANSIStrings;
+
+    fn quote(name: &str, quoting: QuotingStyle) -> String {
+        let mut bits = Vec::new();
+        escape(name.into(), &mut bits, Style::default(), Style::default(), quoting);
+        ANSIStrings(&bits).to_string()
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(quote("two words\n", QuotingStyle::Literal), "two words\\n");
+    }
+
+    #[test]
+    fn shell_plain() {
+        assert_eq!(quote("exa.rs", QuotingStyle::Shell), "exa.rs");
+    }
+
+    #[test]
+    fn shell_spaces() {
+        assert_eq!(quote("two words", QuotingStyle::Shell), "'two words'");
+    }
+
+    #[test]
+    fn shell_apostrophe() {
+        assert_eq!(quote("it's", QuotingStyle::Shell), "\"it's\"");
+    }
+
+    #[test]
+    fn shell_both_quotes() {
+        assert_eq!(quote("it's \"quoted\"", QuotingStyle::Shell), "'it'\\''s \"quoted\"'");
+    }
+
+    #[test]
+    fn shell_control() {
+        assert_eq!(quote("a\nb", QuotingStyle::Shell), "'a?b'");
+    }
+
+    #[test]
+    fn shell_escape_control() {
+        assert_eq!(quote("a\nb", QuotingStyle::ShellEscape), "'a'$'\\n''b'");
+    }
+
+    #[test]
+    fn shell_escape_only_control() {
+        assert_eq!(quote("\t", QuotingStyle::ShellEscape), "$'\\t'");
+    }
+
+    #[test]
+    fn c() {
+        assert_eq!(quote("say \"hi\"\n", QuotingStyle::C), "\"say \\\"hi\\\"\\n\"");
+    }
+
+    #[test]
+    fn c_octal() {
+        assert_eq!(quote("\x1B", QuotingStyle::C), "\"\\033\"");
+    }
+
+    #[test]
+    fn escape_spaces() {
+        assert_eq!(quote("two words", QuotingStyle::Escape), "two\\ words");
+    }
+
+    #[test]
+    fn automatic() {
+        assert_eq!(QuotingStyle::Automatic.for_output(true), QuotingStyle::ShellEscape);
+        assert_eq!(QuotingStyle::Automatic.for_output(false), QuotingStyle::Literal);
+        assert_eq!(QuotingStyle::C.for_output(false), QuotingStyle::C);
+    }
+}
@@ -4,9 +4,10 @@ use std::path::Path;
 use ansi_term::{ANSIString, Style};
 
 use crate::fs::{File, FileTarget};
-use crate::output::cell::TextCellContents;
-use crate::output::escape;
-use crate::output::icons::{icon_for_file, iconify_style};
+use crate::output::cell::{TextCellContents, truncate_middle};
+use crate::output::{escape, QuotingStyle};
+use crate::output::hyperlink;
+use crate::output::icons::{icon_for_file, iconify_style, FileIcon};
 use crate::output::render::FiletypeColours;
 
 
@@ -19,6 +20,15 @@ pub struct Options {
 
     /// Whether to prepend icon characters before file names.
     pub show_icons: ShowIcons,
+
+    /// The widest a file name can be before the middle of it gets cut out.
+    pub max_width: Option<usize>,
+
+    /// Whether to make file names into hyperlinks to the files.
+    pub embed_hyperlinks: EmbedHyperlinks,
+
+    /// How to quote and escape file names.
+    pub quoting_style: QuotingStyle,
 }
 
 impl Options {
@@ -85,6 +95,19 @@ pub enum ShowIcons {
 }
 
 
+/// Whether to wrap file names in hyperlinks, which terminals that support
+/// them let the user click on to open the file.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EmbedHyperlinks {
+
+    /// Just display the file names.
+    Off,
+
+    /// Link each file name, and each link target’s name, to its file.
+    On,
+}
+
+
 /// A **file name** holds all the information necessary to display the name
 /// of the given file. This is used in all of the views.
 pub struct FileName<'a, 'dir, C> {
@@ -135,6 +158,9 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                             let target_options = Options {
                                 classify: Classify::JustFilenames,
                                 show_icons: ShowIcons::Off,
+                                max_width: None,
+                                embed_hyperlinks: EmbedHyperlinks::Off,
+                                quoting_style: self.options.quoting_style,
                             };
 
                             let target_file = FileName {
@@ -161,7 +187,7 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                     }
                 }
             }
-            let file_icon = icon_for_file(self.file).to_string();
+            let file_icon = icon_for_file(self.file, self.colours).to_string();
             bits.push(style.paint(file_icon));
 
             match spaces_count {
@@ -171,6 +197,10 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
             }
         }
 
+        if self.options.embed_hyperlinks == EmbedHyperlinks::On {
+            bits.push(Style::default().paint(hyperlink::start(&self.file.path)));
+        }
+
         if self.file.parent_dir.is_none() {
             if let Some(parent) = self.file.path.parent() {
                 self.add_parent_bits(&mut bits, parent);
@@ -189,6 +219,10 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
             }
         }
 
+        if self.options.embed_hyperlinks == EmbedHyperlinks::On {
+            bits.push(Style::default().paint(hyperlink::END));
+        }
+
         if let (LinkStyle::FullLinkPaths, Some(target)) = (self.link_style, self.target.as_ref()) {
             match target {
                 FileTarget::Ok(target) => {
@@ -196,6 +230,10 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                     bits.push(self.colours.normal_arrow().paint("->"));
                     bits.push(Style::default().paint(" "));
 
+                    if self.options.embed_hyperlinks == EmbedHyperlinks::On {
+                        bits.push(Style::default().paint(hyperlink::start(&target.path)));
+                    }
+
                     if let Some(parent) = target.path.parent() {
                         self.add_parent_bits(&mut bits, parent);
                     }
@@ -204,6 +242,9 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                         let target_options = Options {
                             classify: Classify::JustFilenames,
                             show_icons: ShowIcons::Off,
+                            max_width: None,
+                            embed_hyperlinks: EmbedHyperlinks::Off,
+                            quoting_style: self.options.quoting_style,
                         };
 
                         let target_name = FileName {
@@ -217,7 +258,13 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                         for bit in target_name.coloured_file_name() {
                             bits.push(bit);
                         }
+                    }
+
+                    if self.options.embed_hyperlinks == EmbedHyperlinks::On {
+                        bits.push(Style::default().paint(hyperlink::END));
+                    }
 
+                    if ! target.name.is_empty() {
                         if let Classify::AddFileIndicators = self.options.classify {
                             if let Some(class) = self.classify_char(target) {
                                 bits.push(Style::default().paint(class));
@@ -236,6 +283,7 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                         &mut bits,
                         self.colours.broken_filename(),
                         self.colours.broken_control_char(),
+                        self.options.quoting_style,
                     );
                 }
 
@@ -267,6 +315,7 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
                 bits,
                 self.colours.symlink_path(),
                 self.colours.control_char(),
+                self.options.quoting_style,
             );
             bits.push(self.colours.symlink_path().paint(std::path::MAIN_SEPARATOR.to_string()));
         }
@@ -325,11 +374,17 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
         let file_style = Fixed(252).normal();
         let mut bits = Vec::new();
 
+        let name = match self.options.max_width {
+            Some(width)  => truncate_middle(&self.file.name, width).into_owned(),
+            None         => self.file.name.clone(),
+        };
+
         escape(
-            self.file.name.clone(),
+            name,
             &mut bits,
             file_style,
             self.colours.control_char(),
+            self.options.quoting_style,
         );
 
         bits
@@ -368,8 +423,9 @@ impl<'a, 'dir, C: Colours> FileName<'a, 'dir, C> {
 }
 
 
-/// The set of colours that are needed to paint a file name.
-pub trait Colours: FiletypeColours {
+/// The set of colours that are needed to paint a file name, along with the
+/// icons to go next to it.
+pub trait Colours: FiletypeColours + FileIcon {
 
     /// The style to paint the path of a symlink’s target, up to but not
     /// including the file’s name.
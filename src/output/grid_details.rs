@@ -7,13 +7,16 @@ use term_grid as grid;
 
 use crate::fs::{Dir, File};
 use crate::fs::feature::git::GitCache;
+use crate::fs::feature::magic::Magic;
+use crate::fs::ignore::IgnoreCache;
 use crate::fs::feature::xattr::FileAttributes;
-use crate::fs::filter::FileFilter;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::read_ahead::ReadAhead;
 use crate::output::cell::TextCell;
 use crate::output::details::{Options as DetailsOptions, Row as DetailsRow, Render as DetailsRender};
 use crate::output::file_name::Options as FileStyle;
 use crate::output::grid::Options as GridOptions;
-use crate::output::table::{Table, Row as TableRow, Options as TableOptions};
+use crate::output::table::{Table, Row as TableRow, Options as TableOptions, ScaleRanges};
 use crate::output::tree::{TreeParams, TreeDepth};
 use crate::theme::Theme;
 
@@ -82,8 +85,15 @@ pub struct Render<'a> {
     /// mode is activated.
     pub row_threshold: RowThreshold,
 
-    /// Whether we are skipping Git-ignored files.
-    pub git_ignoring: bool,
+    /// The ignore rules to skip files with, if we are skipping ignored
+    /// files.
+    pub ignore: Option<&'a IgnoreCache>,
+
+    /// What’s been found out about the directories in this listing so far.
+    pub cache: &'a FilterCache,
+
+    /// The threads that read directories ahead of them being listed.
+    pub read_ahead: &'a ReadAhead,
 
     pub git: Option<&'a GitCache>,
 
@@ -107,8 +117,11 @@ impl<'a> Render<'a> {
             opts:          self.details,
             recurse:       None,
             filter:        self.filter,
-            git_ignoring:  self.git_ignoring,
+            ignore:        self.ignore,
+            cache:         self.cache,
+            read_ahead:    self.read_ahead,
             git:           self.git,
+            summary:       None,
         }
     }
 
@@ -125,14 +138,14 @@ impl<'a> Render<'a> {
             opts:          self.details,
             recurse:       None,
             filter:        self.filter,
-            git_ignoring:  self.git_ignoring,
+            ignore:        self.ignore,
+            cache:         self.cache,
+            read_ahead:    self.read_ahead,
             git:           self.git,
+            summary:       None,
         }
     }
 
-    // This doesn’t take an IgnoreCache even though the details one does
-    // because grid-details has no tree view.
-
     pub fn render<W: Write>(mut self, w: &mut W) -> io::Result<()> {
         if let Some((grid, width)) = self.find_fitting_grid() {
             write!(w, "{}", grid.fit_into_columns(width))
@@ -145,6 +158,14 @@ impl<'a> Render<'a> {
     pub fn find_fitting_grid(&mut self) -> Option<(grid::Grid, grid::Width)> {
         let options = self.details.table.as_ref().expect("Details table options not given!");
 
+        // The kind of each file has to be known before its name gets
+        // painted to work out the widths of the columns.
+        if self.details.magic {
+            for file in &mut self.files {
+                file.magic = Magic::detect(file);
+            }
+        }
+
         let drender = self.details_for_column();
 
         let (first_table, _) = self.make_table(options, &drender);
@@ -215,9 +236,18 @@ impl<'a> Render<'a> {
     }
 
     fn make_grid(&mut self, column_count: usize, options: &'a TableOptions, file_names: &[TextCell], rows: Vec<TableRow>, drender: &DetailsRender<'_>) -> grid::Grid {
+        // Values coloured on a scale get compared with the whole listing,
+        // rather than just the other files in their column of the grid.
+        let mut ranges = ScaleRanges::default();
+        for row in &rows {
+            ranges.add(row);
+        }
+
         let mut tables = Vec::new();
         for _ in 0 .. column_count {
-            tables.push(self.make_table(options, drender));
+            let (mut table, details_rows) = self.make_table(options, drender);
+            table.set_ranges(ranges.clone());
+            tables.push((table, details_rows));
         }
 
         let mut num_cells = rows.len();
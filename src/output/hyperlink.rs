@@ -0,0 +1,112 @@
+//! Hyperlinks that terminals can open, using the OSC 8 escape sequence.
+//!
+//! A hyperlink is made of an opening sequence containing the URL, the text
+//! of the link, and a closing sequence with an empty URL. Terminals that
+//! don’t support them just print the text, so they’re safe to emit, but
+//! the escape sequences take up no room on screen and have to be left out
+//! of width calculations.
+
+use std::env;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+
+
+/// The sequence that ends a hyperlink.
+pub const END: &str = "\x1B]8;;\x1B\\";
+
+/// Returns the sequence that starts a hyperlink to the given file, which
+/// gets turned into an absolute `file://` URL.
+pub fn start(path: &Path) -> String {
+    format!("\x1B]8;;file://{}{}\x1B\\", &*HOSTNAME, encode(&absolute(path)))
+}
+
+/// Makes a path absolute without resolving any symlinks in it, as a link
+/// should point to the link itself rather than what it points to.
+fn absolute(path: &Path) -> PathBuf {
+    match *CURRENT_DIR {
+        Some(ref dir) if path.is_relative()  => dir.join(path),
+        _                                    => path.to_path_buf(),
+    }
+}
+
+/// Percent-encodes every byte in a path that can’t appear in a URL as it
+/// is, keeping the slashes between its components.
+fn encode(path: &Path) -> String {
+    let mut url = String::new();
+
+    for byte in path_bytes(path) {
+        match byte {
+            b'A' ..= b'Z' | b'a' ..= b'z' | b'0' ..= b'9' |
+            b'-' | b'.' | b'_' | b'~' | b'/'  => url.push(char::from(byte)),
+            _                                 => { let _ = write!(url, "%{:02X}", byte); }
+        }
+    }
+
+    url
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(windows)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    // URLs for Windows paths look like ‘file:///C:/Users’.
+    let path = path.to_string_lossy().replace('\\', "/");
+    format!("/{}", path.trim_start_matches('/')).into_bytes()
+}
+
+
+lazy_static! {
+    static ref CURRENT_DIR: Option<PathBuf> = env::current_dir().ok();
+    static ref HOSTNAME: String = hostname();
+}
+
+/// The name of this machine, which goes in each URL so that terminals can
+/// tell a file on this machine from one on another, such as over SSH.
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buffer = [0_u8; 256];
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return String::new();
+    }
+
+    let length = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[.. length]).into_owned()
+}
+
+#[cfg(windows)]
+fn hostname() -> String {
+    String::new()
+}
+
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plain() {
+        assert_eq!(encode(Path::new("/home/ben/exa.rs")), "/home/ben/exa.rs");
+    }
+
+    #[test]
+    fn spaces() {
+        assert_eq!(encode(Path::new("/tmp/two words")), "/tmp/two%20words");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(encode(Path::new("/tmp/é")), "/tmp/%C3%A9");
+    }
+
+    #[test]
+    fn already_absolute() {
+        assert_eq!(absolute(Path::new("/etc/hosts")), PathBuf::from("/etc/hosts"));
+    }
+}
@@ -1,7 +1,6 @@
 use ansi_term::Style;
 
 use crate::fs::File;
-use crate::info::filetype::FileExtensions;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
@@ -92,10 +91,9 @@ lazy_static! {
     };
 }
 
-pub fn icon_for_file(file: &File<'_>) -> char {
-    let extensions = Box::new(FileExtensions);
-
+pub fn icon_for_file<I: FileIcon>(file: &File<'_>, icons: &I) -> char {
     if let Some(icon) = MAP_BY_NAME.get(file.name.as_str()) { *icon }
+    else if let Some(icon) = icons.icon_file(file) { icon }
     else if file.points_to_directory() {
         match file.name.as_str() {
             "bin"           => '\u{e5fc}', // 
@@ -105,7 +103,6 @@ pub fn icon_for_file(file: &File<'_>) -> char {
             _               => '\u{f07c}'  // 
         }
     }
-    else if let Some(icon) = extensions.icon_file(file) { icon }
     else if let Some(ext) = file.ext.as_ref() {
         match ext.as_str() {
             "ai"            => '\u{e7b4}', // 
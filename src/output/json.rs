@@ -0,0 +1,318 @@
+//! The **JSON** output view writes each file as a JSON object, one per line,
+//! for scripts that want to read exa’s output without having to scrape the
+//! columns of the details view.
+//!
+//! It reads the same fields that the details view puts in its table, but
+//! writes out their raw values rather than the formatted and coloured ones:
+//!
+//! ```text
+//!     {"name":"Cargo.toml","path":"./Cargo.toml","type":"file","size":547,...}
+//! ```
+//!
+//! When recursing, either with `--recurse` or `--tree`, each directory gets a
+//! `children` array holding the objects for the files inside it, instead of
+//! them being listed separately.
+
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use scoped_threadpool::Pool;
+
+use crate::fs::{Dir, File, FileTarget};
+use crate::fs::dir_action::RecurseOptions;
+use crate::fs::feature::git::GitCache;
+use crate::fs::ignore::IgnoreCache;
+use crate::fs::feature::xattr::{self, FileAttributes};
+use crate::fs::fields as f;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::output::{read_children, thread_pool};
+use crate::output::table::{self, ENVIRONMENT};
+use crate::output::time::TimeFormat;
+use crate::output::tree::TreeDepth;
+use crate::theme::Theme;
+
+
+/// Options for the JSON view.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Options {
+
+    /// Whether to include each file’s Git status. Looking this up means
+    /// scanning for repositories first, so it’s only done if asked for.
+    pub git: bool,
+}
+
+
+pub struct Render<'a> {
+    pub dir: Option<&'a Dir>,
+    pub files: Vec<File<'a>>,
+    pub opts: &'a Options,
+
+    /// Whether to recurse through directories, and if so, which options to
+    /// use. Both `--recurse` and `--tree` nest the results the same way.
+    pub recurse: Option<&'a RecurseOptions>,
+
+    /// How to sort and filter the files after getting their details.
+    pub filter: &'a FileFilter,
+
+    /// The ignore rules to skip files with, if we are skipping ignored
+    /// files.
+    pub ignore: Option<&'a IgnoreCache>,
+
+    /// What’s been found out about the directories in this listing so far.
+    pub cache: &'a FilterCache,
+
+    pub git: Option<&'a GitCache>,
+}
+
+impl<'a> Render<'a> {
+    pub fn render<W: Write>(mut self, w: &mut W) -> io::Result<()> {
+        // The symbolic permissions are rendered with the same code as the
+        // details view, so they need a theme that never adds colours.
+        let theme = Theme::plain();
+        let mut pool = thread_pool();
+
+        self.filter.sort_files(&mut self.files);
+        for file in &self.files {
+            let mut object = String::new();
+            self.write_file(&mut pool, &mut object, file, &theme, TreeDepth::root())?;
+            writeln!(w, "{}", object)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_file(&self, pool: &mut Pool, out: &mut String, file: &File<'_>, theme: &Theme, depth: TreeDepth) -> io::Result<()> {
+        let xattrs = if xattr::ENABLED { file.path.attributes().unwrap_or_default() }
+                                  else { Vec::new() };
+
+        out.push('{');
+        write_key(out, "name");
+        write_string(out, &file.name);
+        write_key(out, "path");
+        write_string(out, &file.path.to_string_lossy());
+        write_key(out, "type");
+        write_string(out, type_name(file.type_char()));
+
+        write_key(out, "size");
+        match file.size() {
+            f::Size::Some(bytes)  => { let _ = write!(out, "{}", bytes); }
+            f::Size::None         => out.push_str("null"),
+            f::Size::DeviceIDs(ids) => {
+                let _ = write!(out, "null,\"device\":{{\"major\":{},\"minor\":{}}}", ids.major, ids.minor);
+            }
+        }
+
+        let permissions = table::permissions_plus(file, ! xattrs.is_empty()).render(theme);
+        write_key(out, "permissions");
+        write_string(out, &permissions.strings().to_string());
+
+        #[cfg(unix)]
+        self.write_unix_fields(out, file);
+
+        for (key, time) in &[ ("modified", file.modified_time()), ("changed",  file.changed_time()),
+                              ("accessed", file.accessed_time()), ("created",  file.created_time()) ] {
+            write_key(out, key);
+            match time {
+                Some(t)  => write_string(out, &format_time(*t)),
+                None     => out.push_str("null"),
+            }
+        }
+
+        if self.opts.git {
+            let git = table::git_status(self.git, file);
+            let _ = write!(out, ",\"git\":{{\"staged\":\"{}\",\"unstaged\":\"{}\"}}",
+                           git_status_name(git.staged), git_status_name(git.unstaged));
+        }
+
+        write_key(out, "xattrs");
+        out.push('[');
+        for (index, attr) in xattrs.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            write_string(out, &attr.name);
+            let _ = write!(out, ",\"size\":{}}}", attr.size);
+        }
+        out.push(']');
+
+        if file.is_link() {
+            let target = file.link_target();
+            write_key(out, "target");
+            match target {
+                FileTarget::Ok(ref target)    => write_string(out, &target.path.to_string_lossy()),
+                FileTarget::Broken(ref path)  => write_string(out, &path.to_string_lossy()),
+                FileTarget::Err(_)            => out.push_str("null"),
+            }
+            write_key(out, "broken");
+            out.push_str(if target.is_broken() { "true" } else { "false" });
+        }
+
+        if let Some(r) = self.recurse {
+            if r.descends_into(file, depth.0) {
+                if let Ok(dir) = file.to_dir() {
+                    self.write_children(pool, out, &dir, r, theme, depth)?;
+                }
+            }
+        }
+
+        out.push('}');
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn write_unix_fields(&self, out: &mut String, file: &File<'_>) {
+        use users::{Users, Groups};
+
+        let octal = table::octal_permissions(file).render(ansi_term::Style::default());
+        write_key(out, "octal");
+        write_string(out, &octal.strings().to_string());
+
+        let users = ENVIRONMENT.lock_users();
+        let f::User(uid) = file.user();
+        let _ = write!(out, ",\"user\":{{\"id\":{},\"name\":", uid);
+        match users.get_user_by_uid(uid) {
+            Some(user)  => write_string(out, &user.name().to_string_lossy()),
+            None        => out.push_str("null"),
+        }
+        out.push('}');
+
+        let f::Group(gid) = file.group();
+        let _ = write!(out, ",\"group\":{{\"id\":{},\"name\":", gid);
+        match users.get_group_by_gid(gid) {
+            Some(group)  => write_string(out, &group.name().to_string_lossy()),
+            None         => out.push_str("null"),
+        }
+        out.push('}');
+
+        let f::Inode(inode) = file.inode();
+        let _ = write!(out, ",\"inode\":{},\"links\":{}", inode, file.links().count);
+
+        write_key(out, "blocks");
+        match file.blocks() {
+            f::Blocks::Some(blocks)  => { let _ = write!(out, "{}", blocks); }
+            f::Blocks::None          => out.push_str("null"),
+        }
+    }
+
+    fn write_children(&self, pool: &mut Pool, out: &mut String, dir: &Dir, recurse: &RecurseOptions, theme: &Theme, depth: TreeDepth) -> io::Result<()> {
+        let root = self.dir.map_or(Path::new(""), |d| d.path.as_path());
+        let files = read_children(pool, dir, root, recurse, depth.deeper().0, self.filter, self.ignore, self.cache)?;
+
+        write_key(out, "children");
+        out.push('[');
+        for (index, file) in files.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            self.write_file(pool, out, file, theme, depth.deeper())?;
+        }
+        out.push(']');
+        Ok(())
+    }
+}
+
+
+/// Writes an object key, along with the comma separating it from the
+/// previous field if it’s not the first one.
+fn write_key(out: &mut String, key: &str) {
+    if ! out.ends_with('{') {
+        out.push(',');
+    }
+
+    write_string(out, key);
+    out.push(':');
+}
+
+/// Writes a string as a quoted JSON string, escaping anything that JSON
+/// does not allow to appear literally.
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"'   => out.push_str("\\\""),
+            '\\'  => out.push_str("\\\\"),
+            '\n'  => out.push_str("\\n"),
+            '\r'  => out.push_str("\\r"),
+            '\t'  => out.push_str("\\t"),
+            c if c.is_control() => { let _ = write!(out, "\\u{:04x}", u32::from(c)); }
+            c     => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+fn format_time(time: SystemTime) -> String {
+    match ENVIRONMENT.time_zone() {
+        Some(tz)  => TimeFormat::FullISO.format_zoned(time, tz),
+        None      => TimeFormat::FullISO.format_local(time),
+    }
+}
+
+fn type_name(file_type: f::Type) -> &'static str {
+    match file_type {
+        f::Type::Directory    => "directory",
+        f::Type::File         => "file",
+        f::Type::Link         => "link",
+        f::Type::Pipe         => "pipe",
+        f::Type::Socket       => "socket",
+        f::Type::CharDevice   => "char_device",
+        f::Type::BlockDevice  => "block_device",
+        f::Type::Special      => "special",
+    }
+}
+
+fn git_status_name(status: f::GitStatus) -> &'static str {
+    match status {
+        f::GitStatus::NotModified  => "unmodified",
+        f::GitStatus::New          => "new",
+        f::GitStatus::Modified     => "modified",
+        f::GitStatus::Deleted      => "deleted",
+        f::GitStatus::Renamed      => "renamed",
+        f::GitStatus::TypeChange   => "typechange",
+        f::GitStatus::Ignored      => "ignored",
+        f::GitStatus::Conflicted   => "conflicted",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn json(string: &str) -> String {
+        let mut out = String::new();
+        write_string(&mut out, string);
+        out
+    }
+
+    #[test]
+    fn plain_string() {
+        assert_eq!(json("Cargo.toml"), "\"Cargo.toml\"");
+    }
+
+    #[test]
+    fn quotes_and_backslashes() {
+        assert_eq!(json("a \"b\" \\c"), "\"a \\\"b\\\" \\\\c\"");
+    }
+
+    #[test]
+    fn control_characters() {
+        assert_eq!(json("new\nline\u{1b}"), "\"new\\nline\\u001b\"");
+    }
+
+    #[test]
+    fn keys_get_commas() {
+        let mut out = String::from("{");
+        write_key(&mut out, "a");
+        out.push('1');
+        write_key(&mut out, "b");
+        out.push('2');
+        assert_eq!(out, "{\"a\":1,\"b\":2");
+    }
+}
@@ -1,11 +1,17 @@
 use std::io::{self, Write};
+use std::path::Path;
 
 use ansi_term::ANSIStrings;
+use scoped_threadpool::Pool;
 
-use crate::fs::File;
-use crate::fs::filter::FileFilter;
+use crate::fs::{Dir, File};
+use crate::fs::dir_action::RecurseOptions;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::ignore::IgnoreCache;
 use crate::output::cell::TextCellContents;
 use crate::output::file_name::{Options as FileStyle};
+use crate::output::{read_children, thread_pool};
+use crate::output::tree::TreeDepth;
 use crate::theme::Theme;
 
 
@@ -35,3 +41,79 @@ impl<'a> Render<'a> {
             .paint()
     }
 }
+
+
+/// The NUL-separated view, for scripts, writes each file’s path exactly as
+/// it is, followed by a NUL byte, without any colours, icons, or escaping.
+/// As file names can’t contain NUL bytes, any path can safely be read back
+/// in by something like `xargs -0`.
+pub struct Print0Render<'a> {
+    pub dir: Option<&'a Dir>,
+    pub files: Vec<File<'a>>,
+
+    /// Whether to recurse through directories, and if so, which options to
+    /// use. Both `--recurse` and `--tree` list every file under each
+    /// directory, right after the directory itself.
+    pub recurse: Option<&'a RecurseOptions>,
+
+    /// How to sort and filter the files after getting their details.
+    pub filter: &'a FileFilter,
+
+    /// The ignore rules to skip files with, if we are skipping ignored
+    /// files.
+    pub ignore: Option<&'a IgnoreCache>,
+
+    /// What’s been found out about the directories in this listing so far.
+    pub cache: &'a FilterCache,
+}
+
+impl<'a> Print0Render<'a> {
+    pub fn render<W: Write>(mut self, w: &mut W) -> io::Result<()> {
+        let mut pool = thread_pool();
+
+        self.filter.sort_files(&mut self.files);
+        for file in &self.files {
+            self.render_file(&mut pool, w, file, TreeDepth::root())?;
+        }
+
+        Ok(())
+    }
+
+    fn render_file<W: Write>(&self, pool: &mut Pool, w: &mut W, file: &File<'_>, depth: TreeDepth) -> io::Result<()> {
+        write_path(w, &file.path)?;
+        w.write_all(b"\0")?;
+
+        if let Some(r) = self.recurse {
+            if r.descends_into(file, depth.0) {
+                if let Ok(dir) = file.to_dir() {
+                    self.render_children(pool, w, &dir, r, depth)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_children<W: Write>(&self, pool: &mut Pool, w: &mut W, dir: &Dir, recurse: &RecurseOptions, depth: TreeDepth) -> io::Result<()> {
+        let root = self.dir.map_or(Path::new(""), |d| d.path.as_path());
+        let files = read_children(pool, dir, root, recurse, depth.deeper().0, self.filter, self.ignore, self.cache)?;
+
+        for file in &files {
+            self.render_file(pool, w, file, depth.deeper())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a path’s bytes as they are, even if they aren’t valid UTF-8.
+#[cfg(unix)]
+fn write_path<W: Write>(w: &mut W, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    w.write_all(path.as_os_str().as_bytes())
+}
+
+#[cfg(windows)]
+fn write_path<W: Write>(w: &mut W, path: &Path) -> io::Result<()> {
+    w.write_all(path.to_string_lossy().as_bytes())
+}
@@ -1,18 +1,33 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use scoped_threadpool::Pool;
+
+use crate::fs::{Dir, File};
+use crate::fs::dir_action::RecurseOptions;
+use crate::fs::filter::{FileFilter, FilterCache};
+use crate::fs::ignore::IgnoreCache;
+use crate::fs::total_size::fill_total_sizes;
+
 pub use self::cell::{TextCell, TextCellContents, DisplayWidth};
-pub use self::escape::escape;
+pub use self::escape::{escape, QuotingStyle};
+pub use self::tree::TreeStyle;
 
 pub mod details;
 pub mod file_name;
 pub mod grid;
 pub mod grid_details;
 pub mod icons;
+pub mod json;
 pub mod lines;
 pub mod render;
+pub mod summary;
 pub mod table;
 pub mod time;
 
 mod cell;
 mod escape;
+mod hyperlink;
 mod tree;
 
 
@@ -22,6 +37,9 @@ pub struct View {
     pub mode: Mode,
     pub width: TerminalWidth,
     pub file_style: file_name::Options,
+
+    /// Whether to print a line counting the files after each listing.
+    pub summary: bool,
 }
 
 
@@ -33,6 +51,8 @@ pub enum Mode {
     Details(details::Options),
     GridDetails(grid_details::Options),
     Lines,
+    Json(json::Options),
+    Print0,
 }
 
 
@@ -59,3 +79,36 @@ impl TerminalWidth {
         }
     }
 }
+
+
+/// Makes a pool with a thread for each CPU, for looking up the details of
+/// files and adding up directories in parallel.
+pub fn thread_pool() -> Pool {
+    let n_cpus = match num_cpus::get() as u32 {
+        0 => 1,
+        n => n,
+    };
+
+    Pool::new(n_cpus)
+}
+
+
+/// Reads the files inside a directory that one of the script views is
+/// recursing into, which are at the given depth, printing an error for any
+/// that can’t be read, then filters and sorts them the same way as the
+/// directory’s own listing, adding up totals on the pool.
+#[allow(clippy::too_many_arguments)]
+fn read_children<'dir>(pool: &mut Pool, dir: &'dir Dir, root: &Path, recurse: &RecurseOptions, depth: usize, filter: &FileFilter, ignore: Option<&IgnoreCache>, cache: &FilterCache) -> io::Result<Vec<File<'dir>>> {
+    let mut files = Vec::new();
+    for file in dir.files(filter.dot_filter, ignore) {
+        match file {
+            Ok(file)        => files.push(file),
+            Err((path, e))  => writeln!(io::stderr(), "[{}: {}]", path.display(), e)?,
+        }
+    }
+
+    filter.filter_recursed_files(&mut files, root, recurse, depth, ignore, cache);
+    fill_total_sizes(pool, &mut files, root, filter, ignore, cache);
+    filter.sort_files(&mut files);
+    Ok(files)
+}
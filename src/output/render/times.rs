@@ -4,27 +4,38 @@ use datetime::TimeZone;
 use ansi_term::Style;
 
 use crate::output::cell::TextCell;
-use crate::output::time::TimeFormat;
+use crate::output::time::{Age, AgeUnit, TimeFormat};
 
 
 pub trait Render {
-    fn render(self, style: Style, tz: &Option<TimeZone>, format: TimeFormat) -> TextCell;
+    fn render<C: Colours>(self, colours: &C, tz: &Option<TimeZone>, format: &TimeFormat) -> TextCell;
 }
 
 impl Render for Option<SystemTime> {
-    fn render(self, style: Style, tz: &Option<TimeZone>, format: TimeFormat) -> TextCell {
-        let datestamp = if let Some(time) = self {
-            if let Some(ref tz) = tz {
-                format.format_zoned(time, tz)
-            }
-            else {
-                format.format_local(time)
-            }
+    fn render<C: Colours>(self, colours: &C, tz: &Option<TimeZone>, format: &TimeFormat) -> TextCell {
+        let time = match self {
+            Some(time)  => time,
+            None        => return TextCell::paint(colours.date(), String::from("-")),
+        };
+
+        if let TimeFormat::Relative = format {
+            let age = Age::since(time);
+            return TextCell::paint(colours.age(age.unit), age.to_string());
+        }
+
+        let datestamp = if let Some(ref tz) = tz {
+            format.format_zoned(time, tz)
         }
         else {
-            String::from("-")
+            format.format_local(time)
         };
 
-        TextCell::paint(style, datestamp)
+        TextCell::paint(colours.date(), datestamp)
     }
 }
+
+
+pub trait Colours {
+    fn date(&self) -> Style;
+    fn age(&self, unit: AgeUnit) -> Style;
+}
@@ -0,0 +1,231 @@
+//! The **summary** line that gets printed after a listing with `--summary`,
+//! counting what was listed:
+//!
+//! ```text
+//!     42 files, 7 dirs, 3 symlinks, 1.2 GiB total, 3 git-modified
+//! ```
+//!
+//! A listing covers the files in one directory, or in a whole tree when
+//! `--tree` is given. When there’s more than one listing, a grand total of
+//! all of them gets printed at the end.
+//!
+//! The entries of a directory that get left out with `--max-entries` are
+//! counted here too, for the placeholder that stands in for them:
+//!
+//! ```text
+//!     … 9,812 more files (1.4 GiB)
+//! ```
+
+use std::fmt;
+
+use number_prefix::NumberPrefix;
+
+use crate::fs::File;
+use crate::fs::feature::git::GitCache;
+use crate::fs::fields as f;
+use crate::output::table::git_status;
+
+
+/// The counts and totals of the files in one or more listings.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Summary {
+    pub files: u64,
+    pub dirs: u64,
+    pub symlinks: u64,
+
+    /// The total size of every file that isn’t a directory, in bytes.
+    pub bytes: u64,
+
+    /// How many files have been modified since the last commit, which is
+    /// only counted when Git statuses are being looked up.
+    pub git_modified: Option<u64>,
+}
+
+impl Summary {
+
+    /// Counts the given files, which are the ones at the top of a listing.
+    /// When the listing is a tree, the details view counts the files inside
+    /// each directory as it lists them, so none of them get read again.
+    pub fn of_files(files: &[File<'_>], git: Option<&GitCache>) -> Self {
+        let mut summary = Self {
+            git_modified: git.map(|_| 0),
+            .. Self::default()
+        };
+
+        for file in files {
+            summary.count(file, git);
+        }
+
+        summary
+    }
+
+    /// Adds another listing’s counts to these ones.
+    pub fn add(&mut self, other: &Self) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+        self.symlinks += other.symlinks;
+        self.bytes += other.bytes;
+
+        self.git_modified = match (self.git_modified, other.git_modified) {
+            (None, None)  => None,
+            (a, b)        => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+
+    /// Counts one more file that got listed.
+    pub fn count(&mut self, file: &File<'_>, git: Option<&GitCache>) {
+        if file.is_directory() {
+            self.dirs += 1;
+            return;
+        }
+
+        if file.is_link() {
+            self.symlinks += 1;
+        }
+        else {
+            self.files += 1;
+        }
+
+        if let f::Size::Some(bytes) = file.size() {
+            self.bytes += bytes;
+        }
+
+        if let Some(ref mut modified) = self.git_modified {
+            let status = git_status(git, file);
+            if status.staged == f::GitStatus::Modified || status.unstaged == f::GitStatus::Modified {
+                *modified += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}, {}, {} total",
+               plural(self.files, "file"), plural(self.dirs, "dir"),
+               plural(self.symlinks, "symlink"), Bytes(self.bytes))?;
+
+        if let Some(modified) = self.git_modified {
+            write!(f, ", {} git-modified", modified)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn plural(count: u64, noun: &str) -> String {
+    if count == 1 { format!("1 {}", noun) }
+             else { format!("{} {}s", count, noun) }
+}
+
+/// The entries of a directory that were left out of its listing, and how
+/// much space they take up.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct Remainder {
+    pub count: u64,
+
+    /// The total size of the files, along with the totals of any
+    /// directories when `--total-size` has added them up.
+    pub bytes: u64,
+}
+
+impl Remainder {
+
+    /// Counts the files that aren’t going to be listed.
+    pub fn of_files(files: &[File<'_>]) -> Self {
+        let bytes = files.iter()
+                         .map(|file| match file.size() {
+                             _ if file.is_directory()  => file.total_size.unwrap_or(0),
+                             f::Size::Some(bytes)      => bytes,
+                             _                         => 0,
+                         })
+                         .sum();
+
+        Self { count: files.len() as u64, bytes }
+    }
+
+    /// Describes the files that were left out, writing the number of them
+    /// the way the user’s locale writes numbers.
+    pub fn describe(&self, numeric: &locale::Numeric) -> String {
+        let noun = if self.count == 1 { "file" } else { "files" };
+        format!("… {} more {} ({})", numeric.format_int(self.count), noun, Bytes(self.bytes))
+    }
+}
+
+
+/// A number of bytes, written with a binary prefix.
+struct Bytes(u64);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match NumberPrefix::binary(self.0 as f64) {
+            NumberPrefix::Standalone(_)   => write!(f, "{} bytes", self.0),
+            NumberPrefix::Prefixed(p, n)  => write!(f, "{:.1} {}B", n, p.symbol()),
+        }
+    }
+}
+
+
+/// The total number of blocks taken up by the files in a listing, which
+/// gets printed before it like the `total` line in `ls -ls`.
+#[cfg(unix)]
+pub fn total_blocks(files: &[File<'_>]) -> u64 {
+    files.iter()
+         .map(|file| match file.blocks() {
+             f::Blocks::Some(blocks)  => blocks,
+             f::Blocks::None          => 0,
+         })
+         .sum()
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let summary = Summary { files: 42, dirs: 7, symlinks: 3, bytes: 1_288_490_189, git_modified: Some(3) };
+        assert_eq!(summary.to_string(), "42 files, 7 dirs, 3 symlinks, 1.2 GiB total, 3 git-modified");
+    }
+
+    #[test]
+    fn singular() {
+        let summary = Summary { files: 1, dirs: 1, symlinks: 0, bytes: 12, git_modified: None };
+        assert_eq!(summary.to_string(), "1 file, 1 dir, 0 symlinks, 12 bytes total");
+    }
+
+    #[test]
+    fn remainder() {
+        let remainder = Remainder { count: 9812, bytes: 1_503_238_554 };
+        assert_eq!(remainder.describe(&locale::Numeric::english()), "… 9,812 more files (1.4 GiB)");
+    }
+
+    #[test]
+    fn remainder_of_one() {
+        let remainder = Remainder { count: 1, bytes: 12 };
+        assert_eq!(remainder.describe(&locale::Numeric::english()), "… 1 more file (12 bytes)");
+    }
+
+    #[test]
+    fn grand_total() {
+        let mut total = Summary { files: 1, dirs: 2, symlinks: 3, bytes: 4, git_modified: None };
+        total.add(&Summary { files: 1, dirs: 1, symlinks: 1, bytes: 1, git_modified: Some(2) });
+        assert_eq!(total, Summary { files: 2, dirs: 3, symlinks: 4, bytes: 5, git_modified: Some(2) });
+    }
+
+    #[test]
+    fn counts_only_the_files_given() {
+        use crate::fs::temp_tree::TempTree;
+
+        let tree = TempTree::new("summary-files");
+        tree.file("a", 100).file("sub/b", 20);
+
+        let files = vec![
+            File::from_args(tree.join("a"), None, None).unwrap(),
+            File::from_args(tree.join("sub"), None, None).unwrap(),
+        ];
+
+        assert_eq!(Summary::of_files(&files, None), Summary { files: 1, dirs: 1, symlinks: 0, bytes: 100, git_modified: None });
+    }
+}
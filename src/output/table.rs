@@ -3,6 +3,7 @@ use std::env;
 use std::ops::Deref;
 #[cfg(unix)]
 use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
 
 use datetime::TimeZone;
 use zoneinfo_compiled::{CompiledData, Result as TZResult};
@@ -13,8 +14,9 @@ use log::*;
 use users::UsersCache;
 
 use crate::fs::{File, fields as f};
+pub use crate::fs::fields::TimeType;
 use crate::fs::feature::git::GitCache;
-use crate::output::cell::TextCell;
+use crate::output::cell::{TextCell, truncate_left};
 use crate::output::render::TimeRender;
 use crate::output::time::TimeFormat;
 use crate::theme::Theme;
@@ -27,11 +29,19 @@ pub struct Options {
     pub time_format: TimeFormat,
     pub user_format: UserFormat,
     pub columns: Columns,
+
+    /// Columns that have been given an alignment other than their usual
+    /// one.
+    pub alignments: Vec<(Column, Alignment)>,
+
+    /// The widest the path column can be before the start of each path
+    /// gets cut off.
+    pub max_path_width: Option<usize>,
 }
 
 /// Extra columns to display in the table.
 #[allow(clippy::struct_excessive_bools)]
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Columns {
 
     /// At least one of these timestamps will be shown.
@@ -51,81 +61,201 @@ pub struct Columns {
     pub user: bool,
 
     pub path: bool,
+
+    /// The columns to show and their order, file name included, when
+    /// they’ve been picked with a `--columns` spec. This overrides all the
+    /// fields above.
+    pub order: Option<Vec<ColumnName>>,
 }
 
 impl Columns {
-    pub fn collect(&self, actually_enable_git: bool) -> Vec<Column> {
-        let mut columns = Vec::with_capacity(4);
+
+    /// Returns the columns to show, in order, along with how many of them
+    /// come before the file name.
+    pub fn collect(&self, actually_enable_git: bool) -> (Vec<Column>, usize) {
+        let order = match self.order {
+            Some(ref order)  => order.clone(),
+            None             => self.default_order(),
+        };
+
+        let mut columns = Vec::with_capacity(order.len());
+        let mut name_position = None;
+
+        for name in order {
+            match name {
+                ColumnName::Name       => name_position = Some(columns.len()),
+                ColumnName::GitStatus  => if actually_enable_git { columns.push(Column::GitStatus) },
+                other                  => columns.extend(other.column()),
+            }
+        }
+
+        let name_position = name_position.unwrap_or(columns.len());
+        (columns, name_position)
+    }
+
+    /// Whether a Git column is going to be shown, if there’s a repository.
+    pub fn shows_git(&self) -> bool {
+        match self.order {
+            Some(ref order)  => order.contains(&ColumnName::GitStatus),
+            None             => self.git,
+        }
+    }
+
+    /// Whether the blocks column is going to be shown.
+    pub fn shows_blocks(&self) -> bool {
+        match self.order {
+            Some(ref order)  => order.contains(&ColumnName::Blocks),
+            None             => self.blocks,
+        }
+    }
+
+    /// The order of the columns picked with the individual flags, which
+    /// always put the file name last.
+    fn default_order(&self) -> Vec<ColumnName> {
+        let mut order = Vec::with_capacity(4);
 
         if self.path {
-            columns.push(Column::Path);
+            order.push(ColumnName::Path);
         }
 
         if self.inode {
-            #[cfg(unix)]
-            columns.push(Column::Inode);
+            order.push(ColumnName::Inode);
         }
 
         if self.octal {
-            #[cfg(unix)]
-            columns.push(Column::Octal);
+            order.push(ColumnName::Octal);
         }
 
         if self.permissions {
-            columns.push(Column::Permissions);
+            order.push(ColumnName::Permissions);
         }
 
         if self.links {
-            #[cfg(unix)]
-            columns.push(Column::HardLinks);
+            order.push(ColumnName::HardLinks);
         }
 
         if self.filesize {
-            columns.push(Column::FileSize);
+            order.push(ColumnName::FileSize);
         }
 
         if self.blocks {
-            #[cfg(unix)]
-            columns.push(Column::Blocks);
+            order.push(ColumnName::Blocks);
         }
 
         if self.user {
-            #[cfg(unix)]
-            columns.push(Column::User);
+            order.push(ColumnName::User);
         }
 
         if self.group {
-            #[cfg(unix)]
-            columns.push(Column::Group);
+            order.push(ColumnName::Group);
         }
 
         if self.time_types.modified {
-            columns.push(Column::Timestamp(TimeType::Modified));
+            order.push(ColumnName::Timestamp(TimeType::Modified));
         }
 
         if self.time_types.changed {
-            columns.push(Column::Timestamp(TimeType::Changed));
+            order.push(ColumnName::Timestamp(TimeType::Changed));
         }
 
         if self.time_types.created {
-            columns.push(Column::Timestamp(TimeType::Created));
+            order.push(ColumnName::Timestamp(TimeType::Created));
         }
 
         if self.time_types.accessed {
-            columns.push(Column::Timestamp(TimeType::Accessed));
+            order.push(ColumnName::Timestamp(TimeType::Accessed));
         }
 
-        if self.git && actually_enable_git {
-            columns.push(Column::GitStatus);
+        if self.git {
+            order.push(ColumnName::GitStatus);
         }
 
-        columns
+        order.push(ColumnName::Name);
+        order
+    }
+}
+
+
+/// A column that can be named in a `--columns` spec. As well as the table
+/// columns, this includes the file name, and columns that can only be
+/// shown on some platforms.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ColumnName {
+    Path,
+    Inode,
+    Octal,
+    Permissions,
+    HardLinks,
+    FileSize,
+    Blocks,
+    User,
+    Group,
+    Timestamp(TimeType),
+    GitStatus,
+    Name,
+}
+
+impl ColumnName {
+
+    /// The names that can be used for columns in a `--columns` spec.
+    pub const ALL: &'static [&'static str] = &[
+        "path", "inode", "octal", "perm", "links", "size", "blocks", "user", "group",
+        "mtime", "ctime", "btime", "atime", "git", "name",
+    ];
+
+    /// Looks up a column by the name used for it in a `--columns` spec.
+    /// The timestamps can be called by their full names, too.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "path"                 => Self::Path,
+            "inode"                => Self::Inode,
+            "octal"                => Self::Octal,
+            "perm" | "permissions" => Self::Permissions,
+            "links"                => Self::HardLinks,
+            "size"                 => Self::FileSize,
+            "blocks"               => Self::Blocks,
+            "user"                 => Self::User,
+            "group"                => Self::Group,
+            "mtime" | "modified"   => Self::Timestamp(TimeType::Modified),
+            "ctime" | "changed"    => Self::Timestamp(TimeType::Changed),
+            "btime" | "created"    => Self::Timestamp(TimeType::Created),
+            "atime" | "accessed"   => Self::Timestamp(TimeType::Accessed),
+            "git"                  => Self::GitStatus,
+            "name"                 => Self::Name,
+            _                      => return None,
+        })
+    }
+
+    /// The table column for this name, if there is one on this platform.
+    pub fn column(self) -> Option<Column> {
+        match self {
+            Self::Path          => Some(Column::Path),
+            Self::Permissions   => Some(Column::Permissions),
+            Self::FileSize      => Some(Column::FileSize),
+            Self::Timestamp(t)  => Some(Column::Timestamp(t)),
+            Self::GitStatus     => Some(Column::GitStatus),
+            Self::Name          => None,
+            #[cfg(unix)]
+            Self::Inode         => Some(Column::Inode),
+            #[cfg(unix)]
+            Self::Octal         => Some(Column::Octal),
+            #[cfg(unix)]
+            Self::HardLinks     => Some(Column::HardLinks),
+            #[cfg(unix)]
+            Self::Blocks        => Some(Column::Blocks),
+            #[cfg(unix)]
+            Self::User          => Some(Column::User),
+            #[cfg(unix)]
+            Self::Group         => Some(Column::Group),
+            #[cfg(windows)]
+            _                   => None,
+        }
     }
 }
 
 
 /// A table contains these.
-#[derive(Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum Column {
     Path,
     Permissions,
@@ -148,7 +278,7 @@ pub enum Column {
 
 /// Each column can pick its own **Alignment**. Usually, numbers are
 /// right-aligned, and text is left-aligned.
-#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum Alignment {
     Left,
     Right,
@@ -242,38 +372,6 @@ impl Default for SizeFormat {
 }
 
 
-/// The types of a file’s time fields. These three fields are standard
-/// across most (all?) operating systems.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub enum TimeType {
-
-    /// The file’s modified time (`st_mtime`).
-    Modified,
-
-    /// The file’s changed time (`st_ctime`)
-    Changed,
-
-    /// The file’s accessed time (`st_atime`).
-    Accessed,
-
-    /// The file’s creation time (`btime` or `birthtime`).
-    Created,
-}
-
-impl TimeType {
-
-    /// Returns the text to use for a column’s heading in the columns output.
-    pub fn header(self) -> &'static str {
-        match self {
-            Self::Modified  => "Date Modified",
-            Self::Changed   => "Date Changed",
-            Self::Accessed  => "Date Accessed",
-            Self::Created   => "Date Created",
-        }
-    }
-}
-
-
 /// Fields for which of a file’s time fields should be displayed in the
 /// columns output.
 ///
@@ -327,6 +425,14 @@ impl Environment {
         self.users.lock().unwrap()
     }
 
+    pub fn time_zone(&self) -> Option<&TimeZone> {
+        self.tz.as_ref()
+    }
+
+    pub fn numeric(&self) -> &locale::Numeric {
+        &self.numeric
+    }
+
     fn load_all() -> Self {
         let tz = match determine_time_zone() {
             Ok(t) => {
@@ -395,12 +501,16 @@ fn determine_time_zone() -> TZResult<TimeZone> {
 }
 
 lazy_static! {
-    static ref ENVIRONMENT: Environment = Environment::load_all();
+    pub static ref ENVIRONMENT: Environment = Environment::load_all();
 }
 
 
 pub struct Table<'a> {
     columns: Vec<Column>,
+
+    /// How many of the columns come before the file name.
+    name_position: usize,
+
     theme: &'a Theme,
     env: &'a Environment,
     widths: TableWidths,
@@ -408,16 +518,73 @@ pub struct Table<'a> {
     size_format: SizeFormat,
     user_format: UserFormat,
     git: Option<&'a GitCache>,
+    alignments: &'a [(Column, Alignment)],
+    max_path_width: Option<usize>,
+
+    /// The ranges of the values that get coloured on a scale, which have to
+    /// be known before any row can be rendered.
+    ranges: ScaleRanges,
+
+    /// The group of files that the sizes of new rows get compared with.
+    size_group: usize,
 }
 
 #[derive(Clone)]
 pub struct Row {
     cells: Vec<TextCell>,
+
+    /// The timestamp behind each cell in a timestamp column, only kept
+    /// when they need to get shaded once every row is known.
+    times: Vec<Option<SystemTime>>,
+
+    /// The file’s size, along with the group of files it gets compared
+    /// with, only kept when sizes get coloured relative to one another.
+    size: Option<(usize, u64)>,
+}
+
+/// The smallest and largest values in a table that get coloured on a
+/// scale: the oldest and newest timestamps in each timestamp column, and
+/// the smallest and largest sizes in each group of files.
+#[derive(Clone, Default)]
+pub struct ScaleRanges {
+    times: Vec<Option<(SystemTime, SystemTime)>>,
+    sizes: Vec<Option<(u64, u64)>>,
+}
+
+impl ScaleRanges {
+
+    /// Widens the ranges to fit the values in the given row.
+    pub fn add(&mut self, row: &Row) {
+        if self.times.len() < row.times.len() {
+            self.times.resize(row.times.len(), None);
+        }
+
+        for (range, time) in self.times.iter_mut().zip(&row.times) {
+            if let Some(time) = *time {
+                *range = Some(widen(*range, time));
+            }
+        }
+
+        if let Some((group, size)) = row.size {
+            if self.sizes.len() <= group {
+                self.sizes.resize(group + 1, None);
+            }
+
+            self.sizes[group] = Some(widen(self.sizes[group], size));
+        }
+    }
+}
+
+fn widen<T: Ord + Copy>(range: Option<(T, T)>, value: T) -> (T, T) {
+    match range {
+        Some((min, max))  => (min.min(value), max.max(value)),
+        None              => (value, value),
+    }
 }
 
 impl<'a, 'f> Table<'a> {
     pub fn new(options: &'a Options, git: Option<&'a GitCache>, theme: &'a Theme) -> Table<'a> {
-        let columns = options.columns.collect(git.is_some());
+        let (columns, name_position) = options.columns.collect(git.is_some());
         let widths = TableWidths::zero(columns.len());
         let env = &*ENVIRONMENT;
 
@@ -425,16 +592,32 @@ impl<'a, 'f> Table<'a> {
             theme,
             widths,
             columns,
+            name_position,
             git,
             env,
-            time_format: options.time_format,
+            time_format: options.time_format.clone(),
             size_format: options.size_format,
             user_format: options.user_format,
+            alignments: &options.alignments,
+            max_path_width: options.max_path_width,
+            ranges: ScaleRanges::default(),
+            size_group: 0,
         }
     }
 
-    pub fn widths(&self) -> &TableWidths {
-        &self.widths
+    /// How wide the cells before the file name are in total, including
+    /// the space after each of them.
+    pub fn leading_width(&self) -> usize {
+        self.widths.iter()
+            .take(self.name_position)
+            .map(|w| w + 1)
+            .sum()
+    }
+
+    /// Whether any columns get rendered after the file name, which means
+    /// the names have to be padded to line them up.
+    pub fn has_trailing_columns(&self) -> bool {
+        self.name_position < self.columns.len()
     }
 
     pub fn header_row(&self) -> Row {
@@ -442,7 +625,7 @@ impl<'a, 'f> Table<'a> {
                         .map(|c| TextCell::paint_str(self.theme.ui.header, c.header()))
                         .collect();
 
-        Row { cells }
+        Row { cells, times: Vec::new(), size: None }
     }
 
     pub fn row_for_file(&self, file: &File<'_>, xattrs: bool) -> Row {
@@ -450,42 +633,68 @@ impl<'a, 'f> Table<'a> {
                         .map(|c| self.display(file, *c, xattrs))
                         .collect();
 
-        Row { cells }
+        let times = if self.theme.scale.age {
+            self.columns.iter()
+                .map(|c| match c {
+                    Column::Timestamp(t)  => t.of_file(file),
+                    _                     => None,
+                })
+                .collect()
+        }
+        else {
+            Vec::new()
+        };
+
+        let size = match file.size() {
+            f::Size::Some(size) if self.theme.scale.relative_sizes()  => Some((self.size_group, size)),
+            _                                                         => None,
+        };
+
+        Row { cells, times, size }
     }
 
+    /// Takes a row into account before the table gets rendered, widening
+    /// the columns to fit its cells and widening the ranges of its values
+    /// that get coloured on a scale.
     pub fn add_widths(&mut self, row: &Row) {
-        self.widths.add_widths(row)
+        self.widths.add_widths(row);
+        self.ranges.add(row);
     }
 
-    fn permissions_plus(&self, file: &File<'_>, xattrs: bool) -> f::PermissionsPlus {
-        f::PermissionsPlus {
-            file_type: file.type_char(),
-            #[cfg(unix)]
-            permissions: file.permissions(),
-            #[cfg(windows)]
-            attributes: file.attributes(),
-            xattrs,
-        }
+    /// Starts a new group of files whose sizes get compared with one
+    /// another, such as the contents of the next directory in a tree.
+    pub fn start_size_group(&mut self) {
+        self.size_group += 1;
     }
 
-    #[cfg(unix)]
-    fn octal_permissions(&self, file: &File<'_>) -> f::OctalPermissions {
-        f::OctalPermissions {
-            permissions: file.permissions(),
-        }
+    /// Replaces the ranges of the values that get coloured on a scale, so
+    /// that tables showing parts of the same listing can share them.
+    pub fn set_ranges(&mut self, ranges: ScaleRanges) {
+        self.ranges = ranges;
+    }
+
+    /// Get the alignment a column should use, which is usually the one that
+    /// suits its values, unless it’s been overridden.
+    fn alignment(&self, column: Column) -> Alignment {
+        self.alignments.iter()
+            .find(|(c, _)| *c == column)
+            .map_or_else(|| column.alignment(), |(_, a)| *a)
     }
 
     fn display(&self, file: &File<'_>, column: Column, xattrs: bool) -> TextCell {
         match column {
             Column::Path => {
                 use ansi_term::Style;
-                TextCell::paint(
-                    Style::default(), 
-                    file.path.display().to_string() + "!"
-                )
+                let path = file.path.display().to_string() + "!";
+                let path = match self.max_path_width {
+                    Some(width)  => truncate_left(&path, width).into_owned(),
+                    None         => path,
+                };
+
+                TextCell::paint(Style::default(), path)
             }
             Column::Permissions => {
-                self.permissions_plus(file, xattrs).render(self.theme)
+                permissions_plus(file, xattrs).render(self.theme)
             }
             Column::FileSize => {
                 file.size().render(self.theme, self.size_format, &self.env.numeric)
@@ -511,50 +720,66 @@ impl<'a, 'f> Table<'a> {
                 file.group().render(self.theme, &*self.env.lock_users(), self.user_format)
             }
             Column::GitStatus => {
-                self.git_status(file).render(self.theme)
+                git_status(self.git, file).render(self.theme)
             }
             #[cfg(unix)]
             Column::Octal => {
-                self.octal_permissions(file).render(self.theme.ui.octal)
+                octal_permissions(file).render(self.theme.ui.octal)
             }
 
             Column::Timestamp(TimeType::Modified)  => {
-                file.modified_time().render(self.theme.ui.date, &self.env.tz, self.time_format)
+                file.modified_time().render(self.theme, &self.env.tz, &self.time_format)
             }
             Column::Timestamp(TimeType::Changed)   => {
-                file.changed_time().render(self.theme.ui.date, &self.env.tz, self.time_format)
+                file.changed_time().render(self.theme, &self.env.tz, &self.time_format)
             }
             Column::Timestamp(TimeType::Created)   => {
-                file.created_time().render(self.theme.ui.date, &self.env.tz, self.time_format)
+                file.created_time().render(self.theme, &self.env.tz, &self.time_format)
             }
             Column::Timestamp(TimeType::Accessed)  => {
-                file.accessed_time().render(self.theme.ui.date, &self.env.tz, self.time_format)
+                file.accessed_time().render(self.theme, &self.env.tz, &self.time_format)
             }
         }
     }
 
-    fn git_status(&self, file: &File<'_>) -> f::Git {
-        debug!("Getting Git status for file {:?}", file.path);
-
-        self.git
-            .map(|g| g.get(&file.path, file.is_directory()))
-            .unwrap_or_default()
-    }
-
-    pub fn render(&self, row: Row) -> TextCell {
-        let mut cell = TextCell::default();
+    /// Renders a row as the cells that go before the file name and the
+    /// cells that go after it, the second of which is empty unless the
+    /// name was moved away from the end.
+    pub fn render(&self, row: Row) -> (TextCell, TextCell) {
+        let mut before = TextCell::default();
+        let mut after = TextCell::default();
+        let last = self.columns.len().saturating_sub(1);
 
         let iter = row.cells.into_iter()
                       .zip(self.widths.iter())
                       .enumerate();
 
-        for (n, (this_cell, width)) in iter {
+        for (n, (mut this_cell, width)) in iter {
             let padding = width - *this_cell.width;
 
-            match self.columns[n].alignment() {
+            if let (Some(Some(time)), Some(Some((oldest, newest)))) = (row.times.get(n), self.ranges.times.get(n)) {
+                this_cell.restyle(self.theme.age_shade(age_fraction(*time, *oldest, *newest)));
+            }
+
+            if let (Column::FileSize, Some((group, size))) = (self.columns[n], row.size) {
+                if let Some(Some((smallest, largest))) = self.ranges.sizes.get(group) {
+                    this_cell.restyle(self.theme.size_shade(size_fraction(size, *smallest, *largest)));
+                }
+            }
+
+            let cell = if n < self.name_position { &mut before } else { &mut after };
+            if n >= self.name_position {
+                cell.add_spaces(1);
+            }
+
+            match self.alignment(self.columns[n]) {
                 Alignment::Left => {
                     cell.append(this_cell);
-                    cell.add_spaces(padding);
+
+                    // Don’t leave trailing spaces at the end of the line.
+                    if n < self.name_position || n < last {
+                        cell.add_spaces(padding);
+                    }
                 }
                 Alignment::Right => {
                     cell.add_spaces(padding);
@@ -562,11 +787,66 @@ impl<'a, 'f> Table<'a> {
                 }
             }
 
-            cell.add_spaces(1);
+            if n < self.name_position {
+                cell.add_spaces(1);
+            }
         }
 
-        cell
+        (before, after)
+    }
+}
+
+
+/// How far along a timestamp is from the oldest to the newest one, from
+/// 0.0 to 1.0. When they’re all the same, they all count as the newest.
+fn age_fraction(time: SystemTime, oldest: SystemTime, newest: SystemTime) -> f64 {
+    if newest <= oldest {
+        return 1.0;
     }
+
+    let span = newest.duration_since(oldest).unwrap_or_default();
+    let along = time.duration_since(oldest).unwrap_or_default();
+    along.as_secs_f64() / span.as_secs_f64()
+}
+
+/// How far along a size is from the smallest to the largest one, from 0.0
+/// to 1.0. When they’re all the same, none of them stand out.
+fn size_fraction(size: u64, smallest: u64, largest: u64) -> f64 {
+    if largest <= smallest {
+        return 0.0;
+    }
+
+    (size - smallest) as f64 / (largest - smallest) as f64
+}
+
+
+/// The permissions column’s data: the file type, its permission bits, and
+/// whether it has any extended attributes.
+pub fn permissions_plus(file: &File<'_>, xattrs: bool) -> f::PermissionsPlus {
+    f::PermissionsPlus {
+        file_type: file.type_char(),
+        #[cfg(unix)]
+        permissions: file.permissions(),
+        #[cfg(windows)]
+        attributes: file.attributes(),
+        xattrs,
+    }
+}
+
+#[cfg(unix)]
+pub fn octal_permissions(file: &File<'_>) -> f::OctalPermissions {
+    f::OctalPermissions {
+        permissions: file.permissions(),
+    }
+}
+
+/// Looks up a file’s Git status in the cache, if there is one. Files
+/// outside of any repository are treated as unmodified.
+pub fn git_status(git: Option<&GitCache>, file: &File<'_>) -> f::Git {
+    debug!("Getting Git status for file {:?}", file.path);
+
+    git.map(|g| g.get(&file.path, file.is_directory()))
+       .unwrap_or_default()
 }
 
 
@@ -590,8 +870,4 @@ impl TableWidths {
             *old_width = max(*old_width, *cell.width);
         }
     }
-
-    pub fn total(&self) -> usize {
-        self.0.len() + self.0.iter().sum::<usize>()
-    }
 }
@@ -1,5 +1,6 @@
 //! Timestamp formatting.
 
+use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use datetime::{LocalDateTime, TimeZone, DatePiece, TimePiece};
@@ -23,9 +24,9 @@ use unicode_width::UnicodeWidthStr;
 /// own enum variants. It’s not worth looking the locale up if the formatter
 /// prints month names as numbers.
 ///
-/// Currently exa does not support *custom* styles, where the user enters a
-/// format string in an environment variable or something. Just these four.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// As well as these, the user can give a *custom* style as a strftime-style
+/// format string, the same way as GNU `ls`.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum TimeFormat {
 
     /// The **default format** uses the user’s locale to print month names,
@@ -46,27 +47,60 @@ pub enum TimeFormat {
     /// millisecond and includes its offset down to the minute. This too uses
     /// only numbers so doesn’t require any special consideration.
     FullISO,
+
+    /// Use the **relative format**, which prints how long ago the timestamp
+    /// was in one compact unit, such as `5m` or `2d`, instead of a date.
+    Relative,
+
+    /// Use a **custom format** that the user passed in as a strftime-style
+    /// format string. Like the default format, older times can be given a
+    /// different format from recent ones.
+    Custom {
+
+        /// The format string to use for recent times.
+        recent: String,
+
+        /// The format string to use for older times, if there is one
+        /// separate from the recent format.
+        old: Option<String>,
+    },
 }
 
 // There are two different formatting functions because local and zoned
 // timestamps are separate types.
 
 impl TimeFormat {
-    pub fn format_local(self, time: SystemTime) -> String {
+
+    /// Creates a custom format from the text of a `+FORMAT` time style,
+    /// without the plus. If there’s a newline in it, the format before the
+    /// newline gets used for recent times, and the one after it for older
+    /// times.
+    pub fn custom(format: &str) -> Self {
+        match format.split_once('\n') {
+            Some((recent, old))  => Self::Custom { recent: recent.into(), old: Some(old.into()) },
+            None                 => Self::Custom { recent: format.into(), old: None },
+        }
+    }
+
+    pub fn format_local(&self, time: SystemTime) -> String {
         match self {
-            Self::DefaultFormat  => default_local(time),
-            Self::ISOFormat      => iso_local(time),
-            Self::LongISO        => long_local(time),
-            Self::FullISO        => full_local(time),
+            Self::DefaultFormat         => default_local(time),
+            Self::ISOFormat             => iso_local(time),
+            Self::LongISO               => long_local(time),
+            Self::FullISO               => full_local(time),
+            Self::Relative              => Age::since(time).to_string(),
+            Self::Custom { recent, old }  => custom_local(time, recent, old.as_deref()),
         }
     }
 
-    pub fn format_zoned(self, time: SystemTime, zone: &TimeZone) -> String {
+    pub fn format_zoned(&self, time: SystemTime, zone: &TimeZone) -> String {
         match self {
-            Self::DefaultFormat  => default_zoned(time, zone),
-            Self::ISOFormat      => iso_zoned(time, zone),
-            Self::LongISO        => long_zoned(time, zone),
-            Self::FullISO        => full_zoned(time, zone),
+            Self::DefaultFormat         => default_zoned(time, zone),
+            Self::ISOFormat             => iso_zoned(time, zone),
+            Self::LongISO               => long_zoned(time, zone),
+            Self::FullISO               => full_zoned(time, zone),
+            Self::Relative              => Age::since(time).to_string(),
+            Self::Custom { recent, old }  => custom_zoned(time, zone, recent, old.as_deref()),
         }
     }
 }
@@ -164,6 +198,168 @@ fn iso_zoned(time: SystemTime, zone: &TimeZone) -> String {
     }
 }
 
+fn custom_local(time: SystemTime, recent: &str, old: Option<&str>) -> String {
+    let date = LocalDateTime::at(systemtime_epoch(time));
+    let format = if is_recent(&date) { recent } else { old.unwrap_or(recent) };
+    strftime(format, &date, time, 0, "UTC")
+}
+
+fn custom_zoned(time: SystemTime, zone: &TimeZone, recent: &str, old: Option<&str>) -> String {
+    let local = LocalDateTime::at(systemtime_epoch(time));
+    let date = zone.to_zoned(local);
+    let format = if is_recent(&date) { recent } else { old.unwrap_or(recent) };
+    strftime(format, &date, time, zone.offset(local), &zone.name(local))
+}
+
+/// Formats a date using a strftime-style format string, with the offset
+/// and name of its time zone. The `%N` conversion takes a width for how
+/// many digits of the fraction of a second to print, as in `%3N`.
+/// Conversions that aren’t recognised get printed as they are, like GNU
+/// `ls` does.
+fn strftime(format: &str, date: &LocalDateTime, time: SystemTime, offset: i64, zone_name: &str) -> String {
+    let month = date.month() as usize;
+    let weekday = date.weekday() as usize;
+    let hour_12 = match date.hour() % 12 { 0 => 12, h => h };
+
+    let mut out = String::with_capacity(format.len() * 2);
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        let mut width = None;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = Some(width.unwrap_or(0) * 10 + digit as usize);
+            chars.next();
+        }
+
+        let spec = if let Some(spec) = chars.next() { spec } else {
+            out.push('%');
+            break;
+        };
+
+        let piece = match spec {
+            'Y'        => date.year().to_string(),
+            'C'        => format!("{:02}", date.year().div_euclid(100)),
+            'y'        => format!("{:02}", date.year().rem_euclid(100)),
+            'm'        => format!("{:02}", month),
+            'b' | 'h'  => LOCALE.short_month_name(month - 1).clone(),
+            'B'        => LOCALE.long_month_name(month - 1).clone(),
+            'd'        => format!("{:02}", date.day()),
+            'e'        => format!("{:>2}", date.day()),
+            'j'        => format!("{:03}", date.yearday()),
+            'a'        => LOCALE.short_day_name(weekday).clone(),
+            'A'        => LOCALE.long_day_name(weekday).clone(),
+            'u'        => (if weekday == 0 { 7 } else { weekday }).to_string(),
+            'w'        => weekday.to_string(),
+            'H'        => format!("{:02}", date.hour()),
+            'k'        => format!("{:>2}", date.hour()),
+            'I'        => format!("{:02}", hour_12),
+            'l'        => format!("{:>2}", hour_12),
+            'p'        => String::from(if date.hour() < 12 { "AM" } else { "PM" }),
+            'P'        => String::from(if date.hour() < 12 { "am" } else { "pm" }),
+            'M'        => format!("{:02}", date.minute()),
+            'S'        => format!("{:02}", date.second()),
+            'N'        => {
+                let nanos = format!("{:09}", systemtime_nanos(time));
+                nanos[.. width.unwrap_or(9).clamp(1, 9)].to_string()
+            }
+            's'        => systemtime_epoch(time).to_string(),
+            'z'        => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                format!("{}{:02}{:02}", sign, offset.abs() / 3600, offset.abs() % 3600 / 60)
+            }
+            'Z'        => zone_name.to_string(),
+            'F'        => format!("{:04}-{:02}-{:02}", date.year(), month, date.day()),
+            'T'        => format!("{:02}:{:02}:{:02}", date.hour(), date.minute(), date.second()),
+            'R'        => format!("{:02}:{:02}", date.hour(), date.minute()),
+            'D'        => format!("{:02}/{:02}/{:02}", month, date.day(), date.year().rem_euclid(100)),
+            'n'        => String::from("\n"),
+            't'        => String::from("\t"),
+            '%'        => String::from("%"),
+            other      => match width {
+                Some(w)  => format!("%{}{}", w, other),
+                None     => format!("%{}", other),
+            },
+        };
+
+        out.push_str(&piece);
+    }
+
+    out
+}
+
+
+/// The **age** of a timestamp is how long ago it was, counted in the
+/// largest unit that fits into it at least once. Timestamps in the future
+/// get a negative count.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Age {
+    pub count: i64,
+    pub unit: AgeUnit,
+}
+
+/// The units used for ages, each of which gets its own colour.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum AgeUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Months,
+    Years,
+}
+
+impl Age {
+
+    /// Works out how long ago the timestamp was, from the time exa started
+    /// running, so every file in a listing gets measured from the same
+    /// point.
+    pub fn since(time: SystemTime) -> Self {
+        Self::between(time, *NOW)
+    }
+
+    /// Works out how long before `now` the timestamp was. Months count as
+    /// thirty days, and years as 365.
+    pub fn between(time: SystemTime, now: SystemTime) -> Self {
+        const MINUTE: i64 = 60;
+        const HOUR: i64 = 60 * MINUTE;
+        const DAY: i64 = 24 * HOUR;
+        const MONTH: i64 = 30 * DAY;
+        const YEAR: i64 = 365 * DAY;
+
+        let secs = systemtime_epoch(now) - systemtime_epoch(time);
+        let (size, unit) = match secs.abs() {
+            s if s < MINUTE  => (1,      AgeUnit::Seconds),
+            s if s < HOUR    => (MINUTE, AgeUnit::Minutes),
+            s if s < DAY     => (HOUR,   AgeUnit::Hours),
+            s if s < MONTH   => (DAY,    AgeUnit::Days),
+            s if s < YEAR    => (MONTH,  AgeUnit::Months),
+            _                => (YEAR,   AgeUnit::Years),
+        };
+
+        Self { count: secs / size, unit }
+    }
+}
+
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = match self.unit {
+            AgeUnit::Seconds  => "s",
+            AgeUnit::Minutes  => "m",
+            AgeUnit::Hours    => "h",
+            AgeUnit::Days     => "d",
+            AgeUnit::Months   => "mo",
+            AgeUnit::Years    => "y",
+        };
+
+        write!(f, "{}{}", self.count, suffix)
+    }
+}
+
 
 fn systemtime_epoch(time: SystemTime) -> i64 {
     time.duration_since(UNIX_EPOCH)
@@ -198,6 +394,8 @@ fn is_recent(date: &LocalDateTime) -> bool {
 
 lazy_static! {
 
+    static ref NOW: SystemTime = SystemTime::now();
+
     static ref CURRENT_YEAR: i64 = LocalDateTime::now().year();
 
     static ref LOCALE: locale::Time = {
@@ -241,3 +439,89 @@ lazy_static! {
         "{2>:D} {:M} {5>:Y}"
     ).unwrap();
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64, nanos: u32) -> SystemTime {
+        UNIX_EPOCH + Duration::new(secs, nanos)
+    }
+
+    fn format(format: &str, secs: u64, nanos: u32) -> String {
+        let time = at(secs, nanos);
+        let date = LocalDateTime::at(systemtime_epoch(time));
+        strftime(format, &date, time, 0, "UTC")
+    }
+
+    #[test]
+    fn numbers() {
+        // 2009-02-13 23:31:30 UTC
+        assert_eq!(format("%Y-%m-%d %H:%M:%S", 1_234_567_890, 0), "2009-02-13 23:31:30");
+    }
+
+    #[test]
+    fn shorthands() {
+        assert_eq!(format("%F %T", 1_234_567_890, 0), "2009-02-13 23:31:30");
+        assert_eq!(format("%D %R", 1_234_567_890, 0), "02/13/09 23:31");
+    }
+
+    #[test]
+    fn twelve_hours() {
+        assert_eq!(format("%I:%M %p", 1_234_567_890, 0), "11:31 PM");
+    }
+
+    #[test]
+    fn nanoseconds() {
+        assert_eq!(format("%S.%N", 1_234_567_890, 123_456_789), "30.123456789");
+        assert_eq!(format("%S.%3N", 1_234_567_890, 123_456_789), "30.123");
+    }
+
+    #[test]
+    fn epoch_and_zone() {
+        assert_eq!(format("%s %z %Z", 1_234_567_890, 0), "1234567890 +0000 UTC");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(format("100%% %q %", 0, 0), "100% %q %");
+    }
+
+    fn age(secs_ago: u64) -> String {
+        let now = at(1_234_567_890, 0);
+        Age::between(now - Duration::from_secs(secs_ago), now).to_string()
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(age(0), "0s");
+        assert_eq!(age(12), "12s");
+        assert_eq!(age(5 * 60 + 59), "5m");
+        assert_eq!(age(3 * 60 * 60), "3h");
+        assert_eq!(age(2 * 24 * 60 * 60), "2d");
+        assert_eq!(age(125 * 24 * 60 * 60), "4mo");
+        assert_eq!(age(400 * 24 * 60 * 60), "1y");
+    }
+
+    #[test]
+    fn future_age() {
+        let now = at(1_234_567_890, 0);
+        let age = Age::between(now + Duration::from_secs(90), now);
+        assert_eq!(age, Age { count: -1, unit: AgeUnit::Minutes });
+        assert_eq!(age.to_string(), "-1m");
+    }
+
+    #[test]
+    fn split_on_newline() {
+        let expected = TimeFormat::Custom { recent: "%b %d %H:%M".into(), old: Some("%b %d  %Y".into()) };
+        assert_eq!(TimeFormat::custom("%b %d %H:%M\n%b %d  %Y"), expected);
+    }
+
+    #[test]
+    fn no_newline() {
+        let expected = TimeFormat::Custom { recent: "%H:%M".into(), old: None };
+        assert_eq!(TimeFormat::custom("%H:%M"), expected);
+    }
+}
@@ -57,19 +57,55 @@ pub enum TreePart {
 
 impl TreePart {
 
-    /// Turn this tree part into ASCII-licious box drawing characters!
-    /// (Warning: not actually ASCII)
-    pub fn ascii_art(self) -> &'static str {
-        match self {
-            Self::Edge    => "├──",
-            Self::Line    => "│  ",
-            Self::Corner  => "└──",
-            Self::Blank   => "   ",
+    /// Turn this tree part into the characters that draw it in the given
+    /// style. Every part is three characters wide, whichever style is used.
+    pub fn ascii_art(self, style: TreeStyle) -> &'static str {
+        match (style, self) {
+            (TreeStyle::Unicode, Self::Edge)    => "├──",
+            (TreeStyle::Unicode, Self::Line)    => "│  ",
+            (TreeStyle::Unicode, Self::Corner)  => "└──",
+
+            (TreeStyle::Rounded, Self::Edge)    => "├──",
+            (TreeStyle::Rounded, Self::Line)    => "│  ",
+            (TreeStyle::Rounded, Self::Corner)  => "╰──",
+
+            (TreeStyle::Ascii,   Self::Edge)    => "|--",
+            (TreeStyle::Ascii,   Self::Line)    => "|  ",
+            (TreeStyle::Ascii,   Self::Corner)  => "`--",
+
+            (TreeStyle::Heavy,   Self::Edge)    => "┣━━",
+            (TreeStyle::Heavy,   Self::Line)    => "┃  ",
+            (TreeStyle::Heavy,   Self::Corner)  => "┗━━",
+
+            (TreeStyle::Indent,  _) | (_, Self::Blank)  => "   ",
         }
     }
 }
 
 
+/// Which set of characters to draw the tree with.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum TreeStyle {
+
+    /// Unicode box-drawing lines, such as `├──` and `└──`.
+    #[default]
+    Unicode,
+
+    /// Box-drawing lines with a rounded corner, `╰──`.
+    Rounded,
+
+    /// Lines made only of ASCII characters, such as `|--` and `` `-- ``,
+    /// for terminals and fonts without the box-drawing ones.
+    Ascii,
+
+    /// No lines at all, with each level just indented further.
+    Indent,
+
+    /// Thicker box-drawing lines, such as `┣━━` and `┗━━`.
+    Heavy,
+}
+
+
 /// A **tree trunk** builds up arrays of tree parts over multiple depths.
 #[derive(Debug, Default)]
 pub struct TreeTrunk {
@@ -148,6 +184,12 @@ impl TreeParams {
     pub fn is_at_root(&self) -> bool {
         self.depth.0 == 0
     }
+
+    /// How many characters the tree parts before an entry take up,
+    /// including the space between them and the entry’s name.
+    pub fn width(&self) -> usize {
+        if self.is_at_root() { 0 } else { self.depth.0 * 4 + 1 }
+    }
 }
 
 impl TreeDepth {
@@ -246,6 +288,31 @@ mod trunk_test {
 }
 
 
+#[cfg(test)]
+mod style_test {
+    use super::*;
+
+    #[test]
+    fn rounded_corner() {
+        assert_eq!(TreePart::Corner.ascii_art(TreeStyle::Rounded), "╰──");
+        assert_eq!(TreePart::Edge.ascii_art(TreeStyle::Rounded),   "├──");
+    }
+
+    #[test]
+    fn real_ascii() {
+        assert!(TreePart::Edge.ascii_art(TreeStyle::Ascii).is_ascii());
+        assert!(TreePart::Line.ascii_art(TreeStyle::Ascii).is_ascii());
+        assert!(TreePart::Corner.ascii_art(TreeStyle::Ascii).is_ascii());
+    }
+
+    #[test]
+    fn indent_is_blank() {
+        assert_eq!(TreePart::Edge.ascii_art(TreeStyle::Indent), "   ");
+        assert_eq!(TreePart::Corner.ascii_art(TreeStyle::Indent), "   ");
+    }
+}
+
+
 #[cfg(test)]
 mod iter_test {
     use super::*;
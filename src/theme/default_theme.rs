@@ -6,6 +6,8 @@ use crate::theme::ui_styles::*;
 
 
 impl UiStyles {
+
+    /// The palette based on Visual Studio Code’s dark theme.
     pub fn default_theme(scale: ColourScale) -> Self {
         Self {
             colourful: true,
@@ -66,6 +68,11 @@ impl UiStyles {
                 conflicted:  Red.normal(),
             },
 
+            ages: Ages::colourful(),
+
+            tree_depths: [ Blue.normal(), Cyan.normal(), Green.normal(),
+                           Yellow.normal(), Purple.normal(), Red.normal() ],
+
             punctuation:  Fixed(244).normal(),
             date:         Blue.normal(),
             inode:        Purple.normal(),
@@ -79,14 +86,110 @@ impl UiStyles {
             broken_path_overlay:  Style::default().underline(),
         }
     }
+
+    /// The original palette from upstream exa.
+    pub fn exa_theme(scale: ColourScale) -> Self {
+        Self {
+            colourful: true,
+
+            filekinds: FileKinds {
+                normal:       Style::default(),
+                directory:    Blue.bold(),
+                symlink:      Cyan.normal(),
+                pipe:         Yellow.normal(),
+                block_device: Yellow.bold(),
+                char_device:  Yellow.bold(),
+                socket:       Red.bold(),
+                special:      Yellow.normal(),
+                executable:   Green.bold(),
+            },
+
+            perms: Permissions {
+                user_read:           Yellow.bold(),
+                user_write:          Red.bold(),
+                user_execute_file:   Green.bold().underline(),
+                user_execute_other:  Green.bold(),
+
+                group_read:          Yellow.normal(),
+                group_write:         Red.normal(),
+                group_execute:       Green.normal(),
+
+                other_read:          Yellow.normal(),
+                other_write:         Red.normal(),
+                other_execute:       Green.normal(),
+
+                special_user_file:   Purple.normal(),
+                special_other:       Purple.normal(),
+
+                attribute:           Style::default(),
+            },
+
+            size: Size::colourful(scale),
+
+            users: Users {
+                user_you:           Yellow.bold(),
+                user_someone_else:  Style::default(),
+                group_yours:        Yellow.bold(),
+                group_not_yours:    Style::default(),
+            },
+
+            links: Links {
+                normal:          Red.bold(),
+                multi_link_file: Red.on(Yellow),
+            },
+
+            git: Git {
+                new:         Green.normal(),
+                modified:    Blue.normal(),
+                deleted:     Red.normal(),
+                renamed:     Yellow.normal(),
+                typechange:  Purple.normal(),
+                ignored:     Style::default().dimmed(),
+                conflicted:  Red.normal(),
+            },
+
+            ages: Ages::colourful(),
+
+            tree_depths: [ Blue.normal(), Cyan.normal(), Green.normal(),
+                           Yellow.normal(), Purple.normal(), Red.normal() ],
+
+            punctuation:  Fixed(244).normal(),
+            date:         Blue.normal(),
+            inode:        Purple.normal(),
+            blocks:       Cyan.normal(),
+            octal:        Purple.normal(),
+            header:       Style::default().underline(),
+
+            symlink_path:         Cyan.normal(),
+            control_char:         Red.normal(),
+            broken_symlink:       Red.normal(),
+            broken_path_overlay:  Style::default().underline(),
+        }
+    }
+}
+
+
+impl Ages {
+    pub fn colourful() -> Self {
+        Self {
+            seconds: Cyan.bold(),
+            minutes: Cyan.normal(),
+            hours:   Blue.bold(),
+            days:    Blue.normal(),
+            months:  Fixed(244).normal(),
+            years:   Fixed(240).normal(),
+        }
+    }
 }
 
 
 impl Size {
     pub fn colourful(scale: ColourScale) -> Self {
-        match scale {
-            ColourScale::Gradient  => Self::colourful_gradient(),
-            ColourScale::Fixed     => Self::colourful_fixed(),
+        if scale.size {
+            Self::colourful_gradient()
+        }
+        else {
+            Self::colourful_fixed()
         }
     }
 
@@ -1,20 +1,26 @@
 use ansi_term::Style;
 
 use crate::fs::File;
+use crate::info::filetype::{builtin_rules, Rule, Ruleset};
 use crate::output::file_name::Colours as FileNameColours;
+use crate::output::icons::FileIcon;
 use crate::output::render;
+use crate::output::time::AgeUnit;
 
 mod ui_styles;
 pub use self::ui_styles::UiStyles;
 pub use self::ui_styles::Size as SizeColours;
 
 mod lsc;
-pub use self::lsc::LSColors;
+pub use self::lsc::{LSColors, Pair};
 
 mod default_theme;
 
+mod palette;
+pub use self::palette::{BasePalette, Palette, parse_style};
 
-#[derive(PartialEq, Eq, Debug)]
+
+#[derive(PartialEq, Debug)]
 pub struct Options {
 
     pub use_colours: UseColours,
@@ -22,6 +28,9 @@ pub struct Options {
     pub colour_scale: ColourScale,
 
     pub definitions: Definitions,
+
+    /// The colours to start from, picked with `--theme`.
+    pub palette: Palette,
 }
 
 /// Under what circumstances we should display coloured, rather than plain,
@@ -44,9 +53,47 @@ pub enum UseColours {
     Never,
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub enum ColourScale {
+/// Which columns get coloured on a scale, picked with `--color-scale`,
+/// rather than having every value in them painted the same way.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct ColourScale {
+
+    /// Whether file sizes get coloured by their order of magnitude.
+    pub size: bool,
+
+    /// Whether timestamps get shaded from bright to dim, depending on where
+    /// they fall between the newest and oldest ones in the listing.
+    pub age: bool,
+
+    /// How sizes get placed on their scale.
+    pub mode: ColourScaleMode,
+
+    /// Whether the terminal can show 24-bit colours, so colours on a scale
+    /// can be blended rather than picked from the 256-colour palette.
+    pub true_colour: bool,
+}
+
+impl ColourScale {
+
+    /// Whether sizes get coloured relative to the other sizes around them,
+    /// which means they have to be known before any can be shown.
+    pub fn relative_sizes(self) -> bool {
+        self.size && self.mode == ColourScaleMode::Gradient
+    }
+}
+
+/// How file sizes get placed on their scale, picked with
+/// `--color-scale-mode`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum ColourScaleMode {
+
+    /// Each order of magnitude gets its own colour, whatever else is in
+    /// the listing.
+    #[default]
     Fixed,
+
+    /// Sizes get coloured by where they fall between the smallest and the
+    /// largest in the listing, or in their directory in a tree.
     Gradient,
 }
 
@@ -60,33 +107,109 @@ pub struct Definitions {
 pub struct Theme {
     pub ui: UiStyles,
     pub exts: Box<dyn FileColours>,
+
+    /// Which columns get coloured on a scale.
+    pub scale: ColourScale,
+
+    /// The file type rules used to pick icons, which still get shown when
+    /// colours are turned off.
+    pub icons: Ruleset,
+}
+
+impl Theme {
+
+    /// A theme with every style set to the default, used when colours are
+    /// turned off or when the output should never contain escape codes.
+    pub fn plain() -> Self {
+        let ui = UiStyles::plain();
+        let exts = Box::new(NoFileColours);
+        let icons = Ruleset::builtin();
+        let scale = ColourScale::default();
+        Self { ui, exts, scale, icons }
+    }
+
+    /// The style for a timestamp that’s the given fraction of the way from
+    /// the oldest one in the listing (0.0) to the newest (1.0), going up the
+    /// greyscale ramp from dim to bright.
+    pub fn age_shade(&self, fraction: f64) -> Style {
+        const GREYS: &[u8] = &[ 240, 241, 242, 243, 244, 245, 246, 247, 248, 249, 250, 251, 252, 253, 254, 255 ];
+        self.scale_colour(fraction, (0x58, 0x58, 0x58), (0xee, 0xee, 0xee), GREYS)
+    }
+
+    /// The style for a size that’s the given fraction of the way from the
+    /// smallest one it gets compared with (0.0) to the largest (1.0), going
+    /// from green to orange-red.
+    pub fn size_shade(&self, fraction: f64) -> Style {
+        const STEPS: &[u8] = &[ 118, 154, 190, 226, 220, 214, 208, 202 ];
+        self.scale_colour(fraction, (0x87, 0xff, 0x00), (0xff, 0x5f, 0x00), STEPS)
+    }
+
+    /// Picks the colour the given fraction of the way along a scale. With
+    /// 24-bit colour, it gets blended between the two ends; otherwise, it’s
+    /// the nearest of the given steps from the 256-colour palette, which
+    /// should run between the same two ends.
+    fn scale_colour(&self, fraction: f64, from: (u8, u8, u8), to: (u8, u8, u8), steps: &[u8]) -> Style {
+        use ansi_term::Colour::{Fixed, RGB};
+
+        let fraction = fraction.clamp(0.0, 1.0);
+
+        if self.scale.true_colour {
+            let blend = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * fraction).round() as u8;
+            RGB(blend(from.0, to.0), blend(from.1, to.1), blend(from.2, to.2)).normal()
+        }
+        else {
+            let index = (fraction * (steps.len() - 1) as f64).round() as usize;
+            Fixed(steps[index]).normal()
+        }
+    }
 }
 
 impl Options {
 
+    /// Builds the theme, using the user’s own file type rules (if they have
+    /// any) in front of the built-in ones.
     #[allow(trivial_casts)]   // the `as Box<_>` stuff below warns about this for some reason
-    pub fn to_theme(&self, isatty: bool) -> Theme {
-        use crate::info::filetype::FileExtensions;
+    pub fn to_theme(&self, isatty: bool, rules: &[Rule]) -> Theme {
+        let icons = Ruleset::with_builtins(rules.to_vec());
 
         if self.use_colours == UseColours::Never || (self.use_colours == UseColours::Automatic && ! isatty) {
-            let ui = UiStyles::plain();
-            let exts = Box::new(NoFileColours);
-            return Theme { ui, exts };
+            return Theme { icons, .. Theme::plain() };
         }
 
         // Parse the environment variables into colours and extension mappings
-        let mut ui = UiStyles::default_theme(self.colour_scale);
+        let mut ui = self.palette.ui_styles(self.colour_scale);
         let (exts, use_default_filetypes) = self.definitions.parse_color_vars(&mut ui);
 
+        // Resetting the colours turns off the built-in rules, but not the
+        // user’s own ones. The palette can change the style of any of them.
+        let mut styled_rules = rules.to_vec();
+        if use_default_filetypes {
+            styled_rules.extend(builtin_rules());
+        }
+
+        for rule in &mut styled_rules {
+            self.palette.restyle(rule);
+        }
+
+        let rules = if styled_rules.is_empty() {
+            None
+        }
+        else if use_default_filetypes && rules.is_empty() && ! self.palette.restyles_rules() {
+            Some(icons.clone())
+        }
+        else {
+            Ruleset::new(styled_rules).ok()
+        };
+
         // Use between 0 and 2 file name highlighters
-        let exts = match (exts.is_non_empty(), use_default_filetypes) {
-            (false, false)  => Box::new(NoFileColours)           as Box<_>,
-            (false,  true)  => Box::new(FileExtensions)          as Box<_>,
-            ( true, false)  => Box::new(exts)                    as Box<_>,
-            ( true,  true)  => Box::new((exts, FileExtensions))  as Box<_>,
+        let exts = match (exts.is_non_empty(), rules) {
+            (false, None)         => Box::new(NoFileColours)   as Box<_>,
+            (false, Some(rules))  => Box::new(rules)           as Box<_>,
+            ( true, None)         => Box::new(exts)            as Box<_>,
+            ( true, Some(rules))  => Box::new((exts, rules))   as Box<_>,
         };
 
-        Theme { ui, exts }
+        Theme { ui, exts, scale: self.colour_scale, icons }
     }
 }
 
@@ -248,6 +371,21 @@ impl render::GroupColours for Theme {
     fn not_yours(&self)  -> Style { self.ui.users.group_not_yours }
 }
 
+impl render::TimeColours for Theme {
+    fn date(&self)  -> Style { self.ui.date }
+
+    fn age(&self, unit: AgeUnit) -> Style {
+        match unit {
+            AgeUnit::Seconds  => self.ui.ages.seconds,
+            AgeUnit::Minutes  => self.ui.ages.minutes,
+            AgeUnit::Hours    => self.ui.ages.hours,
+            AgeUnit::Days     => self.ui.ages.days,
+            AgeUnit::Months   => self.ui.ages.months,
+            AgeUnit::Years    => self.ui.ages.years,
+        }
+    }
+}
+
 impl render::LinksColours for Theme {
     fn normal(&self)           -> Style { self.ui.links.normal }
     fn multi_link_file(&self)  -> Style { self.ui.links.multi_link_file }
@@ -307,6 +445,12 @@ impl render::UserColours for Theme {
     fn someone_else(&self)  -> Style { self.ui.users.user_someone_else }
 }
 
+impl FileIcon for Theme {
+    fn icon_file(&self, file: &File<'_>) -> Option<char> {
+        self.icons.icon_file(file)
+    }
+}
+
 impl FileNameColours for Theme {
     fn normal_arrow(&self)        -> Style { self.ui.punctuation }
     fn broken_symlink(&self)      -> Style { self.ui.broken_symlink }
@@ -506,6 +650,16 @@ mod customs_test {
     test!(exa_gv:  ls "", exa "gv=38;5;126"  =>  colours c -> { c.git.renamed               = Fixed(126).normal(); });
     test!(exa_gt:  ls "", exa "gt=38;5;127"  =>  colours c -> { c.git.typechange            = Fixed(127).normal(); });
 
+    test!(exa_as:  ls "", exa "as=38;5;160"  =>  colours c -> { c.ages.seconds              = Fixed(160).normal(); });
+    test!(exa_am:  ls "", exa "am=38;5;161"  =>  colours c -> { c.ages.minutes              = Fixed(161).normal(); });
+    test!(exa_ah:  ls "", exa "ah=38;5;162"  =>  colours c -> { c.ages.hours                = Fixed(162).normal(); });
+    test!(exa_ad:  ls "", exa "ad=38;5;163"  =>  colours c -> { c.ages.days                 = Fixed(163).normal(); });
+    test!(exa_ao:  ls "", exa "ao=38;5;164"  =>  colours c -> { c.ages.months               = Fixed(164).normal(); });
+    test!(exa_ay:  ls "", exa "ay=38;5;165"  =>  colours c -> { c.ages.years                = Fixed(165).normal(); });
+
+    test!(exa_t1:  ls "", exa "t1=38;5;170"  =>  colours c -> { c.tree_depths[0]            = Fixed(170).normal(); });
+    test!(exa_t6:  ls "", exa "t6=38;5;171"  =>  colours c -> { c.tree_depths[5]            = Fixed(171).normal(); });
+
     test!(exa_xx:  ls "", exa "xx=38;5;128"  =>  colours c -> { c.punctuation               = Fixed(128).normal(); });
     test!(exa_da:  ls "", exa "da=38;5;129"  =>  colours c -> { c.date                      = Fixed(129).normal(); });
     test!(exa_in:  ls "", exa "in=38;5;130"  =>  colours c -> { c.inode                     = Fixed(130).normal(); });
@@ -544,3 +698,41 @@ mod customs_test {
     test!(ls_overwrite:  ls "pi=31:pi=32:pi=33", exa ""  =>  colours c -> { c.filekinds.pipe = Yellow.normal(); });
     test!(exa_overwrite: ls "", exa "da=36:da=35:da=34"  =>  colours c -> { c.date = Blue.normal(); });
 }
+
+
+#[cfg(test)]
+mod scale_test {
+    use super::*;
+    use ansi_term::Colour::*;
+
+    fn theme(true_colour: bool) -> Theme {
+        let scale = ColourScale { size: true, age: true, mode: ColourScaleMode::Gradient, true_colour };
+        Theme { scale, .. Theme::plain() }
+    }
+
+    #[test]
+    fn size_ends() {
+        assert_eq!(theme(false).size_shade(0.0), Fixed(118).normal());
+        assert_eq!(theme(false).size_shade(1.0), Fixed(202).normal());
+    }
+
+    #[test]
+    fn size_blended() {
+        assert_eq!(theme(true).size_shade(0.0), RGB(0x87, 0xff, 0x00).normal());
+        assert_eq!(theme(true).size_shade(0.5), RGB(0xc3, 0xaf, 0x00).normal());
+        assert_eq!(theme(true).size_shade(1.0), RGB(0xff, 0x5f, 0x00).normal());
+    }
+
+    #[test]
+    fn age_ends() {
+        assert_eq!(theme(false).age_shade(0.0), Fixed(240).normal());
+        assert_eq!(theme(false).age_shade(1.0), Fixed(255).normal());
+        assert_eq!(theme(true).age_shade(1.0), RGB(0xee, 0xee, 0xee).normal());
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(theme(false).size_shade(-1.0), Fixed(118).normal());
+        assert_eq!(theme(false).size_shade(2.0), Fixed(202).normal());
+    }
+}
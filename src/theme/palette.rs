@@ -0,0 +1,269 @@
+//! Named sets of colours, which can either be built in or read from a
+//! theme file.
+//!
+//! A palette starts from one of the built-in ones, then overrides any of the
+//! UI styles and the styles given to classes of file (see the `filetype`
+//! module for where the classes come from). The `LS_COLORS` and
+//! `EXA_COLORS` variables still get applied on top.
+
+use ansi_term::{Colour, Style};
+
+use crate::info::filetype::Rule;
+use crate::theme::ColourScale;
+use crate::theme::ui_styles::UiStyles;
+
+
+/// The colours to use, before any environment variables get applied.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Palette {
+
+    /// The built-in palette to start from.
+    pub base: BasePalette,
+
+    /// UI styles that override the base’s, as their section, their field
+    /// name, and the style.
+    pub ui: Vec<(String, String, Style)>,
+
+    /// Styles for classes of files that aren’t directories. A style of
+    /// `None` removes the class’s colour, so the file falls through to the
+    /// next matching rule.
+    pub files: Vec<(String, Option<Style>)>,
+
+    /// Styles for classes of directories.
+    pub dirs: Vec<(String, Option<Style>)>,
+}
+
+/// The palettes that come with exa.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum BasePalette {
+
+    /// The dark palette based on Visual Studio Code’s, which this fork of
+    /// exa uses by default.
+    #[default]
+    VsCode,
+
+    /// The original palette from upstream exa, which has fewer greys and
+    /// works better on light terminals.
+    Exa,
+}
+
+impl BasePalette {
+
+    /// Looks up a built-in palette by the name given to `--theme`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vscode"  => Some(Self::VsCode),
+            "exa"     => Some(Self::Exa),
+            _         => None,
+        }
+    }
+
+    fn ui_styles(self, scale: ColourScale) -> UiStyles {
+        match self {
+            Self::VsCode  => UiStyles::default_theme(scale),
+            Self::Exa     => UiStyles::exa_theme(scale),
+        }
+    }
+
+    /// The class styles that this palette changes from the ones in the
+    /// built-in rules, which use the VS Code palette.
+    fn class_styles(self, for_dirs: bool) -> Vec<(&'static str, Option<Style>)> {
+        use ansi_term::Colour::*;
+
+        match (self, for_dirs) {
+            (Self::VsCode, _) => {
+                Vec::new()
+            }
+            (Self::Exa, false) => vec![
+                ("immediate",   Some(Yellow.bold().underline())),
+                ("image",       Some(Fixed(133).normal())),
+                ("document",    Some(Fixed(105).normal())),
+                ("pretty_data", None),
+                ("binary",      None),
+                ("script",      None),
+                ("config",      None),
+                ("vim",         None),
+                ("language",    None),
+                ("file",        None),
+            ],
+            (Self::Exa, true) => vec![
+                ("config_folder",   None),
+                ("language_folder", None),
+                ("exe_folder",      None),
+                ("document_folder", None),
+                ("folder",          None),
+            ],
+        }
+    }
+}
+
+impl Palette {
+
+    /// The UI styles to use, before the environment variables get applied.
+    pub fn ui_styles(&self, scale: ColourScale) -> UiStyles {
+        let mut ui = self.base.ui_styles(scale);
+        for (section, field, style) in &self.ui {
+            ui.set_field(section, field, *style);
+        }
+        ui
+    }
+
+    /// Whether this palette changes the styles of any of the rules, which
+    /// means they have to be compiled again rather than using the built-in
+    /// ruleset as-is.
+    pub fn restyles_rules(&self) -> bool {
+        self.base != BasePalette::VsCode || ! self.files.is_empty() || ! self.dirs.is_empty()
+    }
+
+    /// Changes the style of a rule if this palette has one for its class.
+    pub fn restyle(&self, rule: &mut Rule) {
+        let for_dirs = rule.is_for_directories();
+        let own = if for_dirs { &self.dirs } else { &self.files };
+
+        let style = own.iter().rev()
+                       .find(|(class, _)| *class == rule.class)
+                       .map(|(_, style)| *style)
+                       .or_else(|| self.base.class_styles(for_dirs).into_iter()
+                                          .find(|(class, _)| *class == rule.class)
+                                          .map(|(_, style)| style));
+
+        if let Some(style) = style {
+            rule.style = style;
+        }
+    }
+}
+
+
+/// Parses a style written as space-separated words, such as
+/// `"bold #ff8700 on #1c1c1c"`. Colours can be hex RGB, a number from the
+/// 256-colour palette, or one of the eight colour names; the first one is
+/// the foreground, and one after `on` is the background. The word `none`
+/// gives no style at all, as opposed to `plain`, which is the default
+/// style. A string of numbers and semicolons, such as `"1;34"`, gets read
+/// as an `LS_COLORS` code instead.
+pub fn parse_style(input: &str) -> Result<Option<Style>, String> {
+    use crate::theme::lsc::Pair;
+
+    let input = input.trim();
+    if input == "none" {
+        return Ok(None);
+    }
+    else if ! input.is_empty() && input.chars().all(|c| c.is_ascii_digit() || c == ';') && input.contains(';') {
+        return Ok(Some(Pair { key: "", value: input }.to_style()));
+    }
+
+    let mut style = Style::default();
+    let mut background = false;
+
+    for word in input.split_whitespace() {
+        style = match word {
+            "plain"          => style,
+            "bold"           => style.bold(),
+            "dimmed"         => style.dimmed(),
+            "italic"         => style.italic(),
+            "underline"      => style.underline(),
+            "blink"          => style.blink(),
+            "reverse"        => style.reverse(),
+            "hidden"         => style.hidden(),
+            "strikethrough"  => style.strikethrough(),
+            "on"             => { background = true; continue; }
+            colour => {
+                let colour = parse_colour(colour).ok_or_else(|| format!("unknown style {:?}", colour))?;
+                if background { style.on(colour) } else { style.fg(colour) }
+            }
+        };
+
+        background = false;
+    }
+
+    if background {
+        return Err(String::from("expected a colour after ‘on’"));
+    }
+
+    Ok(Some(style))
+}
+
+fn parse_colour(word: &str) -> Option<Colour> {
+    use ansi_term::Colour::*;
+
+    if let Some(hex) = word.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i .. i + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(RGB(channel(0)?, channel(2)?, channel(4)?)),
+            _ => None,
+        };
+    }
+
+    match word {
+        "black"              => Some(Black),
+        "red"                => Some(Red),
+        "green"              => Some(Green),
+        "yellow"             => Some(Yellow),
+        "blue"               => Some(Blue),
+        "purple" | "magenta" => Some(Purple),
+        "cyan"               => Some(Cyan),
+        "white"              => Some(White),
+        number               => number.parse().ok().map(Fixed),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ansi_term::Colour::*;
+
+    #[test]
+    fn hex() {
+        assert_eq!(parse_style("#ff8700"), Ok(Some(RGB(0xff, 0x87, 0x00).normal())));
+    }
+
+    #[test]
+    fn background() {
+        assert_eq!(parse_style("bold #ffffff on #1c1c1c"), Ok(Some(RGB(255, 255, 255).on(RGB(28, 28, 28)).bold())));
+    }
+
+    #[test]
+    fn named_and_fixed() {
+        assert_eq!(parse_style("red on 244"), Ok(Some(Red.on(Fixed(244)))));
+    }
+
+    #[test]
+    fn ls_code() {
+        assert_eq!(parse_style("1;34"), Ok(Some(Blue.bold())));
+    }
+
+    #[test]
+    fn none_and_plain() {
+        assert_eq!(parse_style("none"), Ok(None));
+        assert_eq!(parse_style("plain"), Ok(Some(Style::default())));
+    }
+
+    #[test]
+    fn bad_hex() {
+        assert!(parse_style("#ff87").is_err());
+        assert!(parse_style("#gg8700").is_err());
+    }
+
+    #[test]
+    fn dangling_on() {
+        assert!(parse_style("red on").is_err());
+    }
+
+    #[test]
+    fn restyle_class() {
+        let palette = Palette { files: vec![ ("image".into(), Some(Red.normal())) ], .. Palette::default() };
+        let mut rule = Rule::new("image");
+        palette.restyle(&mut rule);
+        assert_eq!(rule.style, Some(Red.normal()));
+    }
+
+    #[test]
+    fn exa_palette_removes_classes() {
+        let palette = Palette { base: BasePalette::Exa, .. Palette::default() };
+        let mut rule = Rule::new("language");
+        rule.style = Some(Blue.normal());
+        palette.restyle(&mut rule);
+        assert_eq!(rule.style, None);
+    }
+}
@@ -13,6 +13,9 @@ pub struct UiStyles {
     pub users:      Users,
     pub links:      Links,
     pub git:        Git,
+    pub ages:       Ages,
+
+    pub tree_depths:  [Style; 6],
 
     pub punctuation:  Style,
     pub date:         Style,
@@ -104,6 +107,16 @@ pub struct Git {
     pub conflicted: Style,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ages {
+    pub seconds: Style,
+    pub minutes: Style,
+    pub hours: Style,
+    pub days: Style,
+    pub months: Style,
+    pub years: Style,
+}
+
 impl UiStyles {
     pub fn plain() -> Self {
         Self::default()
@@ -184,6 +197,20 @@ impl UiStyles {
             "gv" => self.git.renamed              = pair.to_style(),
             "gt" => self.git.typechange           = pair.to_style(),
 
+            "as" => self.ages.seconds             = pair.to_style(),
+            "am" => self.ages.minutes             = pair.to_style(),
+            "ah" => self.ages.hours               = pair.to_style(),
+            "ad" => self.ages.days                = pair.to_style(),
+            "ao" => self.ages.months              = pair.to_style(),
+            "ay" => self.ages.years               = pair.to_style(),
+
+            "t1" => self.tree_depths[0]           = pair.to_style(),
+            "t2" => self.tree_depths[1]           = pair.to_style(),
+            "t3" => self.tree_depths[2]           = pair.to_style(),
+            "t4" => self.tree_depths[3]           = pair.to_style(),
+            "t5" => self.tree_depths[4]           = pair.to_style(),
+            "t6" => self.tree_depths[5]           = pair.to_style(),
+
             "xx" => self.punctuation              = pair.to_style(),
             "da" => self.date                     = pair.to_style(),
             "in" => self.inode                    = pair.to_style(),
@@ -199,6 +226,96 @@ impl UiStyles {
         true
     }
 
+    /// Sets a value on this set of colours using the name of its section
+    /// and field, as they’re written in a theme file. Invalid names set
+    /// nothing, but return false.
+    pub fn set_field(&mut self, section: &str, field: &str, style: Style) -> bool {
+        let target = match (section, field) {
+            ("filekinds", "normal")        => &mut self.filekinds.normal,
+            ("filekinds", "directory")     => &mut self.filekinds.directory,
+            ("filekinds", "symlink")       => &mut self.filekinds.symlink,
+            ("filekinds", "pipe")          => &mut self.filekinds.pipe,
+            ("filekinds", "block_device")  => &mut self.filekinds.block_device,
+            ("filekinds", "char_device")   => &mut self.filekinds.char_device,
+            ("filekinds", "socket")        => &mut self.filekinds.socket,
+            ("filekinds", "special")       => &mut self.filekinds.special,
+            ("filekinds", "executable")    => &mut self.filekinds.executable,
+
+            ("perms", "user_read")           => &mut self.perms.user_read,
+            ("perms", "user_write")          => &mut self.perms.user_write,
+            ("perms", "user_execute_file")   => &mut self.perms.user_execute_file,
+            ("perms", "user_execute_other")  => &mut self.perms.user_execute_other,
+            ("perms", "group_read")          => &mut self.perms.group_read,
+            ("perms", "group_write")         => &mut self.perms.group_write,
+            ("perms", "group_execute")       => &mut self.perms.group_execute,
+            ("perms", "other_read")          => &mut self.perms.other_read,
+            ("perms", "other_write")         => &mut self.perms.other_write,
+            ("perms", "other_execute")       => &mut self.perms.other_execute,
+            ("perms", "special_user_file")   => &mut self.perms.special_user_file,
+            ("perms", "special_other")       => &mut self.perms.special_other,
+            ("perms", "attribute")           => &mut self.perms.attribute,
+
+            ("size", "major")        => &mut self.size.major,
+            ("size", "minor")        => &mut self.size.minor,
+            ("size", "number_byte")  => &mut self.size.number_byte,
+            ("size", "number_kilo")  => &mut self.size.number_kilo,
+            ("size", "number_mega")  => &mut self.size.number_mega,
+            ("size", "number_giga")  => &mut self.size.number_giga,
+            ("size", "number_huge")  => &mut self.size.number_huge,
+            ("size", "unit_byte")    => &mut self.size.unit_byte,
+            ("size", "unit_kilo")    => &mut self.size.unit_kilo,
+            ("size", "unit_mega")    => &mut self.size.unit_mega,
+            ("size", "unit_giga")    => &mut self.size.unit_giga,
+            ("size", "unit_huge")    => &mut self.size.unit_huge,
+
+            ("users", "user_you")           => &mut self.users.user_you,
+            ("users", "user_someone_else")  => &mut self.users.user_someone_else,
+            ("users", "group_yours")        => &mut self.users.group_yours,
+            ("users", "group_not_yours")    => &mut self.users.group_not_yours,
+
+            ("links", "normal")           => &mut self.links.normal,
+            ("links", "multi_link_file")  => &mut self.links.multi_link_file,
+
+            ("git", "new")         => &mut self.git.new,
+            ("git", "modified")    => &mut self.git.modified,
+            ("git", "deleted")     => &mut self.git.deleted,
+            ("git", "renamed")     => &mut self.git.renamed,
+            ("git", "typechange")  => &mut self.git.typechange,
+            ("git", "ignored")     => &mut self.git.ignored,
+            ("git", "conflicted")  => &mut self.git.conflicted,
+
+            ("ages", "seconds")  => &mut self.ages.seconds,
+            ("ages", "minutes")  => &mut self.ages.minutes,
+            ("ages", "hours")    => &mut self.ages.hours,
+            ("ages", "days")     => &mut self.ages.days,
+            ("ages", "months")   => &mut self.ages.months,
+            ("ages", "years")    => &mut self.ages.years,
+
+            ("tree", "depth_1")  => &mut self.tree_depths[0],
+            ("tree", "depth_2")  => &mut self.tree_depths[1],
+            ("tree", "depth_3")  => &mut self.tree_depths[2],
+            ("tree", "depth_4")  => &mut self.tree_depths[3],
+            ("tree", "depth_5")  => &mut self.tree_depths[4],
+            ("tree", "depth_6")  => &mut self.tree_depths[5],
+
+            ("ui", "punctuation")          => &mut self.punctuation,
+            ("ui", "date")                 => &mut self.date,
+            ("ui", "inode")                => &mut self.inode,
+            ("ui", "blocks")               => &mut self.blocks,
+            ("ui", "header")               => &mut self.header,
+            ("ui", "octal")                => &mut self.octal,
+            ("ui", "symlink_path")         => &mut self.symlink_path,
+            ("ui", "control_char")         => &mut self.control_char,
+            ("ui", "broken_symlink")       => &mut self.broken_symlink,
+            ("ui", "broken_path_overlay")  => &mut self.broken_path_overlay,
+
+            _ => return false,
+        };
+
+        *target = style;
+        true
+    }
+
     pub fn set_number_style(&mut self, style: Style) {
         self.size.number_byte = style;
         self.size.number_kilo = style;